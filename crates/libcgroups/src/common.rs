@@ -12,6 +12,7 @@ use oci_spec::runtime::{
     LinuxDevice, LinuxDeviceBuilder, LinuxDeviceCgroup, LinuxDeviceCgroupBuilder, LinuxDeviceType,
 };
 
+use super::events::{Events, EventsError};
 use super::stats::Stats;
 use super::{systemd, v1, v2};
 
@@ -51,6 +52,16 @@ pub trait CgroupManager {
 
     /// Gets the PIDs inside the cgroup
     fn get_all_pids(&self) -> Result<Vec<Pid>, Self::Error>;
+
+    /// Watches the cgroup for events like OOM kills. Managers which can't
+    /// watch their cgroup keep the default, which reports that events are
+    /// not supported.
+    fn events(&self) -> Result<Events, Self::Error>
+    where
+        Self::Error: From<EventsError>,
+    {
+        Err(EventsError::Unsupported.into())
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -61,6 +72,8 @@ pub enum AnyManagerError {
     V1(#[from] v1::manager::V1ManagerError),
     #[error(transparent)]
     V2(#[from] v2::manager::V2ManagerError),
+    #[error(transparent)]
+    Events(#[from] EventsError),
 }
 
 // systemd is boxed due to size lint https://rust-lang.github.io/rust-clippy/master/index.html#/large_enum_variant
@@ -120,6 +133,14 @@ impl CgroupManager for AnyCgroupManager {
             AnyCgroupManager::V2(m) => Ok(m.get_all_pids()?),
         }
     }
+
+    fn events(&self) -> Result<Events, Self::Error> {
+        match self {
+            AnyCgroupManager::Systemd(m) => Ok(m.events()?),
+            AnyCgroupManager::V1(m) => Ok(m.events()?),
+            AnyCgroupManager::V2(m) => Ok(m.events()?),
+        }
+    }
 }
 
 #[derive(Debug)]
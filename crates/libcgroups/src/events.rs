@@ -0,0 +1,244 @@
+//! Notifications about changes of a cgroup
+//!
+//! The kernel notifies about some changes of a cgroup, like an OOM kill, so
+//! they can be reacted to without polling the stats. On cgroup v2 the
+//! `memory.events` and `cgroup.events` files are watched with inotify, on
+//! cgroup v1 an eventfd is registered for `memory.oom_control` and the systemd
+//! manager additionally watches the properties of its unit over dbus.
+use std::collections::{HashMap, VecDeque};
+use std::io::ErrorKind;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use nix::errno::Errno;
+use nix::libc;
+use serde::Serialize;
+
+use crate::common::WrappedIoError;
+use crate::stats::{self, ParseFlatKeyedDataError};
+
+/// An event of a cgroup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CgroupEvent {
+    /// The memory usage of the cgroup reached its limit and an allocation
+    /// was about to fail
+    Oom,
+    /// A process of the cgroup was killed by the OOM killer
+    OomKill,
+    /// The memory usage went above `memory.high`, so the processes of the
+    /// cgroup were throttled
+    MemoryHigh,
+    /// The last process of the cgroup exited
+    Empty,
+    /// Freezing the cgroup completed
+    Frozen,
+    /// The systemd unit of the cgroup stopped
+    UnitStopped {
+        /// Whether the unit is in the failed state, e.g. after an OOM kill
+        failed: bool,
+    },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum EventsError {
+    #[error("io error: {0}")]
+    WrappedIo(#[from] WrappedIoError),
+    #[error(transparent)]
+    ParseFlatKeyedData(#[from] ParseFlatKeyedDataError),
+    #[error("failed to watch {path}: {err}")]
+    Watch { err: nix::Error, path: PathBuf },
+    #[error("failed to wait for events: {0}")]
+    Wait(nix::Error),
+    #[error("failed to read events: {0}")]
+    Read(nix::Error),
+    #[error("events are not supported by this cgroup manager")]
+    Unsupported,
+    #[cfg(feature = "systemd")]
+    #[error("systemd error: {0}")]
+    Systemd(#[from] crate::systemd::dbus_native::utils::SystemdClientError),
+}
+
+/// A file descriptor which becomes readable when there are events
+pub(crate) trait EventSource: AsRawFd {
+    /// Reads the events once the file descriptor is readable. Returns false
+    /// when there won't be any events anymore because the cgroup is gone.
+    fn read_events(&mut self, events: &mut VecDeque<CgroupEvent>) -> Result<bool, EventsError>;
+}
+
+/// The events of a cgroup, which ends once the cgroup is removed
+///
+/// Iterating blocks until the next event. [`Events::wait`] waits for a limited
+/// time, which allows to do other work in between, like collecting stats.
+pub struct Events {
+    sources: Vec<Box<dyn EventSource>>,
+    pending: VecDeque<CgroupEvent>,
+}
+
+impl Events {
+    pub(crate) fn new(sources: Vec<Box<dyn EventSource>>) -> Self {
+        Self {
+            sources,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Waits for the next event for at most `timeout`, or until there is one
+    /// if it is `None`. Returns `None` if no event happened in time or the
+    /// cgroup is gone, see [`Events::closed`].
+    pub fn wait(&mut self, timeout: Option<Duration>) -> Result<Option<CgroupEvent>, EventsError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            if self.closed() {
+                return Ok(None);
+            }
+
+            // poll waits forever with a negative timeout
+            let timeout = match deadline {
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX)
+                }
+                None => -1,
+            };
+            let ready: Vec<bool> = {
+                let mut fds: Vec<libc::pollfd> = self
+                    .sources
+                    .iter()
+                    .map(|source| poll_fd(source.as_raw_fd()))
+                    .collect();
+                // SAFETY: fds is valid for fds.len() entries and the file
+                // descriptors stay open while the sources exist
+                let res =
+                    unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout) };
+                match Errno::result(res) {
+                    Ok(0) => return Ok(None),
+                    Ok(_) => {}
+                    Err(Errno::EINTR) => continue,
+                    Err(err) => return Err(EventsError::Wait(err)),
+                }
+                // a hangup is reported even though it wasn't asked for, the
+                // source notices it when reading
+                fds.iter().map(|fd| fd.revents != 0).collect()
+            };
+
+            let mut open = true;
+            for (source, _) in self
+                .sources
+                .iter_mut()
+                .zip(ready)
+                .filter(|(_, ready)| *ready)
+            {
+                open &= source.read_events(&mut self.pending)?;
+            }
+            if !open {
+                // the pending events are still returned
+                self.sources.clear();
+            }
+        }
+    }
+
+    /// Returns true once the cgroup is gone and all its events were returned
+    pub fn closed(&self) -> bool {
+        self.sources.is_empty() && self.pending.is_empty()
+    }
+}
+
+impl Iterator for Events {
+    type Item = Result<CgroupEvent, EventsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.wait(None).transpose()
+    }
+}
+
+fn poll_fd(fd: RawFd) -> libc::pollfd {
+    libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    }
+}
+
+/// Reads a flat keyed file of a cgroup, returning `None` if the cgroup is gone
+pub(crate) fn read_flat_keyed(path: &Path) -> Result<Option<HashMap<String, u64>>, EventsError> {
+    match stats::parse_flat_keyed_data(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(ParseFlatKeyedDataError::WrappedIo(err))
+            if err.inner().kind() == ErrorKind::NotFound =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Tells whether the counter `key` went up
+pub(crate) fn increased(old: &HashMap<String, u64>, new: &HashMap<String, u64>, key: &str) -> bool {
+    new.get(key).copied().unwrap_or_default() > old.get(key).copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::io::IntoRawFd;
+
+    use nix::unistd;
+
+    use super::*;
+
+    /// A source that reports an event for every byte written to a pipe and
+    /// closes once the write end is closed
+    struct PipeSource(RawFd);
+
+    impl AsRawFd for PipeSource {
+        fn as_raw_fd(&self) -> RawFd {
+            self.0
+        }
+    }
+
+    impl Drop for PipeSource {
+        fn drop(&mut self) {
+            let _ = unistd::close(self.0);
+        }
+    }
+
+    impl EventSource for PipeSource {
+        fn read_events(&mut self, events: &mut VecDeque<CgroupEvent>) -> Result<bool, EventsError> {
+            let mut buf = [0u8; 16];
+            let len = unistd::read(self.0, &mut buf).map_err(EventsError::Read)?;
+            events.extend((0..len).map(|_| CgroupEvent::Oom));
+            Ok(len > 0)
+        }
+    }
+
+    #[test]
+    fn test_wait_for_events() -> anyhow::Result<()> {
+        let (read, write) = unistd::pipe()?;
+        let mut events = Events::new(vec![Box::new(PipeSource(read.into_raw_fd()))]);
+
+        assert_eq!(events.wait(Some(Duration::from_millis(10)))?, None);
+        assert!(!events.closed());
+
+        unistd::write(&write, b"xx")?;
+        drop(write);
+        assert_eq!(events.next().transpose()?, Some(CgroupEvent::Oom));
+        assert_eq!(events.next().transpose()?, Some(CgroupEvent::Oom));
+        assert_eq!(events.next().transpose()?, None);
+        assert!(events.closed());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_increased() {
+        let old = HashMap::from([("oom".to_owned(), 1), ("oom_kill".to_owned(), 1)]);
+        let new = HashMap::from([("oom".to_owned(), 2), ("oom_kill".to_owned(), 1)]);
+        assert!(increased(&old, &new, "oom"));
+        assert!(!increased(&old, &new, "oom_kill"));
+        assert!(!increased(&old, &new, "high"));
+    }
+}
@@ -87,10 +87,47 @@ pub struct MemoryStats {
     pub hierarchy: bool,
     /// Various memory statistics
     pub stats: HashMap<String, u64>,
+    /// Various memory statistics per NUMA node, in bytes
+    pub numa_stats: HashMap<u32, HashMap<String, u64>>,
+    /// How often the memory usage hit the boundaries of the cgroup
+    pub events: MemoryEvents,
+    /// How often the swap usage hit the boundaries of the cgroup (v2 only)
+    pub swap_events: SwapEvents,
     /// Pressure Stall Information
     pub psi: PSIStats,
 }
 
+/// Reports how often the memory usage of a cgroup hit its boundaries. On
+/// cgroup v1 only `max` and `oom_kill` are known.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct MemoryEvents {
+    /// Number of times the cgroup was reclaimed although its usage was below
+    /// the low boundary
+    pub low: u64,
+    /// Number of times the usage went above the high boundary, so the
+    /// processes were throttled
+    pub high: u64,
+    /// Number of times the usage was about to go above the limit
+    pub max: u64,
+    /// Number of times the OOM killer was invoked
+    pub oom: u64,
+    /// Number of processes killed by the OOM killer
+    pub oom_kill: u64,
+    /// Number of times the processes of the cgroup were killed as a group
+    pub oom_group_kill: u64,
+}
+
+/// Reports how often the swap usage of a cgroup hit its boundaries
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SwapEvents {
+    /// Number of times the usage went above the high boundary
+    pub high: u64,
+    /// Number of times the usage was about to go above the limit
+    pub max: u64,
+    /// Number of times a swap allocation failed
+    pub fail: u64,
+}
+
 /// Reports memory stats for one type of memory
 #[derive(Debug, Default, PartialEq, Eq, Serialize)]
 pub struct MemoryData {
@@ -326,6 +363,54 @@ pub(crate) fn parse_flat_keyed_data(
     Ok(stats)
 }
 
+/// Parses a `memory.numa_stat` file into the statistics of each NUMA node,
+/// multiplying the values by `unit` to get bytes. The lines are
+/// `<key> N0=<value> N1=<value>...` in bytes on cgroup v2 and
+/// `<key>=<total> N0=<value>...` in pages on cgroup v1. The file is missing
+/// if the kernel is built without NUMA support, which yields no statistics.
+pub(crate) fn parse_numa_stat(
+    file_path: &Path,
+    unit: u64,
+) -> Result<HashMap<u32, HashMap<String, u64>>, ParseFlatKeyedDataError> {
+    let mut stats: HashMap<u32, HashMap<String, u64>> = HashMap::new();
+    if !file_path.exists() {
+        return Ok(stats);
+    }
+
+    let numa_stat = common::read_cgroup_file(file_path)?;
+    for entry in numa_stat.lines() {
+        let mut entry_fields = entry.split_ascii_whitespace();
+        let key = match entry_fields.next() {
+            Some(field) => field.split('=').next().unwrap_or(field),
+            None => continue,
+        };
+
+        for field in entry_fields {
+            let (node, value) = field
+                .strip_prefix('N')
+                .and_then(|field| field.split_once('='))
+                .and_then(|(node, value)| Some((node.parse().ok()?, value)))
+                .ok_or_else(|| ParseFlatKeyedDataError::DoesNotConform {
+                    path: file_path.to_path_buf(),
+                })?;
+            let value: u64 =
+                value
+                    .parse()
+                    .map_err(|err| ParseFlatKeyedDataError::FailedToParse {
+                        value: value.into(),
+                        path: file_path.to_path_buf(),
+                        err,
+                    })?;
+            stats
+                .entry(node)
+                .or_default()
+                .insert(key.to_owned(), value * unit);
+        }
+    }
+
+    Ok(stats)
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ParseNestedKeyedDataError {
     #[error("io error: {0}")]
@@ -651,6 +736,44 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_numa_stat() {
+        let tmp = tempfile::tempdir().unwrap();
+        let v2 = ["anon N0=4096 N1=8192", "file N0=0 N1=4096"].join("\n");
+        let file_path = set_fixture(tmp.path(), "memory.numa_stat", &v2).unwrap();
+        let expected = HashMap::from([
+            (
+                0,
+                HashMap::from([("anon".to_owned(), 4096), ("file".to_owned(), 0)]),
+            ),
+            (
+                1,
+                HashMap::from([("anon".to_owned(), 8192), ("file".to_owned(), 4096)]),
+            ),
+        ]);
+        assert_eq!(parse_numa_stat(&file_path, 1).unwrap(), expected);
+
+        let v1 = ["anon=3 N0=1 N1=2", "file=1 N0=0 N1=1"].join("\n");
+        let file_path = set_fixture(tmp.path(), "memory.numa_stat", &v1).unwrap();
+        let expected = HashMap::from([
+            (
+                0,
+                HashMap::from([("anon".to_owned(), 4096), ("file".to_owned(), 0)]),
+            ),
+            (
+                1,
+                HashMap::from([("anon".to_owned(), 8192), ("file".to_owned(), 4096)]),
+            ),
+        ]);
+        assert_eq!(parse_numa_stat(&file_path, 4096).unwrap(), expected);
+
+        let file_path = set_fixture(tmp.path(), "memory.numa_stat", "anon Nx=1").unwrap();
+        assert!(parse_numa_stat(&file_path, 1).is_err());
+
+        let missing = tmp.path().join("missing");
+        assert!(parse_numa_stat(&missing, 1).unwrap().is_empty());
+    }
+
     #[test]
     fn test_parse_device_number() {
         let (major, minor) = parse_device_number("8:0").unwrap();
@@ -4,6 +4,8 @@ use crate::common::{AnyCgroupManager, CgroupManager};
 pub enum V1ManagerError {
     #[error("v1 cgroup feature is required, but was not enabled during compile time")]
     NotEnabled,
+    #[error(transparent)]
+    Events(#[from] crate::events::EventsError),
 }
 
 pub struct Manager {}
@@ -40,4 +42,8 @@ impl CgroupManager for Manager {
     fn get_all_pids(&self) -> Result<Vec<nix::unistd::Pid>, Self::Error> {
         Err(V1ManagerError::NotEnabled)
     }
+
+    fn events(&self) -> Result<crate::events::Events, Self::Error> {
+        Err(V1ManagerError::NotEnabled)
+    }
 }
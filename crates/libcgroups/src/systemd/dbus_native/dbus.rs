@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use std::io::{IoSlice, IoSliceMut};
-use std::os::fd::AsRawFd;
+use std::os::fd::{AsRawFd, RawFd};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicU32, Ordering};
 
@@ -23,7 +23,6 @@ const REPLY_BUF_SIZE: usize = 128; // seems good enough tradeoff between extra s
 // For more information see https://www.freedesktop.org/wiki/Software/systemd/dbus/
 pub struct DbusConnection {
     /// Is the socket system level or session specific
-    #[allow(dead_code)]
     system: bool,
     /// socket fd
     socket: i32,
@@ -311,20 +310,7 @@ impl DbusConnection {
         // we keep looping until we get either of these. see https://github.com/containers/youki/issues/2826
         // for more detailed analysis.
         loop {
-            let reply = self.receive_complete_response()?;
-
-            // note that a single received response can contain multiple
-            // messages, so we must deserialize it piece by piece
-            let mut buf = &reply[..];
-
-            while !buf.is_empty() {
-                let mut ctr = 0;
-                let msg = Message::deserialize(&buf[ctr..], &mut ctr)?;
-                // we reset the buf, because I couldn't figure out how the adjust_counter function
-                // should should be changed to work correctly with non-zero start counter, and this solved that issue
-                buf = &buf[ctr..];
-                ret.push(msg);
-            }
+            ret.extend(self.receive_messages()?);
 
             // in Youki, we only ever do method call apart from initial auth
             // in case it is, we don't really have a specific message to look
@@ -349,6 +335,52 @@ impl DbusConnection {
         Ok(ret)
     }
 
+    /// Receives the messages which are available on the connection, e.g.
+    /// signals. Returns no messages once the connection was closed.
+    pub fn receive_messages(&self) -> Result<Vec<Message>> {
+        let reply = self.receive_complete_response()?;
+
+        // note that a single received response can contain multiple
+        // messages, so we must deserialize it piece by piece
+        let mut buf = &reply[..];
+        let mut messages = Vec::new();
+
+        while !buf.is_empty() {
+            let mut ctr = 0;
+            let msg = Message::deserialize(&buf[ctr..], &mut ctr)?;
+            // we reset the buf, because I couldn't figure out how the adjust_counter function
+            // should should be changed to work correctly with non-zero start counter, and this solved that issue
+            buf = &buf[ctr..];
+            messages.push(msg);
+        }
+        Ok(messages)
+    }
+
+    /// Subscribes to the property changes of a unit and returns the object
+    /// path of the unit, which the signals are sent for. The signals can be
+    /// read with `receive_messages`, but note that method calls drop the
+    /// signals which arrive while they wait for their reply.
+    pub fn watch_unit(&self, unit_name: &str) -> Result<String> {
+        let mut proxy = self.create_proxy();
+        let unit_path = proxy.get_unit(unit_name)?;
+        // systemd only sends signals to its subscribers
+        proxy.subscribe()?;
+
+        let rule = format!(
+            "type='signal',sender='org.freedesktop.systemd1',path='{unit_path}',\
+             interface='org.freedesktop.DBus.Properties',member='PropertiesChanged'"
+        );
+        self.proxy("org.freedesktop.DBus", "/org/freedesktop/DBus")
+            .add_match(&rule)?;
+        Ok(unit_path)
+    }
+
+    /// Gets the active state of a unit, e.g. active or failed
+    pub fn unit_active_state(&self, unit_path: &str) -> Result<String> {
+        self.proxy("org.freedesktop.systemd1", unit_path)
+            .active_state()
+    }
+
     /// function to manage the message counter
     fn get_msg_id(&self) -> u32 {
         let old_ctr = self.msg_ctr.fetch_add(1, Ordering::SeqCst);
@@ -365,6 +397,12 @@ impl DbusConnection {
     }
 }
 
+impl AsRawFd for DbusConnection {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket
+    }
+}
+
 impl SystemdClient for DbusConnection {
     fn is_system(&self) -> bool {
         self.system
@@ -241,6 +241,30 @@ impl<'conn> Proxy<'conn> {
             v => panic!("control group expected string variant, got {:?} instead", v),
         }
     }
+    pub fn subscribe(&self) -> Result<()> {
+        self.method_call::<(), ()>("org.freedesktop.systemd1.Manager", "Subscribe", None)
+    }
+
+    pub fn add_match(&self, rule: &str) -> Result<()> {
+        self.method_call::<_, ()>("org.freedesktop.DBus", "AddMatch", Some(rule))
+    }
+
+    pub fn active_state(&self) -> Result<String> {
+        let t = self.method_call::<_, Variant>(
+            "org.freedesktop.DBus.Properties",
+            "Get",
+            Some(("org.freedesktop.systemd1.Unit", "ActiveState")),
+        )?;
+        match t {
+            Variant::String(s) => Ok(s),
+            v => Err(DbusError::DeserializationError(format!(
+                "active state expected string variant, got {:?} instead",
+                v
+            ))
+            .into()),
+        }
+    }
+
     pub fn attach_process(&self, name: &str, cgroup: &str, pid: u32) -> Result<()> {
         self.method_call::<_, ()>(
             "org.freedesktop.systemd1.Manager",
@@ -0,0 +1,72 @@
+use std::collections::VecDeque;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use super::dbus_native::dbus::DbusConnection;
+use super::dbus_native::message::{HeaderKind, HeaderValue, MessageType};
+use crate::events::{CgroupEvent, EventSource, EventsError};
+
+/// Watches the active state of a unit, which systemd signals a change of
+pub(crate) struct UnitWatcher {
+    /// A connection of its own, as method calls drop the signals
+    client: DbusConnection,
+    unit_path: String,
+    active_state: String,
+}
+
+impl UnitWatcher {
+    pub fn new(client: DbusConnection, unit_name: &str) -> Result<Self, EventsError> {
+        let unit_path = client.watch_unit(unit_name)?;
+        let active_state = client.unit_active_state(&unit_path)?;
+
+        Ok(Self {
+            client,
+            unit_path,
+            active_state,
+        })
+    }
+}
+
+impl AsRawFd for UnitWatcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.client.as_raw_fd()
+    }
+}
+
+impl EventSource for UnitWatcher {
+    fn read_events(&mut self, events: &mut VecDeque<CgroupEvent>) -> Result<bool, EventsError> {
+        let messages = self.client.receive_messages()?;
+        if messages.is_empty() {
+            return Ok(false);
+        }
+
+        let unit_path = HeaderValue::String(self.unit_path.clone());
+        let changed = messages.iter().any(|message| {
+            message.preamble.mtype == MessageType::Signal
+                && message
+                    .headers
+                    .iter()
+                    .any(|header| header.kind == HeaderKind::Path && header.value == unit_path)
+        });
+        if !changed {
+            return Ok(true);
+        }
+
+        // the properties are read instead of parsing the signal, so it doesn't
+        // matter that signals get lost while waiting for the reply. A stopped
+        // transient unit is unloaded and can't be asked anymore.
+        let active_state = self
+            .client
+            .unit_active_state(&self.unit_path)
+            .unwrap_or_else(|_| "inactive".to_owned());
+        if active_state != self.active_state
+            && matches!(active_state.as_str(), "inactive" | "failed")
+        {
+            events.push_back(CgroupEvent::UnitStopped {
+                failed: active_state == "failed",
+            });
+        }
+        self.active_state = active_state;
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+use oci_spec::runtime::LinuxBlockIo;
+
+use super::controller::Controller;
+use super::dbus_native::serialize::Variant;
+use crate::common::ControllerOpt;
+
+pub const IO_WEIGHT: &str = "IOWeight";
+
+pub struct Io {}
+
+impl Controller for Io {
+    type Error = Infallible;
+
+    fn apply(
+        options: &ControllerOpt,
+        _: u32,
+        properties: &mut HashMap<&str, Variant>,
+    ) -> Result<(), Self::Error> {
+        if let Some(blkio) = options.resources.block_io() {
+            tracing::debug!("Applying io resource restrictions");
+            Self::apply(blkio, properties);
+        }
+
+        Ok(())
+    }
+}
+
+impl Io {
+    fn apply(blkio: &LinuxBlockIo, properties: &mut HashMap<&str, Variant>) {
+        if let Some(weight) = blkio.weight() {
+            if weight > 0 {
+                properties.insert(
+                    IO_WEIGHT,
+                    Variant::U64(Self::convert_blkio_weight_to_io(weight)),
+                );
+            }
+        }
+    }
+
+    // The OCI spec uses the cgroup v1 blkio weight range of [10-1000] while
+    // systemd expects the cgroup v2 io weight range of [1-10000], the same
+    // conversion as in the v2 io controller is used.
+    fn convert_blkio_weight_to_io(weight: u16) -> u64 {
+        1 + (weight.saturating_sub(10) as u64) * 9999 / 990
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{anyhow, Context, Result};
+    use oci_spec::runtime::{LinuxBlockIoBuilder, LinuxResourcesBuilder};
+
+    use super::super::dbus_native::serialize::DbusSerialize;
+    use super::*;
+    use crate::recast;
+
+    #[test]
+    fn test_io_weight() -> Result<()> {
+        for (weight, expected) in [(10u16, 1u64), (500, 4950), (1000, 10000)] {
+            let resources = LinuxResourcesBuilder::default()
+                .block_io(LinuxBlockIoBuilder::default().weight(weight).build()?)
+                .build()?;
+            let options = ControllerOpt {
+                resources: &resources,
+                disable_oom_killer: false,
+                oom_score_adj: None,
+                freezer_state: None,
+            };
+            let mut properties = HashMap::new();
+
+            <Io as Controller>::apply(&options, 245, &mut properties)
+                .map_err(|err| anyhow!(err))
+                .context("apply io")?;
+
+            let io_weight = properties.get(IO_WEIGHT).unwrap();
+            let val = recast!(io_weight, Variant)?;
+            assert_eq!(val, Variant::U64(expected));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_io_no_weight() -> Result<()> {
+        let resources = LinuxResourcesBuilder::default()
+            .block_io(LinuxBlockIoBuilder::default().build()?)
+            .build()?;
+        let options = ControllerOpt {
+            resources: &resources,
+            disable_oom_killer: false,
+            oom_score_adj: None,
+            freezer_state: None,
+        };
+        let mut properties = HashMap::new();
+
+        <Io as Controller>::apply(&options, 245, &mut properties)
+            .map_err(|err| anyhow!(err))
+            .context("apply io")?;
+        assert!(properties.is_empty());
+
+        Ok(())
+    }
+}
@@ -15,15 +15,19 @@ use super::cpuset::CpuSet;
 use super::dbus_native::client::SystemdClient;
 use super::dbus_native::dbus::DbusConnection;
 use super::dbus_native::utils::SystemdClientError;
+use super::events::UnitWatcher;
+use super::io::Io;
 use super::memory::Memory;
 use super::pids::Pids;
 use crate::common::{
     self, AnyCgroupManager, CgroupManager, ControllerOpt, FreezerState, JoinSafelyError,
     PathBufExt, WrapIoResult, WrappedIoError,
 };
+use crate::events::{Events, EventsError};
 use crate::stats::Stats;
 use crate::systemd::dbus_native::serialize::Variant;
 use crate::systemd::unified::Unified;
+use crate::v2::events::EventWatcher;
 use crate::v2::manager::{Manager as FsManager, V2ManagerError};
 
 const CGROUP_CONTROLLERS: &str = "cgroup.controllers";
@@ -155,6 +159,8 @@ pub enum SystemdManagerError {
     BadDelegationBoundary { boundary: PathBuf, cgroup: PathBuf },
     #[error("in v2 manager: {0}")]
     V2Manager(#[from] V2ManagerError),
+    #[error(transparent)]
+    Events(#[from] EventsError),
 
     #[error("in cpu controller: {0}")]
     Cpu(#[from] super::cpu::SystemdCpuError),
@@ -162,6 +168,8 @@ pub enum SystemdManagerError {
     CpuSet(#[from] super::cpuset::SystemdCpuSetError),
     #[error("in memory controller: {0}")]
     Memory(#[from] super::memory::SystemdMemoryError),
+    #[error("in io controller: {0}")]
+    Io(Infallible),
     #[error("in pids controller: {0}")]
     Pids(Infallible),
     #[error("in pids unified controller: {0}")]
@@ -383,6 +391,11 @@ impl CgroupManager for Manager {
                     CpuSet::apply(controller_opt, systemd_version, &mut properties)?;
                 }
 
+                ControllerType::Io => {
+                    Io::apply(controller_opt, systemd_version, &mut properties)
+                        .map_err(SystemdManagerError::Io)?;
+                }
+
                 ControllerType::Pids => {
                     Pids::apply(controller_opt, systemd_version, &mut properties)
                         .map_err(SystemdManagerError::Pids)?;
@@ -390,7 +403,6 @@ impl CgroupManager for Manager {
                 ControllerType::Memory => {
                     Memory::apply(controller_opt, systemd_version, &mut properties)?;
                 }
-                _ => {}
             };
         }
 
@@ -427,6 +439,17 @@ impl CgroupManager for Manager {
     fn get_all_pids(&self) -> Result<Vec<Pid>, Self::Error> {
         Ok(common::get_all_pids(&self.full_path)?)
     }
+
+    fn events(&self) -> Result<Events, Self::Error> {
+        let client = match self.client.is_system() {
+            true => DbusConnection::new_system()?,
+            false => DbusConnection::new_session()?,
+        };
+        let unit = UnitWatcher::new(client, &self.unit_name)?;
+        let cgroup = EventWatcher::new(&self.full_path)?;
+
+        Ok(Events::new(vec![Box::new(cgroup), Box::new(unit)]))
+    }
 }
 
 #[cfg(test)]
@@ -4,7 +4,9 @@ mod controller;
 pub mod controller_type;
 mod cpu;
 mod cpuset;
-mod dbus_native;
+pub(crate) mod dbus_native;
+mod events;
+mod io;
 pub mod manager;
 mod memory;
 mod pids;
@@ -4,6 +4,7 @@ use std::convert::Infallible;
 use nix::unistd::Pid;
 
 use crate::common::{CgroupManager, ControllerOpt, FreezerState};
+use crate::events::Events;
 use crate::stats::Stats;
 
 #[derive(Debug)]
@@ -50,6 +51,10 @@ impl CgroupManager for TestManager {
     fn get_all_pids(&self) -> Result<Vec<Pid>, Infallible> {
         unimplemented!()
     }
+
+    fn events(&self) -> Result<Events, Infallible> {
+        unimplemented!()
+    }
 }
 
 impl TestManager {
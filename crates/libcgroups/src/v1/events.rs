@@ -0,0 +1,123 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+
+use nix::errno::Errno;
+use nix::sys::eventfd::{EfdFlags, EventFd};
+
+use crate::common::{self, WrapIoResult};
+use crate::events::{self, CgroupEvent, EventSource, EventsError};
+
+const CGROUP_EVENT_CONTROL: &str = "cgroup.event_control";
+const MEMORY_OOM_CONTROL: &str = "memory.oom_control";
+
+/// Registers an eventfd for the OOM notifications of a memory cgroup. The
+/// kernel signals the eventfd on every OOM and once more when the cgroup is
+/// removed. v1 has no notifications for the other events.
+pub(crate) struct OomWatcher {
+    eventfd: EventFd,
+    path: PathBuf,
+    oom_control: HashMap<String, u64>,
+}
+
+impl OomWatcher {
+    pub fn new(memory_path: &Path) -> Result<Self, EventsError> {
+        let oom_control_path = memory_path.join(MEMORY_OOM_CONTROL);
+        let oom_control = File::open(&oom_control_path).wrap_open(&oom_control_path)?;
+        let eventfd =
+            EventFd::from_flags(EfdFlags::EFD_CLOEXEC | EfdFlags::EFD_NONBLOCK).map_err(|err| {
+                EventsError::Watch {
+                    err,
+                    path: oom_control_path.clone(),
+                }
+            })?;
+        // the kernel keeps a reference to the eventfd, the control file isn't
+        // needed anymore once the eventfd is registered
+        common::write_cgroup_file(
+            memory_path.join(CGROUP_EVENT_CONTROL),
+            format!("{} {}", eventfd.as_raw_fd(), oom_control.as_raw_fd()),
+        )?;
+
+        Ok(Self {
+            eventfd,
+            path: memory_path.to_owned(),
+            oom_control: events::read_flat_keyed(&oom_control_path)?.unwrap_or_default(),
+        })
+    }
+}
+
+impl AsRawFd for OomWatcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.eventfd.as_raw_fd()
+    }
+}
+
+impl EventSource for OomWatcher {
+    fn read_events(&mut self, events: &mut VecDeque<CgroupEvent>) -> Result<bool, EventsError> {
+        match self.eventfd.read() {
+            Ok(_) => {}
+            Err(Errno::EAGAIN) => return Ok(true),
+            Err(err) => return Err(EventsError::Read(err)),
+        }
+
+        let current = match events::read_flat_keyed(&self.path.join(MEMORY_OOM_CONTROL))? {
+            Some(current) => current,
+            None => return Ok(false),
+        };
+        events.push_back(CgroupEvent::Oom);
+        // oom_kill is only there since linux 4.13
+        if events::increased(&self.oom_control, &current, "oom_kill") {
+            events.push_back(CgroupEvent::OomKill);
+        }
+        self.oom_control = current;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::events::Events;
+    use crate::test::set_fixture;
+
+    #[test]
+    fn test_oom_events() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        set_fixture(tmp.path(), CGROUP_EVENT_CONTROL, "")?;
+        set_fixture(
+            tmp.path(),
+            MEMORY_OOM_CONTROL,
+            "oom_kill_disable 0\nunder_oom 0\noom_kill 0\n",
+        )?;
+        let watcher = OomWatcher::new(tmp.path())?;
+        let registration = fs::read_to_string(tmp.path().join(CGROUP_EVENT_CONTROL))?;
+        assert!(registration.starts_with(&format!("{} ", watcher.eventfd.as_raw_fd())));
+
+        // what the kernel does on an OOM kill
+        set_fixture(
+            tmp.path(),
+            MEMORY_OOM_CONTROL,
+            "oom_kill_disable 0\nunder_oom 0\noom_kill 1\n",
+        )?;
+        watcher.eventfd.write(1)?;
+        let eventfd = watcher.eventfd.as_raw_fd();
+        let mut events = Events::new(vec![Box::new(watcher)]);
+        let timeout = Some(Duration::from_secs(5));
+        assert_eq!(events.wait(timeout)?, Some(CgroupEvent::Oom));
+        assert_eq!(events.wait(timeout)?, Some(CgroupEvent::OomKill));
+
+        // and when the cgroup is removed
+        fs::remove_file(tmp.path().join(MEMORY_OOM_CONTROL))?;
+        // SAFETY: the eventfd is still open, it's owned by the events
+        Errno::result(unsafe { nix::libc::eventfd_write(eventfd, 1) })?;
+        assert_eq!(events.wait(timeout)?, None);
+        assert!(events.closed());
+
+        Ok(())
+    }
+}
@@ -14,6 +14,7 @@ use super::cpu::{Cpu, V1CpuStatsError};
 use super::cpuacct::{CpuAcct, V1CpuAcctStatsError};
 use super::cpuset::{CpuSet, V1CpuSetControllerError};
 use super::devices::Devices;
+use super::events::OomWatcher;
 use super::freezer::{Freezer, V1FreezerControllerError};
 use super::hugetlb::{HugeTlb, V1HugeTlbControllerError, V1HugeTlbStatsError};
 use super::memory::{Memory, V1MemoryControllerError, V1MemoryStatsError};
@@ -27,6 +28,7 @@ use crate::common::{
     self, AnyCgroupManager, CgroupManager, ControllerOpt, FreezerState, JoinSafelyError,
     PathBufExt, WrapIoResult, WrappedIoError, CGROUP_PROCS,
 };
+use crate::events::{Events, EventsError};
 use crate::stats::{PidStatsError, Stats, StatsProvider};
 
 pub struct Manager {
@@ -77,6 +79,9 @@ pub enum V1ManagerError {
     HugeTlbStats(#[from] V1HugeTlbStatsError),
     #[error(transparent)]
     MemoryStats(#[from] V1MemoryStatsError),
+
+    #[error(transparent)]
+    Events(#[from] EventsError),
 }
 
 impl Manager {
@@ -269,4 +274,14 @@ impl CgroupManager for Manager {
 
         Ok(stats)
     }
+
+    fn events(&self) -> Result<Events, Self::Error> {
+        // v1 only notifies about OOMs, which need the memory cgroup
+        let memory_path = self
+            .subsystems
+            .get(&CtrlType::Memory)
+            .ok_or(V1ManagerError::CGroupRequired(CtrlType::Memory))?;
+        let watcher = OomWatcher::new(memory_path)?;
+        Ok(Events::new(vec![Box::new(watcher)]))
+    }
 }
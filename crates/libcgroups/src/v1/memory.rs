@@ -12,7 +12,8 @@ use oci_spec::runtime::LinuxMemory;
 use super::controller::Controller;
 use crate::common::{self, ControllerOpt, WrapIoResult, WrappedIoError};
 use crate::stats::{
-    self, parse_single_value, MemoryData, MemoryStats, ParseFlatKeyedDataError, StatsProvider,
+    self, parse_single_value, MemoryData, MemoryEvents, MemoryStats, ParseFlatKeyedDataError,
+    StatsProvider,
 };
 
 const CGROUP_MEMORY_SWAP_LIMIT: &str = "memory.memsw.limit_in_bytes";
@@ -22,6 +23,7 @@ const CGROUP_MEMORY_MAX_USAGE: &str = "memory.max_usage_in_bytes";
 const CGROUP_MEMORY_SWAPPINESS: &str = "memory.swappiness";
 const CGROUP_MEMORY_RESERVATION: &str = "memory.soft_limit_in_bytes";
 const CGROUP_MEMORY_OOM_CONTROL: &str = "memory.oom_control";
+const CGROUP_MEMORY_NUMA_STAT: &str = "memory.numa_stat";
 
 const CGROUP_KERNEL_MEMORY_LIMIT: &str = "memory.kmem.limit_in_bytes";
 const CGROUP_KERNEL_TCP_MEMORY_LIMIT: &str = "memory.kmem.tcp.limit_in_bytes";
@@ -171,6 +173,17 @@ impl StatsProvider for Memory {
         let kernel_tcp = Self::get_memory_data(cgroup_path, MEMORY_KERNEL_TCP_PREFIX)?;
         let hierarchy = Self::hierarchy_enabled(cgroup_path)?;
         let stats = Self::get_stat_data(cgroup_path)?;
+        // the values of memory.numa_stat are in pages on cgroup v1
+        let numa_stats = stats::parse_numa_stat(
+            &cgroup_path.join(CGROUP_MEMORY_NUMA_STAT),
+            procfs::page_size(),
+        )?;
+        let events = MemoryEvents {
+            // the number of times the limit was hit, as on v2
+            max: memory.fail_count,
+            oom_kill: Self::get_oom_kill_count(cgroup_path),
+            ..Default::default()
+        };
 
         Ok(MemoryStats {
             memory,
@@ -180,6 +193,8 @@ impl StatsProvider for Memory {
             cache: stats["cache"],
             hierarchy,
             stats,
+            numa_stats,
+            events,
             ..Default::default()
         })
     }
@@ -220,6 +235,14 @@ impl Memory {
         stats::parse_flat_keyed_data(&cgroup_path.join(MEMORY_STAT))
     }
 
+    fn get_oom_kill_count(cgroup_path: &Path) -> u64 {
+        // oom_kill is only reported by memory.oom_control since Linux 4.13
+        stats::parse_flat_keyed_data(&cgroup_path.join(CGROUP_MEMORY_OOM_CONTROL))
+            .ok()
+            .and_then(|oom_control| oom_control.get("oom_kill").copied())
+            .unwrap_or_default()
+    }
+
     fn get_memory_usage(cgroup_root: &Path) -> Result<u64, V1MemoryControllerError> {
         let path = cgroup_root.join(CGROUP_MEMORY_USAGE);
         let mut contents = String::new();
@@ -714,4 +737,18 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_stat_oom_kill_count() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(Memory::get_oom_kill_count(tmp.path()), 0);
+
+        set_fixture(
+            tmp.path(),
+            CGROUP_MEMORY_OOM_CONTROL,
+            "oom_kill_disable 0\nunder_oom 0\noom_kill 2\n",
+        )
+        .unwrap();
+        assert_eq!(Memory::get_oom_kill_count(tmp.path()), 2);
+    }
 }
@@ -5,6 +5,7 @@ mod cpu;
 mod cpuacct;
 mod cpuset;
 mod devices;
+mod events;
 mod freezer;
 mod hugetlb;
 pub mod manager;
@@ -14,5 +15,5 @@ mod network_priority;
 pub mod perf_event;
 mod pids;
 pub mod util;
-pub use controller_type::ControllerType;
+pub use controller_type::{ControllerType, CONTROLLERS};
 pub use manager::Manager;
@@ -0,0 +1,203 @@
+use std::collections::{HashMap, VecDeque};
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::path::{Path, PathBuf};
+
+use nix::errno::Errno;
+use nix::libc;
+use nix::sys::inotify::{AddWatchFlags, Inotify, WatchDescriptor};
+
+use crate::events::{self, CgroupEvent, EventSource, EventsError};
+
+const CGROUP_EVENTS: &str = "cgroup.events";
+const MEMORY_EVENTS: &str = "memory.events";
+
+/// Watches `cgroup.events` and `memory.events` of a cgroup. The kernel
+/// signals a modification of these files whenever a value in them changes,
+/// so the events are found by comparing the values with the previous ones.
+pub(crate) struct EventWatcher {
+    inotify: Inotify,
+    /// The file descriptor of `inotify`, which it doesn't expose as a raw one
+    fd: RawFd,
+    path: PathBuf,
+    memory_watch: Option<WatchDescriptor>,
+    cgroup_events: HashMap<String, u64>,
+    memory_events: HashMap<String, u64>,
+}
+
+impl EventWatcher {
+    pub fn new(path: &Path) -> Result<Self, EventsError> {
+        // SAFETY: inotify_init1 has no preconditions
+        let fd =
+            Errno::result(unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) })
+                .map_err(|err| EventsError::Watch {
+                    err,
+                    path: path.to_owned(),
+                })?;
+        // SAFETY: the file descriptor was just created and is owned by
+        // nothing else
+        let inotify = unsafe { Inotify::from_raw_fd(fd) };
+        let watch = |file: &Path| {
+            inotify
+                .add_watch(file, AddWatchFlags::IN_MODIFY)
+                .map_err(|err| EventsError::Watch {
+                    err,
+                    path: file.to_owned(),
+                })
+        };
+
+        watch(&path.join(CGROUP_EVENTS))?;
+        // the memory controller might not be enabled for the cgroup
+        let memory_events_path = path.join(MEMORY_EVENTS);
+        let memory_watch = if memory_events_path.exists() {
+            Some(watch(&memory_events_path)?)
+        } else {
+            None
+        };
+
+        let mut watcher = Self {
+            inotify,
+            fd,
+            path: path.to_owned(),
+            memory_watch,
+            cgroup_events: HashMap::new(),
+            memory_events: HashMap::new(),
+        };
+        // only changes from now on are events
+        let mut events = VecDeque::new();
+        watcher.read_cgroup_events(&mut events)?;
+        if watcher.memory_watch.is_some() {
+            watcher.read_memory_events(&mut events)?;
+        }
+
+        Ok(watcher)
+    }
+
+    fn read_cgroup_events(
+        &mut self,
+        events: &mut VecDeque<CgroupEvent>,
+    ) -> Result<bool, EventsError> {
+        let current = match events::read_flat_keyed(&self.path.join(CGROUP_EVENTS))? {
+            Some(current) => current,
+            None => return Ok(false),
+        };
+
+        if became(&self.cgroup_events, &current, "populated", 0) {
+            events.push_back(CgroupEvent::Empty);
+        }
+        if became(&self.cgroup_events, &current, "frozen", 1) {
+            events.push_back(CgroupEvent::Frozen);
+        }
+        self.cgroup_events = current;
+
+        Ok(true)
+    }
+
+    fn read_memory_events(
+        &mut self,
+        events: &mut VecDeque<CgroupEvent>,
+    ) -> Result<bool, EventsError> {
+        let current = match events::read_flat_keyed(&self.path.join(MEMORY_EVENTS))? {
+            Some(current) => current,
+            None => return Ok(false),
+        };
+
+        if !self.memory_events.is_empty() {
+            for (key, event) in [
+                ("high", CgroupEvent::MemoryHigh),
+                ("oom", CgroupEvent::Oom),
+                ("oom_kill", CgroupEvent::OomKill),
+            ] {
+                if events::increased(&self.memory_events, &current, key) {
+                    events.push_back(event);
+                }
+            }
+        }
+        self.memory_events = current;
+
+        Ok(true)
+    }
+}
+
+impl AsRawFd for EventWatcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl EventSource for EventWatcher {
+    fn read_events(&mut self, events: &mut VecDeque<CgroupEvent>) -> Result<bool, EventsError> {
+        let notifications = match self.inotify.read_events() {
+            Ok(notifications) => notifications,
+            Err(Errno::EAGAIN) => return Ok(true),
+            Err(err) => return Err(EventsError::Read(err)),
+        };
+
+        let mut open = true;
+        for notification in notifications {
+            // the watch is removed along with the cgroup
+            if notification.mask.contains(AddWatchFlags::IN_IGNORED) {
+                open = false;
+            } else if Some(notification.wd) == self.memory_watch {
+                open &= self.read_memory_events(events)?;
+            } else {
+                open &= self.read_cgroup_events(events)?;
+            }
+        }
+
+        Ok(open)
+    }
+}
+
+/// Tells whether the value of `key` changed to `value`
+fn became(old: &HashMap<String, u64>, new: &HashMap<String, u64>, key: &str, value: u64) -> bool {
+    new.get(key) == Some(&value) && old.get(key) != Some(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::events::Events;
+    use crate::test::set_fixture;
+
+    #[test]
+    fn test_watch_events() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        set_fixture(tmp.path(), CGROUP_EVENTS, "populated 1\nfrozen 0\n")?;
+        set_fixture(
+            tmp.path(),
+            MEMORY_EVENTS,
+            "low 0\nhigh 0\nmax 0\noom 0\noom_kill 0\n",
+        )?;
+        let watcher = EventWatcher::new(tmp.path())?;
+        let mut events = Events::new(vec![Box::new(watcher)]);
+        let timeout = Some(Duration::from_secs(5));
+
+        set_fixture(
+            tmp.path(),
+            MEMORY_EVENTS,
+            "low 0\nhigh 3\nmax 1\noom 1\noom_kill 1\n",
+        )?;
+        assert_eq!(events.wait(timeout)?, Some(CgroupEvent::MemoryHigh));
+        assert_eq!(events.wait(timeout)?, Some(CgroupEvent::Oom));
+        assert_eq!(events.wait(timeout)?, Some(CgroupEvent::OomKill));
+
+        set_fixture(tmp.path(), CGROUP_EVENTS, "populated 1\nfrozen 1\n")?;
+        assert_eq!(events.wait(timeout)?, Some(CgroupEvent::Frozen));
+        set_fixture(tmp.path(), CGROUP_EVENTS, "populated 0\nfrozen 1\n")?;
+        assert_eq!(events.wait(timeout)?, Some(CgroupEvent::Empty));
+
+        // nothing changed
+        set_fixture(tmp.path(), CGROUP_EVENTS, "populated 0\nfrozen 1\n")?;
+        assert_eq!(events.wait(Some(Duration::from_millis(100)))?, None);
+
+        fs::remove_file(tmp.path().join(CGROUP_EVENTS))?;
+        fs::remove_file(tmp.path().join(MEMORY_EVENTS))?;
+        assert_eq!(events.wait(timeout)?, None);
+        assert!(events.closed());
+
+        Ok(())
+    }
+}
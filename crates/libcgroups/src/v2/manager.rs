@@ -14,6 +14,7 @@ use super::cpu::{Cpu, V2CpuControllerError, V2CpuStatsError};
 use super::cpuset::CpuSet;
 #[cfg(feature = "cgroupsv2_devices")]
 use super::devices::Devices;
+use super::events::EventWatcher;
 use super::freezer::{Freezer, V2FreezerError};
 use super::hugetlb::{HugeTlb, V2HugeTlbControllerError, V2HugeTlbStatsError};
 use super::io::{Io, V2IoControllerError, V2IoStatsError};
@@ -25,6 +26,7 @@ use crate::common::{
     self, AnyCgroupManager, CgroupManager, ControllerOpt, FreezerState, JoinSafelyError,
     PathBufExt, WrapIoResult, WrappedIoError, CGROUP_PROCS,
 };
+use crate::events::{Events, EventsError};
 use crate::stats::{PidStatsError, Stats, StatsProvider};
 
 pub const CGROUP_KILL: &str = "cgroup.kill";
@@ -68,6 +70,9 @@ pub enum V2ManagerError {
     MemoryStats(#[from] V2MemoryStatsError),
     #[error(transparent)]
     IoStats(#[from] V2IoStatsError),
+
+    #[error(transparent)]
+    Events(#[from] EventsError),
 }
 
 /// Represents a management interface for a cgroup located at `{root_path}/{cgroup_path}`
@@ -239,4 +244,9 @@ impl CgroupManager for Manager {
     fn get_all_pids(&self) -> Result<Vec<Pid>, Self::Error> {
         Ok(common::get_all_pids(&self.full_path)?)
     }
+
+    fn events(&self) -> Result<Events, Self::Error> {
+        let watcher = EventWatcher::new(&self.full_path)?;
+        Ok(Events::new(vec![Box::new(watcher)]))
+    }
 }
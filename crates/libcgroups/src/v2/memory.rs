@@ -4,13 +4,18 @@ use oci_spec::runtime::LinuxMemory;
 
 use super::controller::Controller;
 use crate::common::{self, ControllerOpt, WrappedIoError};
-use crate::stats::{self, MemoryData, MemoryStats, ParseFlatKeyedDataError, StatsProvider};
+use crate::stats::{
+    self, MemoryData, MemoryEvents, MemoryStats, ParseFlatKeyedDataError, StatsProvider, SwapEvents,
+};
 
 const CGROUP_MEMORY_SWAP: &str = "memory.swap.max";
 const CGROUP_MEMORY_MAX: &str = "memory.max";
 const CGROUP_MEMORY_LOW: &str = "memory.low";
 const MEMORY_STAT: &str = "memory.stat";
 const MEMORY_PSI: &str = "memory.pressure";
+const MEMORY_EVENTS: &str = "memory.events";
+const MEMORY_SWAP_EVENTS: &str = "memory.swap.events";
+const MEMORY_NUMA_STAT: &str = "memory.numa_stat";
 
 #[derive(thiserror::Error, Debug)]
 pub enum V2MemoryControllerError {
@@ -59,6 +64,9 @@ impl StatsProvider for Memory {
             memswap: Self::get_memory_data(cgroup_path, "memory.swap", "fail")?,
             hierarchy: true,
             stats: stats::parse_flat_keyed_data(&cgroup_path.join(MEMORY_STAT))?,
+            numa_stats: stats::parse_numa_stat(&cgroup_path.join(MEMORY_NUMA_STAT), 1)?,
+            events: Self::get_memory_events(cgroup_path)?,
+            swap_events: Self::get_swap_events(cgroup_path)?,
             psi: stats::psi_stats(&cgroup_path.join(MEMORY_PSI))?,
             ..Default::default()
         };
@@ -98,6 +106,32 @@ impl Memory {
         })
     }
 
+    fn get_memory_events(cgroup_path: &Path) -> Result<MemoryEvents, ParseFlatKeyedDataError> {
+        let events = stats::parse_flat_keyed_data(&cgroup_path.join(MEMORY_EVENTS))?;
+        let get = |key: &str| events.get(key).copied().unwrap_or_default();
+
+        Ok(MemoryEvents {
+            low: get("low"),
+            high: get("high"),
+            max: get("max"),
+            oom: get("oom"),
+            oom_kill: get("oom_kill"),
+            // only there since linux 5.17
+            oom_group_kill: get("oom_group_kill"),
+        })
+    }
+
+    fn get_swap_events(cgroup_path: &Path) -> Result<SwapEvents, ParseFlatKeyedDataError> {
+        let events = stats::parse_flat_keyed_data(&cgroup_path.join(MEMORY_SWAP_EVENTS))?;
+        let get = |key: &str| events.get(key).copied().unwrap_or_default();
+
+        Ok(SwapEvents {
+            high: get("high"),
+            max: get("max"),
+            fail: get("fail"),
+        })
+    }
+
     fn set<P: AsRef<Path>>(path: P, val: i64) -> Result<(), WrappedIoError> {
         if val == 0 {
             Ok(())
@@ -401,4 +435,39 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_get_memory_events() {
+        let tmp = tempfile::tempdir().unwrap();
+        let events = [
+            "low 1",
+            "high 2",
+            "max 3",
+            "oom 4",
+            "oom_kill 5",
+            "oom_group_kill 6",
+        ]
+        .join("\n");
+        set_fixture(tmp.path(), MEMORY_EVENTS, &events).unwrap();
+        set_fixture(tmp.path(), MEMORY_SWAP_EVENTS, "high 0\nmax 1\nfail 2\n").unwrap();
+
+        let actual = Memory::get_memory_events(tmp.path()).expect("get memory events");
+        let expected = MemoryEvents {
+            low: 1,
+            high: 2,
+            max: 3,
+            oom: 4,
+            oom_kill: 5,
+            oom_group_kill: 6,
+        };
+        assert_eq!(actual, expected);
+
+        let actual = Memory::get_swap_events(tmp.path()).expect("get swap events");
+        let expected = SwapEvents {
+            high: 0,
+            max: 1,
+            fail: 2,
+        };
+        assert_eq!(actual, expected);
+    }
 }
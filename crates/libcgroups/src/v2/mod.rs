@@ -4,6 +4,7 @@ mod cpu;
 mod cpuset;
 #[cfg(feature = "cgroupsv2_devices")]
 pub mod devices;
+pub(crate) mod events;
 mod freezer;
 mod hugetlb;
 mod io;
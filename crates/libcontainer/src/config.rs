@@ -44,19 +44,22 @@ const YOUKI_CONFIG_NAME: &str = "youki_config.json";
 pub struct YoukiConfig {
     pub hooks: Option<Hooks>,
     pub cgroup_path: PathBuf,
+    /// The resctrl group of the container, if it uses Intel RDT
+    pub intel_rdt_group: Option<String>,
 }
 
 impl<'a> YoukiConfig {
     pub fn from_spec(spec: &'a Spec, container_id: &str) -> Result<Self> {
+        let linux = spec.linux().as_ref().ok_or(ConfigError::MissingLinux)?;
         Ok(YoukiConfig {
             hooks: spec.hooks().clone(),
-            cgroup_path: utils::get_cgroup_path(
-                spec.linux()
-                    .as_ref()
-                    .ok_or(ConfigError::MissingLinux)?
-                    .cgroups_path(),
-                container_id,
-            ),
+            cgroup_path: utils::get_cgroup_path(linux.cgroups_path(), container_id),
+            intel_rdt_group: linux.intel_rdt().as_ref().map(|intel_rdt| {
+                intel_rdt
+                    .clos_id()
+                    .clone()
+                    .unwrap_or_else(|| container_id.to_owned())
+            }),
         })
     }
 
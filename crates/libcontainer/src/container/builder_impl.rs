@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
 use std::os::fd::{AsRawFd, OwnedFd};
@@ -14,7 +15,10 @@ use crate::notify_socket::NotifyListener;
 use crate::process::args::{ContainerArgs, ContainerType};
 use crate::process::intel_rdt::delete_resctrl_subdirectory;
 use crate::process::{self};
+use crate::rootfs::mount::open_idmapped_mounts;
+use crate::rootfs::RootfsError;
 use crate::syscall::syscall::SyscallType;
+use crate::timing::{self, PhaseProcess};
 use crate::user_ns::UserNamespaceConfig;
 use crate::workload::Executor;
 use crate::{hooks, utils};
@@ -59,6 +63,8 @@ pub(super) struct ContainerBuilderImpl {
     pub stderr: Option<OwnedFd>,
     // Indicate if the init process should be a sibling of the main process.
     pub as_sibling: bool,
+    /// If the init process should keep the session keyring of the caller
+    pub no_new_keyring: bool,
 }
 
 impl ContainerBuilderImpl {
@@ -84,6 +90,7 @@ impl ContainerBuilderImpl {
     }
 
     fn run_container(&mut self) -> Result<Pid, LibcontainerError> {
+        let _phase = timing::phase(PhaseProcess::Main, "run_container");
         let linux = self.spec.linux().as_ref().ok_or(MissingSpecError::Linux)?;
         let cgroups_path = utils::get_cgroup_path(linux.cgroups_path(), &self.container_id);
         let cgroup_config = libcgroups::common::CgroupConfig {
@@ -99,6 +106,7 @@ impl ContainerBuilderImpl {
 
         if matches!(self.container_type, ContainerType::InitContainer) {
             if let Some(hooks) = self.spec.hooks() {
+                let _phase = timing::phase(PhaseProcess::Main, "create_runtime_hooks");
                 hooks::run_hooks(
                     hooks.create_runtime().as_ref(),
                     self.container.as_ref(),
@@ -137,6 +145,23 @@ impl ContainerBuilderImpl {
                 })?;
         }
 
+        // Idmapped mounts are created on the host, where the user namespace
+        // with the id mappings can be set up, and are attached by the init
+        // process while preparing the rootfs. The detached mounts are kept
+        // open until the container process has been created.
+        let idmapped_mounts = if self.is_init_container() {
+            let _phase = timing::phase(PhaseProcess::Main, "idmapped_mounts");
+            let syscall = self.syscall.create_syscall();
+            open_idmapped_mounts(
+                self.spec.mounts().as_deref().unwrap_or_default(),
+                self.user_ns_config.as_ref(),
+                syscall.as_ref(),
+            )
+            .map_err(RootfsError::from)?
+        } else {
+            HashMap::new()
+        };
+
         // Make the process non-dumpable, to avoid various race conditions that
         // could cause processes in namespaces we're joining to access host
         // resources (or potentially execute code).
@@ -175,8 +200,14 @@ impl ContainerBuilderImpl {
             stdout: self.stdout.as_ref().map(|x| x.as_raw_fd()),
             stderr: self.stderr.as_ref().map(|x| x.as_raw_fd()),
             as_sibling: self.as_sibling,
+            no_new_keyring: self.no_new_keyring,
+            idmapped_mounts: idmapped_mounts
+                .iter()
+                .map(|(destination, fd)| (destination.clone(), fd.as_raw_fd()))
+                .collect(),
         };
 
+        let phase = timing::phase(PhaseProcess::Main, "main_process");
         let (init_pid, need_to_clean_up_intel_rdt_dir) =
             process::container_main_process::container_main_process(&container_args).map_err(
                 |err| {
@@ -184,6 +215,7 @@ impl ContainerBuilderImpl {
                     LibcontainerError::MainProcess(err)
                 },
             )?;
+        drop(phase);
 
         // if file to write the pid to is specified, write pid of the child
         if let Some(pid_file) = &self.pid_file {
@@ -199,7 +231,15 @@ impl ContainerBuilderImpl {
                 .set_status(ContainerStatus::Created)
                 .set_creator(nix::unistd::geteuid().as_raw())
                 .set_pid(init_pid.as_raw())
+                .record_init_process_start()
+                .record_memory_cgroup()
                 .set_clean_up_intel_rdt_directory(need_to_clean_up_intel_rdt_dir)
+                .set_intel_rdt(
+                    self.spec
+                        .linux()
+                        .as_ref()
+                        .and_then(|linux| linux.intel_rdt().clone()),
+                )
                 .save()?;
         }
 
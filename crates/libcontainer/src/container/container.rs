@@ -1,15 +1,26 @@
 use std::collections::HashMap;
 use std::ffi::OsString;
 use std::fs;
+use std::os::unix::io::RawFd;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
+use libcgroups::common::CgroupSetup;
+use nix::errno::Errno;
+use nix::sys::signal::{self, Signal};
+use nix::sys::wait::WaitStatus;
 use nix::unistd::Pid;
-use procfs::process::Process;
+use oci_spec::runtime::{LinuxIntelRdt, LinuxResources};
+use procfs::process::{Process, Stat};
 
 use crate::config::YoukiConfig;
-use crate::container::{ContainerStatus, State};
+use crate::container::{
+    CheckpointError, ContainerLock, ContainerStatus, State, DEFAULT_LOCK_TIMEOUT,
+};
 use crate::error::LibcontainerError;
+use crate::pidfd::PidFd;
 use crate::syscall::syscall::create_syscall;
 
 /// Structure representing the container data
@@ -19,6 +30,8 @@ pub struct Container {
     pub state: State,
     // indicated the directory for the root path in the container
     pub root: PathBuf,
+    // time to wait for other processes to release the container lock
+    lock_timeout: Duration,
 }
 
 impl Default for Container {
@@ -26,6 +39,7 @@ impl Default for Container {
         Self {
             state: State::default(),
             root: PathBuf::from("/run/youki"),
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
         }
     }
 }
@@ -51,6 +65,7 @@ impl Container {
         Ok(Self {
             state,
             root: container_root,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
         })
     }
 
@@ -100,6 +115,67 @@ impl Container {
         self
     }
 
+    pub fn init_process_start(&self) -> Option<u64> {
+        self.state.init_process_start
+    }
+
+    /// Records the start time of the process with the current pid, which is
+    /// checked before acting on the container process later on.
+    pub fn record_init_process_start(&mut self) -> &mut Self {
+        self.state.init_process_start = self
+            .pid()
+            .and_then(|pid| Process::new(pid.as_raw()).and_then(|proc| proc.stat()).ok())
+            .map(|stat| stat.starttime);
+        self
+    }
+
+    /// Records the memory cgroup of the process with the current pid, where
+    /// the OOM kills are read from once the process exited.
+    pub fn record_memory_cgroup(&mut self) -> &mut Self {
+        self.state.memory_cgroup = self.pid().and_then(memory_cgroup);
+        self
+    }
+
+    // A process whose start time differs from the recorded one reused the pid
+    // of the container process after it exited.
+    pub(super) fn is_init_process(&self, stat: &Stat) -> bool {
+        self.state
+            .init_process_start
+            .map_or(true, |start| start == stat.starttime)
+    }
+
+    /// Opens the container init process after making sure its pid wasn't
+    /// reused by another process. Returns `None` if the process is gone.
+    pub(crate) fn open_init_process(&self) -> Result<Option<InitProcess>, LibcontainerError> {
+        let pid = match self.pid() {
+            Some(pid) => pid,
+            None => return Ok(None),
+        };
+
+        // The pidfd is opened before checking the process, so it refers to the
+        // checked process even if the pid is reused right afterwards.
+        let pidfd = match PidFd::open(pid) {
+            Ok(pidfd) => Some(pidfd),
+            Err(Errno::ESRCH) => return Ok(None),
+            Err(Errno::ENOSYS) => None,
+            Err(err) => {
+                tracing::error!(id = ?self.id(), ?pid, ?err, "failed to open pidfd");
+                return Err(LibcontainerError::OtherSyscall(err));
+            }
+        };
+
+        let stat = match Process::new(pid.as_raw()).and_then(|proc| proc.stat()) {
+            Ok(stat) => stat,
+            Err(_) => return Ok(None),
+        };
+        if !self.is_init_process(&stat) {
+            tracing::warn!(id = ?self.id(), ?pid, "container process pid was reused by another process");
+            return Ok(None);
+        }
+
+        Ok(Some(InitProcess { pid, pidfd }))
+    }
+
     pub fn created(&self) -> Option<DateTime<Utc>> {
         self.state.created
     }
@@ -139,6 +215,83 @@ impl Container {
         self.state.clean_up_intel_rdt_subdirectory
     }
 
+    pub fn resources(&self) -> Option<&LinuxResources> {
+        self.state.resources.as_ref()
+    }
+
+    pub fn set_resources(&mut self, resources: Option<LinuxResources>) -> &mut Self {
+        self.state.resources = resources;
+        self
+    }
+
+    pub fn intel_rdt(&self) -> Option<&LinuxIntelRdt> {
+        self.state.intel_rdt.as_ref()
+    }
+
+    pub fn set_intel_rdt(&mut self, intel_rdt: Option<LinuxIntelRdt>) -> &mut Self {
+        self.state.intel_rdt = intel_rdt;
+        self
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        self.state.exit_code
+    }
+
+    pub fn exit_signal(&self) -> Option<i32> {
+        self.state.exit_signal
+    }
+
+    pub fn finished_at(&self) -> Option<DateTime<Utc>> {
+        self.state.finished_at
+    }
+
+    pub fn oom_killed(&self) -> Option<bool> {
+        self.state.oom_killed
+    }
+
+    /// Records the exit of the container process observed by the caller, e.g.
+    /// after reaping it.
+    pub fn set_exit_status(&mut self, status: WaitStatus) -> &mut Self {
+        self.record_exit(Some(status))
+    }
+
+    fn record_exit(&mut self, status: Option<WaitStatus>) -> &mut Self {
+        match status {
+            Some(WaitStatus::Exited(_, code)) => self.state.exit_code = Some(code),
+            Some(WaitStatus::Signaled(_, signal, _)) => {
+                self.state.exit_signal = Some(signal as i32)
+            }
+            _ => {}
+        }
+        self.state.finished_at = Some(Utc::now());
+        self.state.oom_killed = self.read_oom_killed();
+        self
+    }
+
+    // The OOM kill counter of the memory cgroup tells if the OOM killer
+    // killed a process of the container, which is not visible in the exit
+    // status itself. v2 counts them in memory.events, v1 in memory.oom_control.
+    fn read_oom_killed(&self) -> Option<bool> {
+        let memory_cgroup = self.state.memory_cgroup.as_ref()?;
+        let path = ["memory.events", "memory.oom_control"]
+            .iter()
+            .map(|file| memory_cgroup.join(file))
+            .find(|path| path.exists())?;
+
+        match fs::read_to_string(&path) {
+            Ok(content) => content
+                .lines()
+                .filter_map(|line| line.split_once(' '))
+                .find(|(key, _)| *key == "oom_kill")
+                .and_then(|(_, count)| count.trim().parse::<u64>().ok())
+                .map(|count| count > 0),
+            Err(err) => {
+                tracing::debug!(id = ?self.id(), ?path, ?err, "failed to read oom kills");
+                None
+            }
+        }
+    }
+
     pub fn status(&self) -> ContainerStatus {
         self.state.status
     }
@@ -156,6 +309,7 @@ impl Container {
     }
 
     pub fn refresh_status(&mut self) -> Result<(), LibcontainerError> {
+        let mut exit_status = None;
         let new_status = match self.pid() {
             Some(pid) => {
                 // Note that Process::new does not spawn a new process
@@ -164,8 +318,18 @@ impl Container {
                 if let Ok(proc) = Process::new(pid.as_raw()) {
                     use procfs::process::ProcState;
 
-                    match proc.stat()?.state()? {
-                        ProcState::Zombie | ProcState::Dead => ContainerStatus::Stopped,
+                    let stat = proc.stat()?;
+                    match stat.state()? {
+                        // the container process exited and its pid was reused
+                        _ if !self.is_init_process(&stat) => ContainerStatus::Stopped,
+                        ProcState::Zombie | ProcState::Dead => {
+                            // the exit status of a process is kept until
+                            // its parent reaps it
+                            exit_status = stat
+                                .exit_code
+                                .and_then(|status| WaitStatus::from_raw(pid, status).ok());
+                            ContainerStatus::Stopped
+                        }
                         _ => match self.status() {
                             ContainerStatus::Creating
                             | ContainerStatus::Created
@@ -180,10 +344,36 @@ impl Container {
             None => ContainerStatus::Stopped,
         };
 
+        if new_status == ContainerStatus::Stopped
+            && self.pid().is_some()
+            && self.state.finished_at.is_none()
+        {
+            self.record_exit(exit_status);
+        }
+
         self.set_status(new_status);
         Ok(())
     }
 
+    /// Saves the exit of the container process the first time it is
+    /// observed. The exit status is only known until the parent of the
+    /// process reaps it, and an unsaved finish time would be the time of
+    /// every later query.
+    pub fn save_exit(&mut self) -> Result<(), LibcontainerError> {
+        if self.status() != ContainerStatus::Stopped {
+            return Ok(());
+        }
+
+        let _lock = self.lock()?;
+        let recorded = self.state.finished_at.is_some();
+        self.refresh_status()?;
+        if !recorded && self.state.finished_at.is_some() {
+            self.save()?;
+        }
+
+        Ok(())
+    }
+
     pub fn refresh_state(&mut self) -> Result<&mut Self, LibcontainerError> {
         let state = State::load(&self.root)?;
         self.state = state;
@@ -196,11 +386,31 @@ impl Container {
         let mut container = Self {
             state,
             root: container_root,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
         };
+        // an exit observed here is only saved by `save_exit` and the
+        // operations that lock the container and save its state afterwards
         container.refresh_status()?;
         Ok(container)
     }
 
+    /// Sets how long operations modifying the container wait for other
+    /// processes operating on the same container
+    pub fn set_lock_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// Locks the container against concurrent modifications by other
+    /// processes until the returned lock is dropped. The state is reloaded
+    /// once the lock is taken, as it may have changed while waiting for it.
+    pub fn lock(&mut self) -> Result<ContainerLock, LibcontainerError> {
+        let lock = ContainerLock::acquire(&self.root, self.lock_timeout)?;
+        self.refresh_state()?;
+
+        Ok(lock)
+    }
+
     pub fn save(&self) -> Result<(), LibcontainerError> {
         tracing::debug!("Save container status: {:?} in {:?}", self, self.root);
         self.state.save(&self.root)?;
@@ -214,6 +424,61 @@ impl Container {
     }
 }
 
+/// The directory of the memory cgroup of a process. On a hybrid setup the
+/// memory controller is mounted on a v1 hierarchy.
+fn memory_cgroup(pid: Pid) -> Option<PathBuf> {
+    let cgroups = Process::new(pid.as_raw())
+        .and_then(|proc| proc.cgroups())
+        .ok()?;
+    let (mount_point, cgroup) = match libcgroups::common::get_cgroup_setup().ok()? {
+        CgroupSetup::Unified => (
+            PathBuf::from(libcgroups::common::DEFAULT_CGROUP_ROOT),
+            cgroups.into_iter().find(|cgroup| cgroup.hierarchy == 0)?,
+        ),
+        CgroupSetup::Legacy | CgroupSetup::Hybrid => (
+            memory_mount_point()?,
+            cgroups.into_iter().find(|cgroup| {
+                cgroup
+                    .controllers
+                    .iter()
+                    .any(|controller| controller == "memory")
+            })?,
+        ),
+    };
+
+    Some(mount_point.join(cgroup.pathname.trim_start_matches('/')))
+}
+
+#[cfg(feature = "v1")]
+fn memory_mount_point() -> Option<PathBuf> {
+    libcgroups::v1::util::get_subsystem_mount_point(&libcgroups::v1::ControllerType::Memory).ok()
+}
+
+#[cfg(not(feature = "v1"))]
+fn memory_mount_point() -> Option<PathBuf> {
+    None
+}
+
+/// The container init process, checked to be the process the container was
+/// created with
+pub(crate) struct InitProcess {
+    pub pid: Pid,
+    // pidfds are not supported on kernels older than 5.3
+    pub pidfd: Option<PidFd>,
+}
+
+impl InitProcess {
+    pub fn kill(&self, signal: Signal) -> nix::Result<()> {
+        if let Some(pidfd) = &self.pidfd {
+            match pidfd.send_signal(signal) {
+                Err(Errno::ENOSYS) => {}
+                result => return result,
+            }
+        }
+        signal::kill(self.pid, signal)
+    }
+}
+
 /// Checkpoint parameter structure
 pub struct CheckpointOptions {
     pub ext_unix_sk: bool,
@@ -223,6 +488,73 @@ pub struct CheckpointOptions {
     pub shell_job: bool,
     pub tcp_established: bool,
     pub work_path: Option<PathBuf>,
+    /// Images of a previous dump, relative to `image_path`
+    pub parent_path: Option<PathBuf>,
+    /// Only dump the memory of the container and leave it running, as the
+    /// parent of a later dump
+    pub pre_dump: bool,
+    /// Page server to send the memory pages to, as ADDRESS:PORT
+    pub page_server: Option<String>,
+    pub lazy_pages: bool,
+    /// File descriptor criu writes to once the dump is ready for a lazy restore
+    pub status_fd: Option<RawFd>,
+    pub auto_dedup: bool,
+    pub manage_cgroups_mode: ManageCgroupsMode,
+    /// Dump the network namespace without its properties
+    pub empty_ns: bool,
+}
+
+/// Restore parameter structure
+pub struct RestoreOptions {
+    pub ext_unix_sk: bool,
+    pub file_locks: bool,
+    pub image_path: PathBuf,
+    pub shell_job: bool,
+    pub tcp_established: bool,
+    pub work_path: Option<PathBuf>,
+    pub manage_cgroups_mode: ManageCgroupsMode,
+    pub console_socket: Option<PathBuf>,
+    pub pid_file: Option<PathBuf>,
+    pub use_systemd: bool,
+}
+
+/// How criu handles the cgroups of the container
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ManageCgroupsMode {
+    /// Restore cgroup properties only for cgroups criu creates itself
+    #[default]
+    Soft,
+    /// Always restore all cgroup properties
+    Full,
+    /// Restore all cgroup properties and fail if a cgroup already exists
+    Strict,
+    /// Don't dump or restore cgroups at all
+    Ignore,
+}
+
+impl ManageCgroupsMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Soft => "soft",
+            Self::Full => "full",
+            Self::Strict => "strict",
+            Self::Ignore => "ignore",
+        }
+    }
+}
+
+impl FromStr for ManageCgroupsMode {
+    type Err = CheckpointError;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "soft" => Ok(Self::Soft),
+            "full" => Ok(Self::Full),
+            "strict" => Ok(Self::Strict),
+            "ignore" => Ok(Self::Ignore),
+            _ => Err(CheckpointError::InvalidManageCgroupsMode(mode.to_string())),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -377,4 +709,167 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_refresh_status_reused_pid() -> Result<()> {
+        let pid = nix::unistd::getpid();
+        let mut container = Container::default();
+        container
+            .set_pid(pid.as_raw())
+            .set_status(ContainerStatus::Running)
+            .record_init_process_start();
+        assert!(container.init_process_start().is_some());
+        assert!(container.open_init_process()?.is_some());
+        container.refresh_status()?;
+        assert_eq!(container.status(), ContainerStatus::Running);
+
+        // a different start time means the pid belongs to another process
+        container.state.init_process_start = container.init_process_start().map(|start| start + 1);
+        assert!(container.open_init_process()?.is_none());
+        container.refresh_status()?;
+        assert_eq!(container.status(), ContainerStatus::Stopped);
+        assert_eq!(container.exit_code(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_exit_status() {
+        let mut container = Container::default();
+        container.set_exit_status(WaitStatus::Exited(Pid::from_raw(1), 3));
+        assert_eq!(container.exit_code(), Some(3));
+        assert_eq!(container.exit_signal(), None);
+        assert!(container.finished_at().is_some());
+
+        let mut container = Container::default();
+        container.set_exit_status(WaitStatus::Signaled(
+            Pid::from_raw(1),
+            nix::sys::signal::Signal::SIGKILL,
+            false,
+        ));
+        assert_eq!(container.exit_code(), None);
+        assert_eq!(container.exit_signal(), Some(9));
+    }
+
+    #[test]
+    #[serial]
+    fn test_refresh_status_records_exit() -> Result<()> {
+        use nix::sys::wait::{waitid, waitpid, Id, WaitPidFlag};
+        use nix::unistd::{fork, ForkResult};
+
+        let child = match unsafe { fork()? } {
+            ForkResult::Child => std::process::exit(3),
+            ForkResult::Parent { child } => child,
+        };
+        // wait for the child to exit, but keep it as a zombie
+        waitid(Id::Pid(child), WaitPidFlag::WEXITED | WaitPidFlag::WNOWAIT)?;
+
+        let mut container = Container::default();
+        container
+            .set_pid(child.as_raw())
+            .set_status(ContainerStatus::Running);
+        container.refresh_status()?;
+        waitpid(child, None)?;
+
+        assert_eq!(container.status(), ContainerStatus::Stopped);
+        assert_eq!(container.exit_code(), Some(3));
+        assert!(container.finished_at().is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_does_not_save_exit() -> Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        let mut container = Container {
+            root: tmp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        // a pid that can't belong to a running process
+        container
+            .set_pid(i32::MAX)
+            .set_status(ContainerStatus::Running)
+            .save()?;
+
+        let loaded = Container::load(tmp_dir.path().to_path_buf())?;
+        assert_eq!(loaded.status(), ContainerStatus::Stopped);
+        assert!(loaded.finished_at().is_some());
+        let saved = State::load(tmp_dir.path())?;
+        assert_eq!(saved.status, ContainerStatus::Running);
+        assert!(saved.finished_at.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_save_exit() -> Result<()> {
+        use nix::sys::wait::{waitid, waitpid, Id, WaitPidFlag};
+        use nix::unistd::{fork, ForkResult};
+
+        let child = match unsafe { fork()? } {
+            ForkResult::Child => std::process::exit(3),
+            ForkResult::Parent { child } => child,
+        };
+        waitid(Id::Pid(child), WaitPidFlag::WEXITED | WaitPidFlag::WNOWAIT)?;
+
+        let tmp_dir = tempfile::tempdir()?;
+        let mut container = Container {
+            root: tmp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        container
+            .set_pid(child.as_raw())
+            .set_status(ContainerStatus::Running)
+            .save()?;
+
+        let mut first = Container::load(tmp_dir.path().to_path_buf())?;
+        first.save_exit()?;
+        // the exit status is gone once the process is reaped
+        waitpid(child, None)?;
+        let second = Container::load(tmp_dir.path().to_path_buf())?;
+
+        assert_eq!(second.status(), ContainerStatus::Stopped);
+        assert_eq!(second.exit_code(), Some(3));
+        assert_eq!(second.exit_code(), first.exit_code());
+        assert!(second.finished_at().is_some());
+        assert_eq!(second.finished_at(), first.finished_at());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_oom_killed() -> Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        let mut container = Container::default();
+        assert_eq!(container.read_oom_killed(), None);
+
+        container.state.memory_cgroup = Some(tmp_dir.path().to_path_buf());
+        assert_eq!(container.read_oom_killed(), None);
+
+        fs::write(
+            tmp_dir.path().join("memory.oom_control"),
+            "oom_kill_disable 0\nunder_oom 0\noom_kill 0\n",
+        )?;
+        assert_eq!(container.read_oom_killed(), Some(false));
+
+        fs::write(
+            tmp_dir.path().join("memory.events"),
+            "low 0\nhigh 0\nmax 4\noom 1\noom_kill 1\noom_group_kill 0\n",
+        )?;
+        assert_eq!(container.read_oom_killed(), Some(true));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_manage_cgroups_mode() -> Result<()> {
+        for mode in ["soft", "full", "strict", "ignore"] {
+            assert_eq!(mode.parse::<ManageCgroupsMode>()?.as_str(), mode);
+        }
+        assert_eq!(ManageCgroupsMode::default(), ManageCgroupsMode::Soft);
+        assert!("props".parse::<ManageCgroupsMode>().is_err());
+
+        Ok(())
+    }
 }
@@ -1,27 +1,37 @@
+use std::ffi::OsString;
 use std::fs::{self, File};
 use std::io::Write;
 use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::process::Command;
 
 use libcgroups::common::CgroupSetup::{Hybrid, Legacy};
 #[cfg(feature = "v1")]
 use libcgroups::common::DEFAULT_CGROUP_ROOT;
 use oci_spec::runtime::Spec;
+use rust_criu::{CgMode, Criu, CriuError};
 
-use super::{Container, ContainerStatus};
+use super::{Container, ContainerStatus, ManageCgroupsMode};
 use crate::container::container::CheckpointOptions;
 use crate::error::LibcontainerError;
 
 const CRIU_CHECKPOINT_LOG_FILE: &str = "dump.log";
-const DESCRIPTORS_JSON: &str = "descriptors.json";
+const CRIU_PRE_DUMP_LOG_FILE: &str = "pre-dump.log";
+pub(super) const DESCRIPTORS_JSON: &str = "descriptors.json";
 
 #[derive(thiserror::Error, Debug)]
 pub enum CheckpointError {
     #[error("criu error: {0}")]
     CriuError(String),
+    #[error("invalid page server {0}, expected ADDRESS:PORT")]
+    InvalidPageServer(String),
+    #[error("invalid cgroups mode {0}, expected soft, full, strict or ignore")]
+    InvalidManageCgroupsMode(String),
 }
 
 impl Container {
     pub fn checkpoint(&mut self, opts: &CheckpointOptions) -> Result<(), LibcontainerError> {
+        let _lock = self.lock()?;
         self.refresh_status()?;
 
         // can_pause() checks if the container is running. That also works for
@@ -31,13 +41,6 @@ impl Container {
             tracing::error!(status = ?self.status(), id = ?self.id(), "cannot checkpoint container because it is not running");
             return Err(LibcontainerError::IncorrectStatus);
         }
-
-        let mut criu = rust_criu::Criu::new().map_err(|e| {
-            LibcontainerError::Checkpoint(CheckpointError::CriuError(format!(
-                "error in creating criu struct: {}",
-                e
-            )))
-        })?;
         // We need to tell CRIU that all bind mounts are external. CRIU will fail checkpointing
         // if it does not know that these bind mounts are coming from the outside of the container.
         // This information is needed during restore again. The external location of the bind
@@ -45,61 +48,11 @@ impl Container {
         // information found in 'config.json'.
         let source_spec_path = self.bundle().join("config.json");
         let spec = Spec::load(source_spec_path)?;
-        let mounts = spec.mounts().clone();
-        for m in mounts.unwrap_or_default() {
-            match m.typ().as_deref() {
-                Some("bind") => {
-                    let dest = m
-                        .destination()
-                        .clone()
-                        .into_os_string()
-                        .into_string()
-                        .expect("failed to convert mount destination");
-                    criu.set_external_mount(dest.clone(), dest);
-                }
-                Some("cgroup") => {
-                    match libcgroups::common::get_cgroup_setup()? {
-                        // For v1 it is necessary to list all cgroup mounts as external mounts
-                        Legacy | Hybrid => {
-                            #[cfg(not(feature = "v1"))]
-                            panic!("libcontainer can't run in a Legacy or Hybrid cgroup setup without the v1 feature");
-                            #[cfg(feature = "v1")]
-                            for mp in libcgroups::v1::util::list_subsystem_mount_points().map_err(
-                                |err| {
-                                    tracing::error!(?err, "failed to get subsystem mount points");
-                                    LibcontainerError::OtherCgroup(err.to_string())
-                                },
-                            )? {
-                                let cgroup_mount = mp
-                                    .clone()
-                                    .into_os_string()
-                                    .into_string()
-                                    .expect("failed to convert mount point");
-                                if cgroup_mount.starts_with(DEFAULT_CGROUP_ROOT) {
-                                    criu.set_external_mount(cgroup_mount.clone(), cgroup_mount);
-                                }
-                            }
-                        }
-                        _ => (),
-                    }
-                }
-                _ => (),
-            }
-        }
-
-        let directory = std::fs::File::open(&opts.image_path).map_err(|err| {
-            tracing::error!(path = ?opts.image_path, ?err, "failed to open criu image directory");
-            LibcontainerError::OtherIO(err)
-        })?;
-        criu.set_images_dir_fd(directory.as_raw_fd());
-
-        // It seems to be necessary to be defined outside of 'if' to
-        // keep the FD open until CRIU uses it.
-        let work_dir: std::fs::File;
-        if let Some(wp) = &opts.work_path {
-            work_dir = std::fs::File::open(wp).map_err(LibcontainerError::OtherIO)?;
-            criu.set_work_dir_fd(work_dir.as_raw_fd());
-        }
+        let terminal = spec
+            .process()
+            .as_ref()
+            .and_then(|process| process.terminal())
+            .unwrap_or(false);
 
         let pid: i32 = self
             .pid()
@@ -108,6 +61,40 @@ impl Container {
             ))?
             .into();
 
+        if opts.pre_dump {
+            let args = pre_dump_args(opts, pid, terminal, &external_mounts(&spec, false)?)?;
+            run_pre_dump(args).map_err(|err| {
+                let logfile = log_path(
+                    &opts.image_path,
+                    opts.work_path.as_deref(),
+                    CRIU_PRE_DUMP_LOG_FILE,
+                );
+                tracing::error!(?err, id = ?self.id(), ?logfile, "pre-dumping container failed");
+                err
+            })?;
+
+            tracing::debug!("container {} pre-dumped", self.id());
+            return Ok(());
+        }
+
+        let directory = std::fs::File::open(&opts.image_path).map_err(|err| {
+            tracing::error!(path = ?opts.image_path, ?err, "failed to open criu image directory");
+            LibcontainerError::OtherIO(err)
+        })?;
+        let work_dir = opts
+            .work_path
+            .as_ref()
+            .map(File::open)
+            .transpose()
+            .map_err(LibcontainerError::OtherIO)?;
+        let mut criu = new_criu(
+            &directory,
+            work_dir.as_ref(),
+            CRIU_CHECKPOINT_LOG_FILE,
+            external_mounts(&spec, false)?,
+            opts.manage_cgroups_mode,
+        )?;
+
         // Remember original stdin, stdout, stderr for container restore.
         let mut descriptors = Vec::new();
         for n in 0..3 {
@@ -129,16 +116,15 @@ impl Container {
         )
         .map_err(LibcontainerError::OtherIO)?;
 
-        criu.set_log_file(CRIU_CHECKPOINT_LOG_FILE.to_string());
-        criu.set_log_level(4);
         criu.set_pid(pid);
-        criu.set_leave_running(opts.leave_running);
-        criu.set_ext_unix_sk(opts.ext_unix_sk);
-        criu.set_shell_job(opts.shell_job);
-        criu.set_tcp_established(opts.tcp_established);
-        criu.set_file_locks(opts.file_locks);
+        set_dump_options(&mut criu, opts)?;
+        // The pseudo terminal of a terminal container is held by the console
+        // socket outside of the container, so the container is dumped as a
+        // shell job the same way it is restored.
+        if terminal {
+            criu.set_shell_job(true);
+        }
         criu.set_orphan_pts_master(true);
-        criu.set_manage_cgroups(true);
         criu.set_root(
             self.bundle()
                 .clone()
@@ -148,8 +134,13 @@ impl Container {
         );
 
         criu.dump().map_err(|err| {
-            tracing::error!(?err, id = ?self.id(), logfile = ?opts.image_path.join(CRIU_CHECKPOINT_LOG_FILE), "checkpointing container failed");
-            LibcontainerError::Other(err.to_string())
+            let logfile = log_path(
+                &opts.image_path,
+                opts.work_path.as_deref(),
+                CRIU_CHECKPOINT_LOG_FILE,
+            );
+            tracing::error!(?err, id = ?self.id(), ?logfile, "checkpointing container failed");
+            criu_error("dump", err)
         })?;
 
         if !opts.leave_running {
@@ -160,3 +151,541 @@ impl Container {
         Ok(())
     }
 }
+
+/// Creates a criu client with the options checkpoint and restore have in
+/// common. The directories have to stay open until criu is done with them.
+pub(super) fn new_criu(
+    image_dir: &File,
+    work_dir: Option<&File>,
+    log_file: &str,
+    external_mounts: Vec<(String, String)>,
+    manage_cgroups_mode: ManageCgroupsMode,
+) -> Result<Criu, LibcontainerError> {
+    let mut criu = Criu::new().map_err(|err| criu_error("create criu client", err))?;
+    criu.set_images_dir_fd(image_dir.as_raw_fd());
+    if let Some(work_dir) = work_dir {
+        criu.set_work_dir_fd(work_dir.as_raw_fd());
+    }
+    criu.set_log_file(log_file.to_string());
+    criu.set_log_level(4);
+    for (key, value) in external_mounts {
+        criu.set_external_mount(key, value);
+    }
+    criu.set_manage_cgroups(manage_cgroups_mode != ManageCgroupsMode::Ignore);
+    criu.cgroups_mode(cg_mode(manage_cgroups_mode));
+
+    Ok(criu)
+}
+
+/// Passes the options of a checkpoint on to criu
+fn set_dump_options(criu: &mut Criu, opts: &CheckpointOptions) -> Result<(), CheckpointError> {
+    criu.set_leave_running(opts.leave_running);
+    criu.set_ext_unix_sk(opts.ext_unix_sk);
+    criu.set_shell_job(opts.shell_job);
+    criu.set_tcp_established(opts.tcp_established);
+    criu.set_file_locks(opts.file_locks);
+    criu.set_auto_dedup(opts.auto_dedup);
+    criu.set_empty_net_ns(opts.empty_ns);
+    if let Some(parent_path) = &opts.parent_path {
+        criu.set_parent_img(parent_path.to_string_lossy().into_owned());
+    }
+    // criu sends the memory pages to the page server instead of writing them
+    // to the image directory. With lazy pages criu listens on the address
+    // itself, for the restore to fetch the pages from there.
+    if let Some(page_server) = &opts.page_server {
+        let (address, port) = parse_page_server(page_server)?;
+        criu.set_page_server(address.to_string(), port.into());
+    }
+    criu.set_lazy_pages(opts.lazy_pages);
+    if let Some(status_fd) = opts.status_fd {
+        criu.set_status_fd(status_fd);
+    }
+
+    Ok(())
+}
+
+/// Builds the arguments of `criu pre-dump` from the options of a checkpoint.
+/// The criu bindings have no pre-dump request, so pre-dumps run the criu
+/// command instead. A pre-dump only dumps the memory of the container and
+/// leaves it running. A later dump with `parent_path` set to the pre-dump
+/// then only dumps the memory changed since.
+fn pre_dump_args(
+    opts: &CheckpointOptions,
+    pid: i32,
+    terminal: bool,
+    external_mounts: &[(String, String)],
+) -> Result<Vec<OsString>, CheckpointError> {
+    let mut args: Vec<OsString> = vec![
+        "pre-dump".into(),
+        "--tree".into(),
+        pid.to_string().into(),
+        "--images-dir".into(),
+        opts.image_path.clone().into(),
+        "--log-file".into(),
+        CRIU_PRE_DUMP_LOG_FILE.into(),
+        "-v4".into(),
+        "--track-mem".into(),
+        format!("--manage-cgroups={}", opts.manage_cgroups_mode.as_str()).into(),
+    ];
+    if let Some(work_path) = &opts.work_path {
+        args.push("--work-dir".into());
+        args.push(work_path.clone().into());
+    }
+    if let Some(parent_path) = &opts.parent_path {
+        args.push("--prev-images-dir".into());
+        args.push(parent_path.clone().into());
+    }
+    if let Some(page_server) = &opts.page_server {
+        let (address, port) = parse_page_server(page_server)?;
+        args.push("--page-server".into());
+        args.push("--address".into());
+        args.push(address.into());
+        args.push("--port".into());
+        args.push(port.to_string().into());
+    }
+    for (key, value) in external_mounts {
+        args.push("--ext-mount-map".into());
+        args.push(format!("{key}:{value}").into());
+    }
+    for (enabled, flag) in [
+        (opts.ext_unix_sk, "--ext-unix-sk"),
+        (opts.shell_job || terminal, "--shell-job"),
+        (opts.tcp_established, "--tcp-established"),
+        (opts.file_locks, "--file-locks"),
+        (opts.auto_dedup, "--auto-dedup"),
+    ] {
+        if enabled {
+            args.push(flag.into());
+        }
+    }
+    if opts.empty_ns {
+        args.push("--empty-ns".into());
+        args.push("net".into());
+    }
+
+    Ok(args)
+}
+
+fn run_pre_dump(args: Vec<OsString>) -> Result<(), LibcontainerError> {
+    let status = Command::new("criu").args(args).status().map_err(|err| {
+        LibcontainerError::Checkpoint(CheckpointError::CriuError(format!(
+            "failed to run criu pre-dump: {err}"
+        )))
+    })?;
+    if !status.success() {
+        return Err(LibcontainerError::Checkpoint(CheckpointError::CriuError(
+            format!("failed to pre-dump: criu {status}"),
+        )));
+    }
+
+    Ok(())
+}
+
+fn cg_mode(mode: ManageCgroupsMode) -> CgMode {
+    match mode {
+        ManageCgroupsMode::Soft => CgMode::SOFT,
+        ManageCgroupsMode::Full => CgMode::FULL,
+        ManageCgroupsMode::Strict => CgMode::STRICT,
+        ManageCgroupsMode::Ignore => CgMode::IGNORE,
+    }
+}
+
+/// Splits a page server given as ADDRESS:PORT. IPv6 addresses are put in
+/// brackets, like [::1]:27, which criu doesn't expect.
+fn parse_page_server(page_server: &str) -> Result<(&str, u16), CheckpointError> {
+    page_server
+        .rsplit_once(':')
+        .and_then(|(address, port)| {
+            let port = port.parse::<u16>().ok()?;
+            let address = address
+                .strip_prefix('[')
+                .and_then(|address| address.strip_suffix(']'))
+                .unwrap_or(address);
+            (!address.is_empty()).then_some((address, port))
+        })
+        .ok_or_else(|| CheckpointError::InvalidPageServer(page_server.to_string()))
+}
+
+pub(super) fn criu_error(action: &str, err: CriuError) -> LibcontainerError {
+    LibcontainerError::Checkpoint(CheckpointError::CriuError(format!(
+        "failed to {action}: {err}"
+    )))
+}
+
+/// criu writes its log to the work directory, which defaults to the image
+/// directory
+pub(super) fn log_path(
+    image_path: &Path,
+    work_path: Option<&Path>,
+    log_file: &str,
+) -> std::path::PathBuf {
+    work_path.unwrap_or(image_path).join(log_file)
+}
+
+/// Returns the mounts CRIU has to treat as external as (key, value) pairs. The
+/// key identifies the mount inside the checkpoint image and the value is its
+/// location on the host. When checkpointing both are the mount destination,
+/// when restoring bind mounts are mapped back to their source from the spec.
+pub(super) fn external_mounts(
+    spec: &Spec,
+    restore: bool,
+) -> Result<Vec<(String, String)>, LibcontainerError> {
+    let mut external_mounts = Vec::new();
+    for m in spec.mounts().clone().unwrap_or_default() {
+        match m.typ().as_deref() {
+            Some("bind") => {
+                let dest = m
+                    .destination()
+                    .clone()
+                    .into_os_string()
+                    .into_string()
+                    .expect("failed to convert mount destination");
+                let value = match m.source() {
+                    Some(source) if restore => source
+                        .clone()
+                        .into_os_string()
+                        .into_string()
+                        .expect("failed to convert mount source"),
+                    _ => dest.clone(),
+                };
+                external_mounts.push((dest, value));
+            }
+            Some("cgroup") => {
+                match libcgroups::common::get_cgroup_setup()? {
+                    // For v1 it is necessary to list all cgroup mounts as external mounts
+                    Legacy | Hybrid => {
+                        #[cfg(not(feature = "v1"))]
+                        panic!("libcontainer can't run in a Legacy or Hybrid cgroup setup without the v1 feature");
+                        #[cfg(feature = "v1")]
+                        for mp in
+                            libcgroups::v1::util::list_subsystem_mount_points().map_err(|err| {
+                                tracing::error!(?err, "failed to get subsystem mount points");
+                                LibcontainerError::OtherCgroup(err.to_string())
+                            })?
+                        {
+                            let cgroup_mount = mp
+                                .clone()
+                                .into_os_string()
+                                .into_string()
+                                .expect("failed to convert mount point");
+                            if cgroup_mount.starts_with(DEFAULT_CGROUP_ROOT) {
+                                external_mounts.push((cgroup_mount.clone(), cgroup_mount));
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok(external_mounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::os::unix::process::CommandExt;
+    use std::path::PathBuf;
+    use std::process::{Command, Stdio};
+
+    use anyhow::Result;
+
+    use super::*;
+
+    fn checkpoint_options() -> CheckpointOptions {
+        CheckpointOptions {
+            ext_unix_sk: false,
+            file_locks: false,
+            image_path: PathBuf::from("/tmp/checkpoint"),
+            leave_running: false,
+            shell_job: false,
+            tcp_established: false,
+            work_path: None,
+            parent_path: None,
+            pre_dump: false,
+            page_server: None,
+            lazy_pages: false,
+            status_fd: None,
+            auto_dedup: false,
+            manage_cgroups_mode: ManageCgroupsMode::default(),
+            empty_ns: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_page_server() {
+        assert_eq!(
+            parse_page_server("192.168.0.1:27").unwrap(),
+            ("192.168.0.1", 27)
+        );
+        assert_eq!(parse_page_server("[::1]:1234").unwrap(), ("::1", 1234));
+        assert!(parse_page_server("192.168.0.1").is_err());
+        assert!(parse_page_server("[::1]").is_err());
+        assert!(parse_page_server("[]:27").is_err());
+        assert!(parse_page_server(":27").is_err());
+        assert!(parse_page_server("192.168.0.1:port").is_err());
+    }
+
+    #[test]
+    fn test_set_dump_options() -> Result<()> {
+        let mut criu = Criu::new()?;
+        let opts = CheckpointOptions {
+            page_server: Some("127.0.0.1".to_string()),
+            ..checkpoint_options()
+        };
+        assert!(matches!(
+            set_dump_options(&mut criu, &opts),
+            Err(CheckpointError::InvalidPageServer(_))
+        ));
+
+        let opts = CheckpointOptions {
+            page_server: Some("127.0.0.1:27".to_string()),
+            lazy_pages: true,
+            status_fd: Some(3),
+            parent_path: Some(PathBuf::from("../dump-1")),
+            ..checkpoint_options()
+        };
+        set_dump_options(&mut criu, &opts)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pre_dump_args() -> Result<()> {
+        let args = |opts: &CheckpointOptions, terminal| -> Result<Vec<String>> {
+            Ok(pre_dump_args(
+                opts,
+                42,
+                terminal,
+                &[("/data".to_string(), "/data".to_string())],
+            )?
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect())
+        };
+
+        assert_eq!(
+            args(&checkpoint_options(), false)?,
+            [
+                "pre-dump",
+                "--tree",
+                "42",
+                "--images-dir",
+                "/tmp/checkpoint",
+                "--log-file",
+                "pre-dump.log",
+                "-v4",
+                "--track-mem",
+                "--manage-cgroups=soft",
+                "--ext-mount-map",
+                "/data:/data",
+            ]
+        );
+
+        let opts = CheckpointOptions {
+            work_path: Some(PathBuf::from("/tmp/work")),
+            parent_path: Some(PathBuf::from("../pre-dump-1")),
+            page_server: Some("[::1]:27".to_string()),
+            tcp_established: true,
+            empty_ns: true,
+            ..checkpoint_options()
+        };
+        let got = args(&opts, true)?;
+        for expected in [
+            ["--work-dir", "/tmp/work"],
+            ["--prev-images-dir", "../pre-dump-1"],
+            ["--address", "::1"],
+            ["--port", "27"],
+            ["--empty-ns", "net"],
+        ] {
+            assert!(got.windows(2).any(|pair| pair == expected), "{expected:?}");
+        }
+        for flag in ["--page-server", "--shell-job", "--tcp-established"] {
+            assert!(got.iter().any(|arg| arg == flag), "{flag}");
+        }
+
+        let opts = CheckpointOptions {
+            page_server: Some("192.168.0.1".to_string()),
+            ..checkpoint_options()
+        };
+        assert!(pre_dump_args(&opts, 42, false, &[]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cg_mode() {
+        for (mode, cg) in [
+            (ManageCgroupsMode::Soft, CgMode::SOFT),
+            (ManageCgroupsMode::Full, CgMode::FULL),
+            (ManageCgroupsMode::Strict, CgMode::STRICT),
+            (ManageCgroupsMode::Ignore, CgMode::IGNORE),
+        ] {
+            assert_eq!(cg_mode(mode) as i32, cg as i32);
+        }
+    }
+
+    fn can_run_criu() -> bool {
+        nix::unistd::geteuid().is_root()
+            && Command::new("criu")
+                .arg("--version")
+                .output()
+                .map_or(false, |output| output.status.success())
+    }
+
+    // criu only dumps session leaders without a shell job
+    fn spawn_session_leader() -> Result<std::process::Child> {
+        let process = unsafe {
+            Command::new("sleep")
+                .arg("100")
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .pre_exec(|| nix::unistd::setsid().map(|_| ()).map_err(Into::into))
+                .spawn()?
+        };
+        Ok(process)
+    }
+
+    fn has_pages(path: &Path) -> Result<bool> {
+        Ok(fs::read_dir(path)?.any(|entry| {
+            entry.map_or(false, |entry| {
+                entry.file_name().to_string_lossy().starts_with("pages-")
+            })
+        }))
+    }
+
+    /// Pre-dumps a process and dumps it on top of the pre-dump. Needs root
+    /// and criu, so it does nothing without them.
+    #[test]
+    fn test_criu_pre_dump_chain() -> Result<()> {
+        if !can_run_criu() {
+            return Ok(());
+        }
+
+        let tmp_dir = tempfile::tempdir()?;
+        let pre_dump_path = tmp_dir.path().join("pre-dump");
+        let image_path = tmp_dir.path().join("dump");
+        fs::create_dir(&pre_dump_path)?;
+        fs::create_dir(&image_path)?;
+        let mut process = spawn_session_leader()?;
+        let pid = process.id() as i32;
+
+        let opts = CheckpointOptions {
+            image_path: pre_dump_path.clone(),
+            manage_cgroups_mode: ManageCgroupsMode::Ignore,
+            pre_dump: true,
+            ..checkpoint_options()
+        };
+        let pre_dumped = run_pre_dump(pre_dump_args(&opts, pid, false, &[])?);
+
+        let dumped = pre_dumped.and_then(|_| {
+            let directory = File::open(&image_path).map_err(LibcontainerError::OtherIO)?;
+            let mut criu = new_criu(
+                &directory,
+                None,
+                CRIU_CHECKPOINT_LOG_FILE,
+                Vec::new(),
+                ManageCgroupsMode::Ignore,
+            )?;
+            criu.set_pid(pid);
+            let opts = CheckpointOptions {
+                image_path: image_path.clone(),
+                parent_path: Some(PathBuf::from("../pre-dump")),
+                ..checkpoint_options()
+            };
+            set_dump_options(&mut criu, &opts)?;
+            criu.dump().map_err(|err| criu_error("dump", err))
+        });
+
+        let _ = process.kill();
+        let _ = process.wait();
+        dumped?;
+
+        // the pre-dump holds the memory, the dump refers to it as its parent
+        assert!(has_pages(&pre_dump_path)?);
+        assert_eq!(
+            fs::read_link(image_path.join("parent"))?,
+            PathBuf::from("../pre-dump")
+        );
+        assert!(image_path.join("inventory.img").exists());
+
+        Ok(())
+    }
+
+    /// Dumps a process to a local `criu page-server`. Needs root and criu,
+    /// so it does nothing without them.
+    #[test]
+    fn test_criu_dump_page_server() -> Result<()> {
+        if !can_run_criu() {
+            return Ok(());
+        }
+
+        let tmp_dir = tempfile::tempdir()?;
+        let image_path = tmp_dir.path().join("images");
+        let page_server_path = tmp_dir.path().join("page-server");
+        fs::create_dir(&image_path)?;
+        fs::create_dir(&page_server_path)?;
+        let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        // the page server daemonizes once it listens and exits after the dump
+        let page_server_pid = tmp_dir.path().join("page-server.pid");
+        let status = Command::new("criu")
+            .arg("page-server")
+            .arg("--daemon")
+            .arg("--pidfile")
+            .arg(&page_server_pid)
+            .arg("--images-dir")
+            .arg(&page_server_path)
+            .arg("--address")
+            .arg("127.0.0.1")
+            .arg("--port")
+            .arg(port.to_string())
+            .status()?;
+        assert!(status.success(), "failed to start the page server");
+
+        let mut process = spawn_session_leader()?;
+
+        let directory = File::open(&image_path)?;
+        let mut criu = new_criu(
+            &directory,
+            None,
+            CRIU_CHECKPOINT_LOG_FILE,
+            Vec::new(),
+            ManageCgroupsMode::Ignore,
+        )?;
+        criu.set_pid(process.id() as i32);
+        let opts = CheckpointOptions {
+            image_path: image_path.clone(),
+            page_server: Some(format!("127.0.0.1:{port}")),
+            ..checkpoint_options()
+        };
+        set_dump_options(&mut criu, &opts)?;
+        let dumped = criu.dump();
+
+        let _ = process.kill();
+        let _ = process.wait();
+        if let Ok(pid) = fs::read_to_string(&page_server_pid) {
+            if let Ok(pid) = pid.trim().parse() {
+                let _ = nix::sys::signal::kill(
+                    nix::unistd::Pid::from_raw(pid),
+                    nix::sys::signal::Signal::SIGKILL,
+                );
+            }
+        }
+        dumped.map_err(|err| {
+            anyhow::anyhow!(
+                "{err}: {}",
+                fs::read_to_string(image_path.join(CRIU_CHECKPOINT_LOG_FILE)).unwrap_or_default()
+            )
+        })?;
+
+        // the pages went to the page server, the rest of the images stayed
+        assert!(has_pages(&page_server_path)?);
+        assert!(!has_pages(&image_path)?);
+        assert!(image_path.join("inventory.img").exists());
+
+        Ok(())
+    }
+}
@@ -32,6 +32,7 @@ impl Container {
     /// # }
     /// ```
     pub fn delete(&mut self, force: bool) -> Result<(), LibcontainerError> {
+        let _lock = self.lock()?;
         self.refresh_status()?;
 
         tracing::debug!("container status: {:?}", self.status());
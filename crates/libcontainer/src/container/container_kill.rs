@@ -28,6 +28,7 @@ impl Container {
     /// # }
     /// ```
     pub fn kill<S: Into<Signal>>(&mut self, signal: S, all: bool) -> Result<(), LibcontainerError> {
+        let _lock = self.lock()?;
         self.refresh_status()?;
         match self.can_kill() {
             true => {
@@ -65,7 +66,13 @@ impl Container {
 
         tracing::debug!("kill signal {} to {}", signal, pid);
 
-        match signal::kill(pid, signal) {
+        let result = match self.open_init_process()? {
+            Some(process) => process.kill(signal),
+            // the process does not exist anymore, and its pid may belong to
+            // an unrelated process by now
+            None => Err(nix::errno::Errno::ESRCH),
+        };
+        match result {
             Ok(_) => {}
             Err(nix::errno::Errno::ESRCH) => {
                 // the process does not exist, which is what we want
@@ -25,6 +25,7 @@ impl Container {
     /// # }
     /// ```
     pub fn pause(&mut self) -> Result<(), LibcontainerError> {
+        let _lock = self.lock()?;
         self.refresh_status()?;
 
         if !self.can_pause() {
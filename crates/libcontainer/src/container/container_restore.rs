@@ -0,0 +1,330 @@
+use std::fs::{self, File};
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use chrono::Utc;
+use nix::mount::{mount, umount2, MntFlags, MsFlags};
+use oci_spec::runtime::Spec;
+use rust_criu::rust_criu_protobuf::rpc::Criu_notify;
+
+use super::container_checkpoint::{
+    criu_error, external_mounts, log_path, new_criu, DESCRIPTORS_JSON,
+};
+use super::{Container, ContainerLock, ContainerStatus, DEFAULT_LOCK_TIMEOUT};
+use crate::config::YoukiConfig;
+use crate::container::container::RestoreOptions;
+use crate::error::{LibcontainerError, MissingSpecError};
+use crate::tty;
+
+const CRIU_RESTORE_LOG_FILE: &str = "restore.log";
+const CRIU_ROOT: &str = "criu-root";
+
+/// The pid of the restored init process, which criu reports in the
+/// post-restore notification. The notification callback can't capture any
+/// state.
+static RESTORED_PID: AtomicI32 = AtomicI32::new(0);
+
+impl Container {
+    /// Restores a container from the images written by a previous checkpoint
+    ///
+    /// The container state directory is created at `container_root` and the
+    /// restored container is left in the `Running` state. The restored init
+    /// process becomes a child of the calling process.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use libcontainer::container::{Container, ManageCgroupsMode, RestoreOptions};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let opts = RestoreOptions {
+    ///     ext_unix_sk: false,
+    ///     file_locks: false,
+    ///     image_path: PathBuf::from("/var/lib/youki/checkpoint"),
+    ///     shell_job: false,
+    ///     tcp_established: false,
+    ///     work_path: None,
+    ///     manage_cgroups_mode: ManageCgroupsMode::Soft,
+    ///     console_socket: None,
+    ///     pid_file: None,
+    ///     use_systemd: false,
+    /// };
+    /// let container = Container::restore(
+    ///     "74f1a4cb3801",
+    ///     Path::new("/run/youki/74f1a4cb3801"),
+    ///     Path::new("/var/run/docker/bundle"),
+    ///     &opts,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn restore(
+        container_id: &str,
+        container_root: &Path,
+        bundle: &Path,
+        opts: &RestoreOptions,
+    ) -> Result<Self, LibcontainerError> {
+        if container_root.exists() {
+            tracing::error!(id = ?container_id, dir = ?container_root, "container already exists");
+            return Err(LibcontainerError::Exist);
+        }
+
+        fs::create_dir_all(container_root).map_err(|err| {
+            tracing::error!(
+                ?container_root,
+                "failed to create container directory: {}",
+                err
+            );
+            LibcontainerError::OtherIO(err)
+        })?;
+
+        let mut container = Container::new(
+            container_id,
+            ContainerStatus::Creating,
+            None,
+            bundle,
+            container_root,
+        )?;
+        container.set_systemd(opts.use_systemd);
+        let _lock = ContainerLock::acquire(&container.root, DEFAULT_LOCK_TIMEOUT)?;
+
+        if let Err(err) = container.do_restore(opts) {
+            tracing::error!(id = ?container_id, ?err, "failed to restore container");
+            if let Err(err) = fs::remove_dir_all(&container.root) {
+                tracing::warn!(container_root = ?container.root, ?err, "failed to delete container root");
+            }
+            return Err(err);
+        }
+
+        tracing::debug!("container {} restored", container.id());
+        Ok(container)
+    }
+
+    fn do_restore(&mut self, opts: &RestoreOptions) -> Result<(), LibcontainerError> {
+        let mut spec = Spec::load(self.bundle().join("config.json"))?;
+        spec.canonicalize_rootfs(self.bundle())?;
+        self.set_annotations(spec.annotations().clone())
+            .set_resources(
+                spec.linux()
+                    .as_ref()
+                    .and_then(|linux| linux.resources().clone()),
+            );
+
+        let config = YoukiConfig::from_spec(&spec, self.id())?;
+        config.save(&self.root).map_err(|err| {
+            tracing::error!(container_root = ?self.root, "failed to save config: {}", err);
+            err
+        })?;
+        self.save()?;
+
+        // CRIU requires the root of the restored process tree to be a mount
+        // point, so the rootfs is bind mounted into the container directory
+        // for the duration of the restore.
+        let rootfs = spec.root().as_ref().ok_or(MissingSpecError::Root)?.path();
+        let criu_root = self.root.join(CRIU_ROOT);
+        fs::create_dir(&criu_root).map_err(LibcontainerError::OtherIO)?;
+        mount(
+            Some(rootfs),
+            &criu_root,
+            None::<&str>,
+            MsFlags::MS_BIND | MsFlags::MS_REC,
+            None::<&str>,
+        )
+        .map_err(|err| {
+            tracing::error!(?rootfs, ?criu_root, ?err, "failed to bind mount rootfs");
+            LibcontainerError::OtherSyscall(err)
+        })?;
+
+        let result = self.run_criu_restore(&spec, &criu_root, opts);
+
+        if let Err(err) = umount2(&criu_root, MntFlags::MNT_DETACH) {
+            tracing::warn!(?criu_root, ?err, "failed to unmount criu root");
+        }
+        let _ = fs::remove_dir(&criu_root);
+        let pid = result?;
+
+        if let Some(pid_file) = &opts.pid_file {
+            fs::write(pid_file, format!("{pid}")).map_err(|err| {
+                tracing::error!("failed to write pid to file: {}", err);
+                LibcontainerError::OtherIO(err)
+            })?;
+        }
+
+        self.state.created = Some(Utc::now());
+        self.set_creator(nix::unistd::geteuid().as_raw())
+            .set_pid(pid)
+            .record_init_process_start()
+            .record_memory_cgroup()
+            .set_status(ContainerStatus::Running)
+            .save()?;
+
+        Ok(())
+    }
+
+    fn run_criu_restore(
+        &self,
+        spec: &Spec,
+        criu_root: &Path,
+        opts: &RestoreOptions,
+    ) -> Result<i32, LibcontainerError> {
+        let directory = File::open(&opts.image_path).map_err(|err| {
+            tracing::error!(path = ?opts.image_path, ?err, "failed to open criu image directory");
+            LibcontainerError::OtherIO(err)
+        })?;
+        let work_dir = opts
+            .work_path
+            .as_ref()
+            .map(File::open)
+            .transpose()
+            .map_err(LibcontainerError::OtherIO)?;
+        let mut criu = new_criu(
+            &directory,
+            work_dir.as_ref(),
+            CRIU_RESTORE_LOG_FILE,
+            external_mounts(spec, true)?,
+            opts.manage_cgroups_mode,
+        )?;
+        criu.set_root(criu_root.to_string_lossy().into_owned());
+        // Restore the process tree as a child of the caller instead of criu
+        // itself, which reports its pid in the post-restore notification.
+        criu.set_rst_sibling(true);
+        criu.set_notify_scripts(true);
+        criu.set_notify_cb(record_restored_pid);
+        criu.set_tcp_established(opts.tcp_established);
+        criu.set_ext_unix_sk(opts.ext_unix_sk);
+        criu.set_file_locks(opts.file_locks);
+
+        let terminal = spec
+            .process()
+            .as_ref()
+            .and_then(|process| process.terminal())
+            .unwrap_or(false);
+        let mut inherited = Vec::new();
+        let console_socket = match &opts.console_socket {
+            Some(console_socket) if terminal => {
+                // The master end of the terminal was held by the console
+                // socket, so criu creates a new pseudo terminal for the
+                // restored process and hands over its master end.
+                criu.set_shell_job(true);
+                criu.set_orphan_pts_master(true);
+                Some(tty::setup_console_socket(
+                    &self.root,
+                    console_socket,
+                    "console-socket",
+                )?)
+            }
+            _ => {
+                criu.set_shell_job(opts.shell_job);
+                // The stdio of the checkpointed process is recorded in
+                // descriptors.json. Pipes can't be restored by criu itself, so
+                // the stdio of the calling process is passed in their place.
+                // criu runs without the stdin of the caller, so it inherits
+                // duplicates which stay open until criu is done.
+                let descriptors = read_descriptors(&opts.image_path)?;
+                for (fd, key) in inherit_fds(&descriptors) {
+                    let fd = nix::unistd::dup(fd).map_err(LibcontainerError::OtherSyscall)?;
+                    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+                    criu.add_inherit_fd(fd.as_raw_fd(), key)
+                        .map_err(|err| criu_error("inherit stdio", err))?;
+                    inherited.push(fd);
+                }
+                None
+            }
+        };
+
+        RESTORED_PID.store(0, Ordering::SeqCst);
+        criu.restore().map_err(|err| {
+            let logfile = log_path(
+                &opts.image_path,
+                opts.work_path.as_deref(),
+                CRIU_RESTORE_LOG_FILE,
+            );
+            tracing::error!(?err, id = ?self.id(), ?logfile, "restoring container failed");
+            criu_error("restore", err)
+        })?;
+        drop(inherited);
+
+        let pid = RESTORED_PID.swap(0, Ordering::SeqCst);
+        if pid <= 0 {
+            return Err(LibcontainerError::Other(
+                "criu did not report the pid of the restored process".into(),
+            ));
+        }
+
+        if let Some(console_socket) = console_socket {
+            let master = criu.take_orphan_pts_master_fd().ok_or_else(|| {
+                LibcontainerError::Other("criu did not send the pty master".into())
+            })?;
+            tty::send_pty_master(console_socket, &master)?;
+        }
+
+        Ok(pid)
+    }
+}
+
+fn record_restored_pid(script: &str, notify: &Criu_notify, _fd: Option<RawFd>) -> i32 {
+    if script == "post-restore" {
+        RESTORED_PID.store(notify.pid(), Ordering::SeqCst);
+    }
+    0
+}
+
+fn read_descriptors(image_path: &Path) -> Result<Vec<String>, LibcontainerError> {
+    let descriptors_json_path: PathBuf = image_path.join(DESCRIPTORS_JSON);
+    let descriptors_json = fs::read_to_string(&descriptors_json_path).map_err(|err| {
+        tracing::error!(path = ?descriptors_json_path, ?err, "failed to read descriptors");
+        LibcontainerError::OtherIO(err)
+    })?;
+    serde_json::from_str(&descriptors_json).map_err(LibcontainerError::OtherSerialization)
+}
+
+/// Maps the stdio of the calling process onto the pipes the checkpointed
+/// process was using, as the fds criu has to inherit.
+fn inherit_fds(descriptors: &[String]) -> Vec<(RawFd, String)> {
+    descriptors
+        .iter()
+        .take(3)
+        .enumerate()
+        .filter(|(_, descriptor)| descriptor.starts_with("pipe:"))
+        .map(|(fd, descriptor)| (fd as RawFd, descriptor.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inherit_fds() {
+        let descriptors = vec![
+            "/dev/null".to_string(),
+            "pipe:[1234]".to_string(),
+            "pipe:[5678]".to_string(),
+        ];
+        assert_eq!(
+            inherit_fds(&descriptors),
+            vec![
+                (1, "pipe:[1234]".to_string()),
+                (2, "pipe:[5678]".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_descriptors() -> anyhow::Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        fs::write(
+            tmp_dir.path().join(DESCRIPTORS_JSON),
+            r#"["/dev/null","pipe:[1]","/dev/pts/0"]"#,
+        )?;
+        assert_eq!(
+            read_descriptors(tmp_dir.path())?,
+            vec!["/dev/null", "pipe:[1]", "/dev/pts/0"]
+        );
+
+        Ok(())
+    }
+}
@@ -25,6 +25,7 @@ impl Container {
     /// # }
     /// ```
     pub fn resume(&mut self) -> Result<(), LibcontainerError> {
+        let _lock = self.lock()?;
         self.refresh_status()?;
         // check if container can be resumed :
         // for example, a running process cannot be resumed
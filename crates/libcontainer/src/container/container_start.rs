@@ -5,6 +5,7 @@ use crate::config::YoukiConfig;
 use crate::error::LibcontainerError;
 use crate::hooks;
 use crate::notify_socket::{NotifySocket, NOTIFY_FILE};
+use crate::timing::{self, PhaseProcess};
 
 impl Container {
     /// Starts a previously created container
@@ -28,22 +29,26 @@ impl Container {
     /// # }
     /// ```
     pub fn start(&mut self) -> Result<(), LibcontainerError> {
-        self.refresh_status()?;
+        let _phase = timing::phase(PhaseProcess::Main, "start");
+        // The hooks run without the container lock, so they can operate on the
+        // container themselves, e.g. with youki state or youki kill.
+        let config = {
+            let _lock = self.lock()?;
+            self.refresh_status()?;
+            self.check_can_start()?;
 
-        if !self.can_start() {
-            tracing::error!(status = ?self.status(), id = ?self.id(), "cannot start container due to incorrect state");
-            return Err(LibcontainerError::IncorrectStatus);
-        }
-
-        let config = YoukiConfig::load(&self.root).map_err(|err| {
-            tracing::error!(
-                "failed to load runtime spec for container {}: {}",
-                self.id(),
+            YoukiConfig::load(&self.root).map_err(|err| {
+                tracing::error!(
+                    "failed to load runtime spec for container {}: {}",
+                    self.id(),
+                    err
+                );
                 err
-            );
-            err
-        })?;
+            })?
+        };
+
         if let Some(hooks) = config.hooks.as_ref() {
+            let _phase = timing::phase(PhaseProcess::Main, "prestart_hooks");
             // While prestart is marked as deprecated in the OCI spec, the docker and integration test still
             // uses it.
             #[allow(deprecated)]
@@ -57,18 +62,27 @@ impl Container {
             })?;
         }
 
-        let mut notify_socket = NotifySocket::new(self.root.join(NOTIFY_FILE));
-        notify_socket.notify_container_start()?;
-        self.set_status(ContainerStatus::Running)
-            .save()
-            .map_err(|err| {
-                tracing::error!(id = ?self.id(), ?err, "failed to save state for container");
-                err
-            })?;
+        {
+            let _lock = self.lock()?;
+            // the container may have been stopped or started by another
+            // process while the prestart hooks ran
+            self.refresh_status()?;
+            self.check_can_start()?;
+
+            let mut notify_socket = NotifySocket::new(self.root.join(NOTIFY_FILE));
+            notify_socket.notify_container_start()?;
+            self.set_status(ContainerStatus::Running)
+                .save()
+                .map_err(|err| {
+                    tracing::error!(id = ?self.id(), ?err, "failed to save state for container");
+                    err
+                })?;
+        }
 
         // Run post start hooks. It runs after the container process is started.
         // It is called in the runtime namespace.
         if let Some(hooks) = config.hooks.as_ref() {
+            let _phase = timing::phase(PhaseProcess::Main, "poststart_hooks");
             hooks::run_hooks(hooks.poststart().as_ref(), Some(self), Some(&self.root)).map_err(
                 |err| {
                     tracing::error!("failed to run post start hooks: {}", err);
@@ -79,4 +93,81 @@ impl Container {
 
         Ok(())
     }
+
+    fn check_can_start(&self) -> Result<(), LibcontainerError> {
+        if !self.can_start() {
+            tracing::error!(status = ?self.status(), id = ?self.id(), "cannot start container due to incorrect state");
+            return Err(LibcontainerError::IncorrectStatus);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    use anyhow::Result;
+    use oci_spec::runtime::{HookBuilder, HooksBuilder};
+    use serial_test::serial;
+
+    use super::*;
+    use crate::notify_socket::NotifyListener;
+
+    #[test]
+    #[serial]
+    fn test_hooks_run_without_lock() -> Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        let root = tmp_dir.path();
+        // each hook creates its file only if it can take the container lock
+        let hook = |name: &str| {
+            HookBuilder::default()
+                .path("/bin/sh")
+                .args(vec![
+                    "sh".to_string(),
+                    "-c".to_string(),
+                    format!(
+                        "flock -n {} touch {}",
+                        root.join("state.lock").display(),
+                        root.join(name).display()
+                    ),
+                ])
+                .build()
+        };
+        YoukiConfig {
+            hooks: Some(
+                HooksBuilder::default()
+                    .prestart(vec![hook("prestart")?])
+                    .poststart(vec![hook("poststart")?])
+                    .build()?,
+            ),
+            cgroup_path: PathBuf::new(),
+            intel_rdt_group: None,
+        }
+        .save(root)?;
+        let listener = NotifyListener::new(&root.join(NOTIFY_FILE))?;
+
+        let mut init = Command::new("sleep").arg("10").spawn()?;
+        let mut container = Container::new(
+            "container",
+            ContainerStatus::Created,
+            Some(init.id() as i32),
+            root,
+            root,
+        )?;
+        container.save()?;
+        let started = container.start();
+        listener.wait_for_container_start()?;
+        init.kill()?;
+        init.wait()?;
+
+        started?;
+        assert_eq!(container.status(), ContainerStatus::Running);
+        assert!(root.join("prestart").exists());
+        assert!(root.join("poststart").exists());
+
+        Ok(())
+    }
 }
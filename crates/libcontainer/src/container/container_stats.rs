@@ -0,0 +1,427 @@
+use std::time::{Duration, Instant};
+
+use libcgroups::common::{AnyCgroupManager, CgroupManager};
+use libcgroups::events::{CgroupEvent, Events};
+use libcgroups::stats::{BlkioStats, Stats};
+use nix::unistd::Pid;
+use procfs::process::Process;
+use procfs::ProcError;
+use serde::Serialize;
+
+use super::{Container, ContainerStatus};
+use crate::error::LibcontainerError;
+use crate::process::intel_rdt::{self, IntelRdtStats};
+
+/// The resource usage of a container
+#[derive(Debug, Serialize)]
+pub struct ContainerStats {
+    /// Statistics of the cgroup of the container
+    pub cgroup: Stats,
+    /// The processes of the container
+    pub processes: Vec<ProcessStats>,
+    /// Usage of the Intel RDT resources, if the container has a resctrl group
+    pub intel_rdt: Option<IntelRdtStats>,
+}
+
+/// The resource usage of a single process of a container
+#[derive(Debug, Serialize)]
+pub struct ProcessStats {
+    pub pid: i32,
+    /// The name of the executable
+    pub command: String,
+    /// Number of threads
+    pub threads: i64,
+    /// Resident memory in bytes
+    pub rss: u64,
+    /// Cpu time consumed in user and kernel mode in nanoseconds
+    pub cpu_usage: u64,
+}
+
+/// The change of the stats compared to the previous ones
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct StatsDelta {
+    /// Time since the previous stats
+    pub elapsed: Duration,
+    /// Cpu usage in percent of a single cpu, so with several cpus it can go
+    /// above 100
+    pub cpu_percent: f64,
+    /// Bytes read from block devices per second
+    pub io_read_bytes_per_second: f64,
+    /// Bytes written to block devices per second
+    pub io_write_bytes_per_second: f64,
+}
+
+/// An item of a [`StatsStream`]
+#[derive(Debug)]
+pub enum StatsEvent {
+    /// The stats collected every interval. The first ones have no delta.
+    Stats {
+        stats: Box<ContainerStats>,
+        delta: Option<StatsDelta>,
+    },
+    /// An event of the cgroup of the container, like an OOM kill, as the
+    /// kernel reports it
+    Cgroup(CgroupEvent),
+}
+
+/// The stats of a container every interval with the events of its cgroup in
+/// between, which ends once the container is gone
+pub struct StatsStream {
+    collector: StatsCollector,
+    // None if the cgroup manager can't report events, then only the stats are
+    // collected
+    events: Option<Events>,
+    interval: Option<Duration>,
+    next_stats: Instant,
+    previous: Option<Counters>,
+}
+
+impl Iterator for StatsStream {
+    type Item = Result<StatsEvent, LibcontainerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.events.as_ref().map_or(false, Events::closed) {
+                // the cgroup is gone along with the container
+                return None;
+            }
+
+            let timeout = match self.interval {
+                Some(interval) => {
+                    let now = Instant::now();
+                    if now >= self.next_stats {
+                        self.next_stats = now + interval;
+                        let stats = self.collect_stats(now);
+                        // without events the container is gone once its
+                        // cgroup has no processes left
+                        if self.events.is_none() && stats.as_ref().map_or(false, is_empty) {
+                            return None;
+                        }
+                        return Some(stats);
+                    }
+                    Some(self.next_stats - now)
+                }
+                None => None,
+            };
+            match &mut self.events {
+                Some(events) => match events.wait(timeout) {
+                    Ok(Some(event)) => return Some(Ok(StatsEvent::Cgroup(event))),
+                    Ok(None) => {}
+                    Err(err) => return Some(Err(err.into())),
+                },
+                // there is always an interval without events
+                None => std::thread::sleep(timeout.unwrap_or_default()),
+            }
+        }
+    }
+}
+
+impl StatsStream {
+    fn collect_stats(&mut self, now: Instant) -> Result<StatsEvent, LibcontainerError> {
+        let stats = self.collector.collect()?;
+        let counters = Counters::new(now, &stats.cgroup);
+        let delta = self
+            .previous
+            .replace(counters)
+            .map(|previous| StatsDelta::new(&previous, &counters));
+
+        Ok(StatsEvent::Stats {
+            stats: Box::new(stats),
+            delta,
+        })
+    }
+}
+
+fn is_empty(event: &StatsEvent) -> bool {
+    match event {
+        StatsEvent::Stats { stats, .. } => stats.processes.is_empty(),
+        StatsEvent::Cgroup(_) => false,
+    }
+}
+
+impl StatsDelta {
+    fn new(previous: &Counters, current: &Counters) -> Self {
+        let elapsed = current.at.saturating_duration_since(previous.at);
+        let seconds = elapsed.as_secs_f64();
+        // the counters start over if the cgroup is recreated
+        let per_second = |previous: u64, current: u64| {
+            if seconds > 0.0 {
+                current.saturating_sub(previous) as f64 / seconds
+            } else {
+                0.0
+            }
+        };
+
+        Self {
+            elapsed,
+            cpu_percent: per_second(previous.cpu_usage, current.cpu_usage) / 1e9 * 100.0,
+            io_read_bytes_per_second: per_second(previous.io_read_bytes, current.io_read_bytes),
+            io_write_bytes_per_second: per_second(previous.io_write_bytes, current.io_write_bytes),
+        }
+    }
+}
+
+/// The values of the stats the deltas are computed of
+#[derive(Debug, Clone, Copy)]
+struct Counters {
+    at: Instant,
+    cpu_usage: u64,
+    io_read_bytes: u64,
+    io_write_bytes: u64,
+}
+
+impl Counters {
+    fn new(at: Instant, stats: &Stats) -> Self {
+        Self {
+            at,
+            cpu_usage: stats.cpu.usage.usage_total,
+            io_read_bytes: io_bytes(&stats.blkio, "read"),
+            io_write_bytes: io_bytes(&stats.blkio, "write"),
+        }
+    }
+}
+
+/// Sums up the bytes of an operation over all devices. v1 capitalizes the
+/// operations, v2 doesn't.
+fn io_bytes(blkio: &BlkioStats, op: &str) -> u64 {
+    blkio
+        .service_bytes
+        .iter()
+        .filter(|stat| {
+            stat.op_type
+                .as_ref()
+                .map_or(false, |op_type| op_type.eq_ignore_ascii_case(op))
+        })
+        .map(|stat| stat.value)
+        .sum()
+}
+
+/// Everything needed to collect the stats of a container
+struct StatsCollector {
+    cgroup_manager: AnyCgroupManager,
+    intel_rdt_group: Option<String>,
+}
+
+impl StatsCollector {
+    fn collect(&self) -> Result<ContainerStats, LibcontainerError> {
+        let cgroup = self.cgroup_manager.stats()?;
+        let processes = self
+            .cgroup_manager
+            .get_all_pids()?
+            .into_iter()
+            .map(process_stats)
+            .filter_map(Result::transpose)
+            .collect::<Result<_, _>>()?;
+        let intel_rdt = self
+            .intel_rdt_group
+            .as_deref()
+            .map(intel_rdt::intel_rdt_stats)
+            .transpose()?;
+
+        Ok(ContainerStats {
+            cgroup,
+            processes,
+            intel_rdt,
+        })
+    }
+}
+
+/// Reads the stats of a process, returning `None` if it exited in the
+/// meantime
+fn process_stats(pid: Pid) -> Result<Option<ProcessStats>, LibcontainerError> {
+    let stat = match Process::new(pid.as_raw()).and_then(|process| process.stat()) {
+        Ok(stat) => stat,
+        Err(ProcError::NotFound(_)) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(Some(ProcessStats {
+        pid: stat.pid,
+        rss: stat.rss * procfs::page_size(),
+        cpu_usage: (stat.utime + stat.stime) * 1_000_000_000 / procfs::ticks_per_second(),
+        threads: stat.num_threads,
+        command: stat.comm,
+    }))
+}
+
+impl Container {
+    /// Collects the resource usage of the container: the stats of its
+    /// cgroup, its processes and the Intel RDT monitoring data
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use libcontainer::container::builder::ContainerBuilder;
+    /// use libcontainer::syscall::syscall::SyscallType;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut container = ContainerBuilder::new(
+    ///     "74f1a4cb3801".to_owned(),
+    ///     SyscallType::default(),
+    /// )
+    /// .as_init("/var/run/docker/bundle")
+    /// .build()?;
+    ///
+    /// let stats = container.stats()?;
+    /// println!("{} bytes of memory", stats.cgroup.memory.memory.usage);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stats(&mut self) -> Result<ContainerStats, LibcontainerError> {
+        self.stats_collector()?.collect()
+    }
+
+    /// Collects the stats of the container every interval along with the
+    /// changes since the previous ones, and reports the events of its cgroup
+    /// as they happen. An interval of zero only reports the events. If the
+    /// cgroup manager can't report events, e.g. without access to the
+    /// cgroup files or dbus, only the stats are collected. The stream ends
+    /// once the container is gone.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use libcontainer::container::builder::ContainerBuilder;
+    /// use libcontainer::container::StatsEvent;
+    /// use libcontainer::syscall::syscall::SyscallType;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut container = ContainerBuilder::new(
+    ///     "74f1a4cb3801".to_owned(),
+    ///     SyscallType::default(),
+    /// )
+    /// .as_init("/var/run/docker/bundle")
+    /// .build()?;
+    ///
+    /// for event in container.stats_stream(Duration::from_secs(5))? {
+    ///     if let StatsEvent::Stats { delta: Some(delta), .. } = event? {
+    ///         println!("{:.1}% cpu", delta.cpu_percent);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stats_stream(&mut self, interval: Duration) -> Result<StatsStream, LibcontainerError> {
+        let collector = self.stats_collector()?;
+        let events = match collector.cgroup_manager.events() {
+            Ok(events) => Some(events),
+            Err(err) if !interval.is_zero() => {
+                tracing::warn!(id = ?self.id(), ?err, "cgroup events are not available, only collecting stats");
+                None
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(StatsStream {
+            collector,
+            events,
+            interval: (!interval.is_zero()).then_some(interval),
+            next_stats: Instant::now(),
+            previous: None,
+        })
+    }
+
+    /// Prints the stats of the cgroup of the container as JSON, once if
+    /// `stats` is set and otherwise every `interval` seconds
+    #[deprecated(note = "use Container::stats or Container::stats_stream")]
+    pub fn events(&mut self, interval: u32, stats: bool) -> Result<(), LibcontainerError> {
+        loop {
+            let container_stats = self.stats()?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&container_stats.cgroup)
+                    .map_err(LibcontainerError::OtherSerialization)?
+            );
+            if stats {
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_secs(interval as u64));
+        }
+    }
+
+    fn stats_collector(&mut self) -> Result<StatsCollector, LibcontainerError> {
+        self.refresh_status()?;
+        if !self.state.status.eq(&ContainerStatus::Running) {
+            tracing::error!(id = ?self.id(), status = ?self.state.status, "container is not running");
+            return Err(LibcontainerError::IncorrectStatus);
+        }
+
+        let config = self.spec()?;
+        let cgroup_manager =
+            libcgroups::common::create_cgroup_manager(libcgroups::common::CgroupConfig {
+                cgroup_path: config.cgroup_path,
+                systemd_cgroup: self.systemd(),
+                container_name: self.id().to_string(),
+            })?;
+
+        Ok(StatsCollector {
+            cgroup_manager,
+            intel_rdt_group: config.intel_rdt_group,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libcgroups::stats::BlkioDeviceStat;
+
+    use super::*;
+
+    #[test]
+    fn test_stats_delta() {
+        let device_stat = |op_type: &str, value| BlkioDeviceStat {
+            major: 8,
+            minor: 0,
+            op_type: Some(op_type.to_owned()),
+            value,
+        };
+        let mut stats = Stats::default();
+        stats.cpu.usage.usage_total = 1_000_000_000;
+        stats.blkio.service_bytes = vec![device_stat("Read", 1000), device_stat("Write", 0)];
+        let start = Instant::now();
+        let previous = Counters::new(start, &stats);
+
+        stats.cpu.usage.usage_total = 4_000_000_000;
+        stats.blkio.service_bytes = vec![
+            device_stat("Read", 3000),
+            device_stat("Write", 4000),
+            device_stat("Total", 7000),
+        ];
+        let current = Counters::new(start + Duration::from_secs(2), &stats);
+
+        assert_eq!(
+            StatsDelta::new(&previous, &current),
+            StatsDelta {
+                elapsed: Duration::from_secs(2),
+                cpu_percent: 150.0,
+                io_read_bytes_per_second: 1000.0,
+                io_write_bytes_per_second: 2000.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_io_bytes() {
+        let blkio = BlkioStats {
+            service_bytes: vec![
+                BlkioDeviceStat {
+                    major: 8,
+                    minor: 0,
+                    op_type: Some("read".to_owned()),
+                    value: 1,
+                },
+                BlkioDeviceStat {
+                    major: 8,
+                    minor: 16,
+                    op_type: Some("read".to_owned()),
+                    value: 2,
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(io_bytes(&blkio, "read"), 3);
+        assert_eq!(io_bytes(&blkio, "write"), 0);
+    }
+}
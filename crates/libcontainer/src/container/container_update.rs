@@ -0,0 +1,209 @@
+use libcgroups::common::{CgroupManager, ControllerOpt};
+use oci_spec::runtime::LinuxResources;
+
+use super::{Container, ContainerStatus};
+use crate::error::LibcontainerError;
+use crate::process::intel_rdt;
+
+impl Container {
+    /// Updates the resource limits of the container
+    ///
+    /// The given resources replace the ones recorded in the container state,
+    /// so they have to contain every limit the container should keep.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use libcontainer::container::builder::ContainerBuilder;
+    /// use libcontainer::oci_spec::runtime::{LinuxPidsBuilder, LinuxResourcesBuilder};
+    /// use libcontainer::syscall::syscall::SyscallType;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut container = ContainerBuilder::new(
+    ///     "74f1a4cb3801".to_owned(),
+    ///     SyscallType::default(),
+    /// )
+    /// .as_init("/var/run/docker/bundle")
+    /// .build()?;
+    ///
+    /// let resources = LinuxResourcesBuilder::default()
+    ///     .pids(LinuxPidsBuilder::default().limit(100).build()?)
+    ///     .build()?;
+    /// container.update(&resources)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update(&mut self, resources: &LinuxResources) -> Result<(), LibcontainerError> {
+        let _lock = self.lock()?;
+        self.check_can_update()?;
+
+        validate_resources(resources)?;
+
+        let cmanager =
+            libcgroups::common::create_cgroup_manager(libcgroups::common::CgroupConfig {
+                cgroup_path: self.spec()?.cgroup_path,
+                systemd_cgroup: self.systemd(),
+                container_name: self.id().to_string(),
+            })?;
+        cmanager.apply(&ControllerOpt {
+            resources,
+            disable_oom_killer: false,
+            oom_score_adj: None,
+            freezer_state: None,
+        })?;
+
+        self.set_resources(Some(resources.clone())).save()?;
+
+        tracing::debug!("container {} updated", self.id());
+        Ok(())
+    }
+
+    /// Updates the Intel RDT schemas of the container
+    ///
+    /// The schemas are written to the resctrl group the container was created
+    /// with. A schema that is not given keeps the value recorded in the
+    /// container state.
+    pub fn update_intel_rdt(
+        &mut self,
+        l3_cache_schema: Option<String>,
+        mem_bw_schema: Option<String>,
+    ) -> Result<(), LibcontainerError> {
+        let _lock = self.lock()?;
+        self.check_can_update()?;
+
+        let group = self.spec()?.intel_rdt_group.ok_or_else(|| {
+            LibcontainerError::InvalidInput(format!(
+                "container {} has no Intel RDT resctrl group",
+                self.id()
+            ))
+        })?;
+
+        let mut intel_rdt = self.intel_rdt().cloned().unwrap_or_default();
+        if l3_cache_schema.is_some() {
+            intel_rdt.set_l3_cache_schema(l3_cache_schema);
+        }
+        if mem_bw_schema.is_some() {
+            intel_rdt.set_mem_bw_schema(mem_bw_schema);
+        }
+
+        intel_rdt::update_intel_rdt(
+            &group,
+            intel_rdt.l3_cache_schema(),
+            intel_rdt.mem_bw_schema(),
+        )?;
+
+        self.set_intel_rdt(Some(intel_rdt)).save()?;
+
+        tracing::debug!("intel rdt of container {} updated", self.id());
+        Ok(())
+    }
+
+    fn check_can_update(&mut self) -> Result<(), LibcontainerError> {
+        self.refresh_status()?;
+
+        if !matches!(
+            self.status(),
+            ContainerStatus::Created | ContainerStatus::Running | ContainerStatus::Paused
+        ) {
+            tracing::error!(status = ?self.status(), id = ?self.id(), "cannot update container");
+            return Err(LibcontainerError::IncorrectStatus);
+        }
+
+        Ok(())
+    }
+}
+
+/// Rejects resource combinations the cgroup managers would only partially
+/// apply before failing.
+fn validate_resources(resources: &LinuxResources) -> Result<(), LibcontainerError> {
+    if let Some(memory) = resources.memory() {
+        // swap is the limit of memory and swap together, so it can't be
+        // lower than the memory limit
+        if let (Some(limit @ 1..=i64::MAX), Some(swap @ 0..=i64::MAX)) =
+            (memory.limit(), memory.swap())
+        {
+            if swap < limit {
+                return Err(LibcontainerError::InvalidInput(format!(
+                    "memory swap limit {swap} is lower than memory limit {limit}"
+                )));
+            }
+        }
+
+        if let (Some(limit @ 1..=i64::MAX), Some(reservation @ 1..=i64::MAX)) =
+            (memory.limit(), memory.reservation())
+        {
+            if reservation > limit {
+                return Err(LibcontainerError::InvalidInput(format!(
+                    "memory reservation {reservation} is higher than memory limit {limit}"
+                )));
+            }
+        }
+    }
+
+    if let Some(cpu) = resources.cpu() {
+        if let (Some(quota @ 1..=i64::MAX), Some(0)) = (cpu.quota(), cpu.period()) {
+            return Err(LibcontainerError::InvalidInput(format!(
+                "cpu quota {quota} requires a non zero cpu period"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use oci_spec::runtime::{LinuxCpuBuilder, LinuxMemoryBuilder, LinuxResourcesBuilder};
+
+    use super::*;
+
+    fn memory(limit: i64, swap: i64, reservation: i64) -> Result<LinuxResources> {
+        Ok(LinuxResourcesBuilder::default()
+            .memory(
+                LinuxMemoryBuilder::default()
+                    .limit(limit)
+                    .swap(swap)
+                    .reservation(reservation)
+                    .build()?,
+            )
+            .build()?)
+    }
+
+    #[test]
+    fn test_validate_memory() -> Result<()> {
+        assert!(validate_resources(&memory(1024, 2048, 512)?).is_ok());
+        assert!(validate_resources(&memory(1024, 1024, 1024)?).is_ok());
+        assert!(validate_resources(&memory(1024, -1, 512)?).is_ok());
+        assert!(validate_resources(&memory(-1, 2048, 512)?).is_ok());
+        assert!(validate_resources(&memory(2048, 1024, 512)?).is_err());
+        assert!(validate_resources(&memory(1024, 2048, 4096)?).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_cpu() -> Result<()> {
+        let resources = LinuxResourcesBuilder::default()
+            .cpu(
+                LinuxCpuBuilder::default()
+                    .quota(50000)
+                    .period(0u64)
+                    .build()?,
+            )
+            .build()?;
+        assert!(validate_resources(&resources).is_err());
+
+        let resources = LinuxResourcesBuilder::default()
+            .cpu(
+                LinuxCpuBuilder::default()
+                    .quota(50000)
+                    .period(100000u64)
+                    .build()?,
+            )
+            .build()?;
+        assert!(validate_resources(&resources).is_ok());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+use std::os::fd::AsFd;
+use std::time::{Duration, Instant};
+
+use nix::errno::Errno;
+use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+use nix::sys::wait::{waitid, Id, WaitPidFlag, WaitStatus};
+use nix::unistd::Pid;
+use procfs::process::{ProcState, Process};
+
+use super::container::InitProcess;
+use super::{Container, ContainerStatus};
+use crate::error::LibcontainerError;
+use crate::pidfd::PidFd;
+
+/// Exit status of the container init process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// The process exited with the given code
+    Exited(i32),
+    /// The process was killed by the given signal number
+    Signaled(i32),
+    /// The process exited, but it was reaped by its parent before its exit
+    /// status could be read
+    Unknown,
+}
+
+impl ExitStatus {
+    /// Exit code following the shell convention, where a process killed by a
+    /// signal exits with 128 plus the signal number
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            Self::Exited(code) => Some(*code),
+            Self::Signaled(signal) => Some(128 + signal),
+            Self::Unknown => None,
+        }
+    }
+}
+
+impl Container {
+    /// Waits for the container init process to exit and returns its exit status
+    ///
+    /// Without a timeout this blocks until the process exits, otherwise `None`
+    /// is returned if the process is still running once the timeout elapsed.
+    /// The exit status can only be read if the caller is the parent of the
+    /// init process or if its parent didn't reap it yet. Once reaped, it is
+    /// only known if the parent recorded it in the container state, as
+    /// `youki run` does with [`Container::set_exit_status`]. Otherwise the
+    /// status is [`ExitStatus::Unknown`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use libcontainer::container::builder::ContainerBuilder;
+    /// use libcontainer::syscall::syscall::SyscallType;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut container = ContainerBuilder::new(
+    ///     "74f1a4cb3801".to_owned(),
+    ///     SyscallType::default(),
+    /// )
+    /// .as_init("/var/run/docker/bundle")
+    /// .build()?;
+    ///
+    /// container.start()?;
+    /// let status = container.wait(Some(Duration::from_secs(10)))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wait(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<Option<ExitStatus>, LibcontainerError> {
+        self.refresh_status()?;
+        if self.status() == ContainerStatus::Stopped {
+            return Ok(Some(self.recorded_exit_status()));
+        }
+
+        let pidfd = match self.open_init_process()? {
+            Some(InitProcess {
+                pidfd: Some(pidfd), ..
+            }) => pidfd,
+            Some(_) => {
+                tracing::error!(id = ?self.id(), "pidfds are not supported by the kernel");
+                return Err(LibcontainerError::OtherSyscall(Errno::ENOSYS));
+            }
+            // the process exited after its status was refreshed
+            None => return self.finish_wait(None).map(Some),
+        };
+
+        if !wait_for_exit(&pidfd, timeout)? {
+            return Ok(None);
+        }
+
+        let status = match waitid(Id::PIDFd(pidfd.as_fd()), WaitPidFlag::WEXITED) {
+            Ok(status) => Some(status),
+            // not a child of the caller, so only its parent can reap it
+            Err(Errno::ECHILD) => self.pid().and_then(|pid| self.read_exit_status(pid)),
+            Err(err) => {
+                tracing::error!(id = ?self.id(), ?err, "failed to wait for container process");
+                return Err(LibcontainerError::OtherSyscall(err));
+            }
+        };
+
+        self.finish_wait(status).map(Some)
+    }
+
+    fn finish_wait(&mut self, status: Option<WaitStatus>) -> Result<ExitStatus, LibcontainerError> {
+        let _lock = self.lock()?;
+        // the parent of the init process may have recorded its exit already
+        if self.finished_at().is_none() {
+            match status {
+                Some(status) => {
+                    self.set_exit_status(status)
+                        .set_status(ContainerStatus::Stopped);
+                }
+                None => self.refresh_status()?,
+            }
+            self.save()?;
+        }
+
+        Ok(self.recorded_exit_status())
+    }
+
+    /// Reads the exit status of an init process the caller can't reap. It is
+    /// kept while the process is a zombie, until its parent reaps it.
+    fn read_exit_status(&self, pid: Pid) -> Option<WaitStatus> {
+        let stat = Process::new(pid.as_raw())
+            .and_then(|proc| proc.stat())
+            .ok()?;
+        if !self.is_init_process(&stat) || stat.state().ok()? != ProcState::Zombie {
+            return None;
+        }
+
+        stat.exit_code
+            .and_then(|status| WaitStatus::from_raw(pid, status).ok())
+    }
+
+    fn recorded_exit_status(&self) -> ExitStatus {
+        match (self.exit_code(), self.exit_signal()) {
+            (Some(code), _) => ExitStatus::Exited(code),
+            (None, Some(signal)) => ExitStatus::Signaled(signal),
+            (None, None) => ExitStatus::Unknown,
+        }
+    }
+}
+
+/// Polls the pidfd, which becomes readable once the process exited. Returns
+/// false if the timeout elapsed first.
+fn wait_for_exit(pidfd: &PidFd, timeout: Option<Duration>) -> Result<bool, LibcontainerError> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    loop {
+        let timeout = match deadline {
+            Some(deadline) => {
+                PollTimeout::try_from(deadline.saturating_duration_since(Instant::now()))
+                    .unwrap_or(PollTimeout::MAX)
+            }
+            None => PollTimeout::NONE,
+        };
+        let mut fds = [PollFd::new(pidfd.as_fd(), PollFlags::POLLIN)];
+        match poll(&mut fds, timeout) {
+            Ok(0) if deadline.map_or(false, |deadline| Instant::now() >= deadline) => {
+                return Ok(false)
+            }
+            Ok(0) | Err(Errno::EINTR) => continue,
+            Ok(_) => return Ok(true),
+            Err(err) => return Err(LibcontainerError::OtherSyscall(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    use anyhow::Result;
+    use nix::sys::signal::{kill, Signal};
+    use nix::sys::wait::waitpid;
+    use nix::unistd::{fork, ForkResult};
+
+    use super::*;
+
+    fn spawn(exit_code: Option<i32>) -> Result<Pid> {
+        match unsafe { fork()? } {
+            ForkResult::Child => match exit_code {
+                Some(code) => std::process::exit(code),
+                None => loop {
+                    nix::unistd::pause();
+                },
+            },
+            ForkResult::Parent { child } => Ok(child),
+        }
+    }
+
+    fn container(pid: Pid, root: &std::path::Path) -> Result<Container> {
+        let mut container = Container::default();
+        container.root = root.to_path_buf();
+        container
+            .set_pid(pid.as_raw())
+            .set_status(ContainerStatus::Running)
+            .save()?;
+        Ok(container)
+    }
+
+    #[test]
+    fn test_wait_exited() -> Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        let mut container = container(spawn(Some(7))?, tmp_dir.path())?;
+
+        assert_eq!(container.wait(None)?, Some(ExitStatus::Exited(7)));
+        assert_eq!(container.status(), ContainerStatus::Stopped);
+        assert_eq!(container.exit_code(), Some(7));
+        assert!(container.finished_at().is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wait_timeout() -> Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        let child = spawn(None)?;
+        let mut container = container(child, tmp_dir.path())?;
+
+        assert_eq!(container.wait(Some(Duration::from_millis(10)))?, None);
+        assert_eq!(container.status(), ContainerStatus::Running);
+
+        kill(child, Signal::SIGKILL)?;
+        let status = container.wait(Some(Duration::from_secs(10)))?;
+        assert_eq!(status, Some(ExitStatus::Signaled(Signal::SIGKILL as i32)));
+        assert_eq!(status.and_then(|status| status.code()), Some(137));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wait_not_a_child() -> Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        // the parent of the process that is waited for never reaps it
+        let mut parent = Command::new("sh")
+            .args(["-c", "sh -c 'sleep 0.2; exit 5' & echo $!; exec sleep 10"])
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let mut line = String::new();
+        BufReader::new(parent.stdout.take().unwrap()).read_line(&mut line)?;
+        let pid = Pid::from_raw(line.trim().parse()?);
+        let mut container = container(pid, tmp_dir.path())?;
+
+        let status = container.wait(None);
+        parent.kill()?;
+        parent.wait()?;
+
+        assert_eq!(status?, Some(ExitStatus::Exited(5)));
+        let saved = Container::load(tmp_dir.path().to_owned())?;
+        assert_eq!(saved.exit_code(), Some(5));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wait_recorded_by_parent() -> Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        let child = spawn(Some(3))?;
+        let mut container = container(child, tmp_dir.path())?;
+
+        // what `youki run` does after reaping the process
+        waitpid(child, None)?;
+        container
+            .set_exit_status(WaitStatus::Exited(child, 3))
+            .save()?;
+
+        let mut container = Container::load(tmp_dir.path().to_owned())?;
+        assert_eq!(container.wait(None)?, Some(ExitStatus::Exited(3)));
+
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
-use oci_spec::runtime::Spec;
+use oci_spec::runtime::{LinuxNamespaceType, Spec};
 use user_ns::UserNamespaceConfig;
 
 use super::builder::ContainerBuilder;
@@ -22,6 +22,7 @@ pub struct InitContainerBuilder {
     detached: bool,
     no_pivot: bool,
     as_sibling: bool,
+    no_new_keyring: bool,
 }
 
 impl InitContainerBuilder {
@@ -35,6 +36,7 @@ impl InitContainerBuilder {
             detached: true,
             no_pivot: false,
             as_sibling: false,
+            no_new_keyring: false,
         }
     }
 
@@ -61,6 +63,13 @@ impl InitContainerBuilder {
         self
     }
 
+    /// Sets if the container should keep the session keyring of the calling
+    /// process instead of joining a new one named after the container
+    pub fn with_no_new_keyring(mut self, no_new_keyring: bool) -> Self {
+        self.no_new_keyring = no_new_keyring;
+        self
+    }
+
     /// Creates a new container
     pub fn build(self) -> Result<Container, LibcontainerError> {
         let spec = self.load_spec()?;
@@ -69,7 +78,12 @@ impl InitContainerBuilder {
         let mut container = self.create_container_state(&container_dir)?;
         container
             .set_systemd(self.use_systemd)
-            .set_annotations(spec.annotations().clone());
+            .set_annotations(spec.annotations().clone())
+            .set_resources(
+                spec.linux()
+                    .as_ref()
+                    .and_then(|linux| linux.resources().clone()),
+            );
 
         let notify_path = container_dir.join(NOTIFY_FILE);
         // convert path of root file system of the container to absolute path
@@ -116,6 +130,7 @@ impl InitContainerBuilder {
             stdout: self.base.stdout,
             stderr: self.base.stderr,
             as_sibling: self.as_sibling,
+            no_new_keyring: self.no_new_keyring,
         };
 
         builder_impl.create()?;
@@ -200,6 +215,22 @@ impl InitContainerBuilder {
             }
         }
 
+        if let Some(linux) = spec.linux() {
+            // Offsets can only be set on a time namespace created for the
+            // container, not on one joined by path.
+            if linux.time_offsets().is_some() {
+                let new_time_ns = linux.namespaces().as_ref().map_or(false, |namespaces| {
+                    namespaces
+                        .iter()
+                        .any(|ns| ns.typ() == LinuxNamespaceType::Time && ns.path().is_none())
+                });
+                if !new_time_ns {
+                    tracing::error!("time offsets are specified without a new time namespace");
+                    Err(ErrInvalidSpec::TimeOffsets)?;
+                }
+            }
+        }
+
         utils::validate_spec_for_new_user_ns(spec)?;
 
         Ok(())
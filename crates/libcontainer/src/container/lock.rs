@@ -0,0 +1,94 @@
+//! Advisory lock serializing the operations of concurrent youki processes on
+//! the same container
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use nix::errno::Errno;
+use nix::fcntl::{Flock, FlockArg};
+
+/// Time to wait for another process to release the lock by default
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+const LOCK_FILE: &str = "state.lock";
+const RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum LockError {
+    #[error("failed to open lock file {path:?}")]
+    Open {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to lock {path:?}")]
+    Lock { path: PathBuf, source: Errno },
+    #[error("timed out after {timeout:?} waiting for {path:?}, the container is in use by another process")]
+    Timeout { path: PathBuf, timeout: Duration },
+}
+
+/// Exclusive lock of a container, released when dropped
+#[derive(Debug)]
+pub struct ContainerLock {
+    _file: Flock<File>,
+}
+
+impl ContainerLock {
+    /// Locks the container with the given root directory, waiting at most
+    /// `timeout` for another process holding the lock to release it.
+    pub fn acquire(container_root: &Path, timeout: Duration) -> Result<Self, LockError> {
+        let path = container_root.join(LOCK_FILE);
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|err| LockError::Open {
+                path: path.to_owned(),
+                source: err,
+            })?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match Flock::lock(file, FlockArg::LockExclusiveNonblock) {
+                Ok(lock) => return Ok(Self { _file: lock }),
+                Err((f, Errno::EWOULDBLOCK)) if Instant::now() < deadline => {
+                    file = f;
+                    std::thread::sleep(RETRY_INTERVAL);
+                }
+                Err((_, Errno::EWOULDBLOCK)) => {
+                    tracing::error!(?path, ?timeout, "timed out waiting for container lock");
+                    return Err(LockError::Timeout { path, timeout });
+                }
+                Err((f, Errno::EINTR)) => file = f,
+                Err((_, err)) => {
+                    tracing::error!(?path, ?err, "failed to lock container");
+                    return Err(LockError::Lock { path, source: err });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn test_lock_timeout() -> Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        let lock = ContainerLock::acquire(tmp_dir.path(), Duration::ZERO)?;
+
+        // flock conflicts between open file descriptions, even in one process
+        assert!(matches!(
+            ContainerLock::acquire(tmp_dir.path(), Duration::from_millis(50)),
+            Err(LockError::Timeout { .. })
+        ));
+
+        drop(lock);
+        ContainerLock::acquire(tmp_dir.path(), Duration::ZERO)?;
+
+        Ok(())
+    }
+}
@@ -10,14 +10,22 @@ mod builder_impl;
 mod container;
 mod container_checkpoint;
 mod container_delete;
-mod container_events;
 mod container_kill;
 mod container_pause;
+mod container_restore;
 mod container_resume;
 mod container_start;
+mod container_stats;
+mod container_update;
+mod container_wait;
 pub mod init_builder;
+mod lock;
+pub mod runc_events;
 pub mod state;
 pub mod tenant_builder;
-pub use container::{CheckpointOptions, Container};
+pub use container::{CheckpointOptions, Container, ManageCgroupsMode, RestoreOptions};
 pub use container_checkpoint::CheckpointError;
+pub use container_stats::{ContainerStats, ProcessStats, StatsDelta, StatsEvent, StatsStream};
+pub use container_wait::ExitStatus;
+pub use lock::{ContainerLock, LockError, DEFAULT_LOCK_TIMEOUT};
 pub use state::{ContainerProcessState, ContainerStatus, State};
@@ -0,0 +1,407 @@
+//! The events of a container in the format of `runc events`
+//!
+//! Tools like the containerd runc shim read the output of `events` line by
+//! line and expect the objects and field names runc uses, which differ from
+//! how [`libcgroups::stats::Stats`] is serialized.
+use std::collections::HashMap;
+
+use libcgroups::events::CgroupEvent;
+use libcgroups::stats;
+use serde::Serialize;
+
+use super::ContainerStats;
+use crate::process::intel_rdt::{CmtStats, IntelRdtStats, MbmStats};
+
+/// An event of a container, serialized as
+/// `{"type": "stats" | "oom", "id": ..., "data": ...}`. runc reports Intel
+/// RDT data as part of the stats.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Event {
+    /// The resource usage of the container
+    Stats { id: String, data: Box<Stats> },
+    /// The container ran out of memory
+    Oom { id: String },
+}
+
+impl Event {
+    pub fn stats(id: &str, stats: &ContainerStats) -> Self {
+        Self::Stats {
+            id: id.to_owned(),
+            data: Box::new(stats.into()),
+        }
+    }
+
+    /// Converts an event of the cgroup of the container. runc only reports
+    /// OOMs, so there is nothing to report for the other events.
+    pub fn from_cgroup_event(id: &str, event: CgroupEvent) -> Option<Self> {
+        match event {
+            CgroupEvent::Oom => Some(Self::Oom { id: id.to_owned() }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub cpu: Cpu,
+    pub memory: Memory,
+    pub pids: Pids,
+    pub blkio: Blkio,
+    pub hugetlb: HashMap<String, Hugetlb>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intel_rdt: Option<IntelRdt>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Cpu {
+    pub usage: CpuUsage,
+    pub throttling: Throttling,
+    pub psi: Psi,
+}
+
+/// Cpu time in nanoseconds
+#[derive(Debug, Serialize)]
+pub struct CpuUsage {
+    pub total: u64,
+    pub percpu: Vec<u64>,
+    pub percpu_kernel: Vec<u64>,
+    pub percpu_user: Vec<u64>,
+    pub kernel: u64,
+    pub user: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Throttling {
+    pub periods: u64,
+    pub throttled_periods: u64,
+    pub throttled_time: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Memory {
+    pub cache: u64,
+    pub usage: MemoryEntry,
+    /// Usage of memory and swap
+    pub swap: MemoryEntry,
+    pub kernel: MemoryEntry,
+    #[serde(rename = "kernelTCP")]
+    pub kernel_tcp: MemoryEntry,
+    pub raw: HashMap<String, u64>,
+    pub psi: Psi,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MemoryEntry {
+    pub limit: u64,
+    pub usage: u64,
+    pub max: u64,
+    pub failcnt: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Pids {
+    pub current: u64,
+    pub limit: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Blkio {
+    pub io_service_bytes_recursive: Vec<BlkioEntry>,
+    pub io_serviced_recursive: Vec<BlkioEntry>,
+    // sic, this is what runc calls it
+    #[serde(rename = "ioQueueRecursive")]
+    pub io_queued_recursive: Vec<BlkioEntry>,
+    pub io_service_time_recursive: Vec<BlkioEntry>,
+    pub io_wait_time_recursive: Vec<BlkioEntry>,
+    pub io_merged_recursive: Vec<BlkioEntry>,
+    pub io_time_recursive: Vec<BlkioEntry>,
+    pub sectors_recursive: Vec<BlkioEntry>,
+    pub psi: Psi,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlkioEntry {
+    pub major: u64,
+    pub minor: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub op: Option<String>,
+    pub value: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Hugetlb {
+    pub usage: u64,
+    pub max: u64,
+    pub failcnt: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntelRdt {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l3_cache_schema: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mem_bw_schema: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mbm_stats: Vec<MbmNumaNodeStats>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cmt_stats: Vec<CmtNumaNodeStats>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MbmNumaNodeStats {
+    pub mbm_total_bytes: u64,
+    pub mbm_local_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CmtNumaNodeStats {
+    pub llc_occupancy: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Psi {
+    pub some: PsiData,
+    pub full: PsiData,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PsiData {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+}
+
+impl From<&ContainerStats> for Stats {
+    fn from(container_stats: &ContainerStats) -> Self {
+        let stats = &container_stats.cgroup;
+        Self {
+            cpu: (&stats.cpu).into(),
+            memory: (&stats.memory).into(),
+            pids: Pids {
+                current: stats.pids.current,
+                limit: stats.pids.limit,
+            },
+            blkio: (&stats.blkio).into(),
+            hugetlb: stats
+                .hugetlb
+                .iter()
+                .map(|(page_size, hugetlb)| {
+                    (
+                        page_size.clone(),
+                        Hugetlb {
+                            usage: hugetlb.usage,
+                            max: hugetlb.max_usage,
+                            failcnt: hugetlb.fail_count,
+                        },
+                    )
+                })
+                .collect(),
+            intel_rdt: container_stats.intel_rdt.as_ref().map(Into::into),
+        }
+    }
+}
+
+impl From<&stats::CpuStats> for Cpu {
+    fn from(cpu: &stats::CpuStats) -> Self {
+        Self {
+            usage: CpuUsage {
+                total: cpu.usage.usage_total,
+                percpu: cpu.usage.per_core_usage_total.clone(),
+                percpu_kernel: cpu.usage.per_core_usage_kernel.clone(),
+                percpu_user: cpu.usage.per_core_usage_user.clone(),
+                kernel: cpu.usage.usage_kernel,
+                user: cpu.usage.usage_user,
+            },
+            throttling: Throttling {
+                periods: cpu.throttling.periods,
+                throttled_periods: cpu.throttling.throttled_periods,
+                throttled_time: cpu.throttling.throttled_time,
+            },
+            psi: (&cpu.psi).into(),
+        }
+    }
+}
+
+impl From<&stats::MemoryStats> for Memory {
+    fn from(memory: &stats::MemoryStats) -> Self {
+        Self {
+            cache: memory.cache,
+            usage: (&memory.memory).into(),
+            swap: (&memory.memswap).into(),
+            kernel: (&memory.kernel).into(),
+            kernel_tcp: (&memory.kernel_tcp).into(),
+            raw: memory.stats.clone(),
+            psi: (&memory.psi).into(),
+        }
+    }
+}
+
+impl From<&stats::MemoryData> for MemoryEntry {
+    fn from(data: &stats::MemoryData) -> Self {
+        Self {
+            limit: data.limit,
+            usage: data.usage,
+            max: data.max_usage,
+            failcnt: data.fail_count,
+        }
+    }
+}
+
+impl From<&stats::BlkioStats> for Blkio {
+    fn from(blkio: &stats::BlkioStats) -> Self {
+        let entries = |stats: &[stats::BlkioDeviceStat]| -> Vec<BlkioEntry> {
+            stats
+                .iter()
+                .map(|stat| BlkioEntry {
+                    major: stat.major,
+                    minor: stat.minor,
+                    op: stat.op_type.clone(),
+                    value: stat.value,
+                })
+                .collect()
+        };
+
+        Self {
+            io_service_bytes_recursive: entries(&blkio.service_bytes),
+            io_serviced_recursive: entries(&blkio.serviced),
+            io_queued_recursive: entries(&blkio.queued),
+            io_service_time_recursive: entries(&blkio.service_time),
+            io_wait_time_recursive: entries(&blkio.wait_time),
+            io_merged_recursive: entries(&blkio.merged),
+            io_time_recursive: entries(&blkio.time),
+            sectors_recursive: entries(&blkio.sectors),
+            psi: (&blkio.psi).into(),
+        }
+    }
+}
+
+impl From<&IntelRdtStats> for IntelRdt {
+    fn from(intel_rdt: &IntelRdtStats) -> Self {
+        Self {
+            l3_cache_schema: intel_rdt.l3_cache_schema.clone(),
+            mem_bw_schema: intel_rdt.mem_bw_schema.clone(),
+            mbm_stats: intel_rdt
+                .mbm_stats
+                .iter()
+                .map(
+                    |&MbmStats {
+                         mbm_total_bytes,
+                         mbm_local_bytes,
+                     }| MbmNumaNodeStats {
+                        mbm_total_bytes,
+                        mbm_local_bytes,
+                    },
+                )
+                .collect(),
+            cmt_stats: intel_rdt
+                .cmt_stats
+                .iter()
+                .map(|&CmtStats { llc_occupancy }| CmtNumaNodeStats { llc_occupancy })
+                .collect(),
+        }
+    }
+}
+
+impl From<&stats::PSIStats> for Psi {
+    fn from(psi: &stats::PSIStats) -> Self {
+        let data = |data: &stats::PSIData| PsiData {
+            avg10: data.avg10,
+            avg60: data.avg60,
+            avg300: data.avg300,
+        };
+
+        Self {
+            some: data(&psi.some),
+            full: data(&psi.full),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_stats_event() -> Result<()> {
+        let mut stats = stats::Stats::default();
+        stats.cpu.usage.usage_total = 30;
+        stats.cpu.usage.per_core_usage_total = vec![10, 20];
+        stats.memory.memory.usage = 4096;
+        stats.memory.memswap.limit = 8192;
+        stats.memory.kernel_tcp.fail_count = 1;
+        stats.blkio.queued = vec![stats::BlkioDeviceStat {
+            major: 8,
+            minor: 0,
+            op_type: Some("Read".to_owned()),
+            value: 2,
+        }];
+        stats.hugetlb.insert(
+            "2MB".to_owned(),
+            stats::HugeTlbStats {
+                usage: 1,
+                max_usage: 2,
+                fail_count: 3,
+            },
+        );
+
+        let stats = ContainerStats {
+            cgroup: stats,
+            processes: Vec::new(),
+            intel_rdt: Some(IntelRdtStats {
+                l3_cache_schema: Some("L3:0=ff".to_owned()),
+                mbm_stats: vec![MbmStats {
+                    mbm_total_bytes: 2,
+                    mbm_local_bytes: 1,
+                }],
+                ..Default::default()
+            }),
+        };
+
+        let event = serde_json::to_value(Event::stats("container", &stats))?;
+        assert_eq!(event["type"], "stats");
+        assert_eq!(event["id"], "container");
+        let data = &event["data"];
+        assert_eq!(data["cpu"]["usage"]["total"], 30);
+        assert_eq!(data["cpu"]["usage"]["percpu"], json!([10, 20]));
+        assert_eq!(data["cpu"]["throttling"]["throttledPeriods"], 0);
+        assert_eq!(data["memory"]["usage"]["usage"], 4096);
+        assert_eq!(data["memory"]["swap"]["limit"], 8192);
+        assert_eq!(data["memory"]["kernelTCP"]["failcnt"], 1);
+        assert_eq!(
+            data["blkio"]["ioQueueRecursive"],
+            json!([{"major": 8, "minor": 0, "op": "Read", "value": 2}])
+        );
+        assert_eq!(
+            data["hugetlb"]["2MB"],
+            json!({"usage": 1, "max": 2, "failcnt": 3})
+        );
+        assert_eq!(
+            data["intel_rdt"],
+            json!({
+                "l3_cache_schema": "L3:0=ff",
+                "mbm_stats": [{"mbm_total_bytes": 2, "mbm_local_bytes": 1}],
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_oom_event() -> Result<()> {
+        let event = Event::from_cgroup_event("container", CgroupEvent::Oom).unwrap();
+        assert_eq!(
+            serde_json::to_string(&event)?,
+            r#"{"type":"oom","id":"container"}"#
+        );
+        assert!(Event::from_cgroup_event("container", CgroupEvent::Frozen).is_none());
+
+        Ok(())
+    }
+}
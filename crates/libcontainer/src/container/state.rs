@@ -7,6 +7,7 @@ use std::io::{BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
 
 use chrono::{DateTime, Utc};
+use oci_spec::runtime::{LinuxIntelRdt, LinuxResources};
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
 
@@ -101,6 +102,10 @@ pub struct State {
     // Pid is the process ID for the container process.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pid: Option<i32>,
+    // Start time of the container process in clock ticks after boot, which
+    // tells it apart from another process reusing its pid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub init_process_start: Option<u64>,
     // Bundle is the path to the container's bundle directory.
     pub bundle: PathBuf,
     // Annotations are key values associated with the container.
@@ -116,6 +121,27 @@ pub struct State {
     pub use_systemd: bool,
     // Specifies if the Intel RDT subdirectory needs be cleaned up.
     pub clean_up_intel_rdt_subdirectory: Option<bool>,
+    // Resource limits currently applied to the container cgroup
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resources: Option<LinuxResources>,
+    // Intel RDT schemas currently applied to the container resctrl group
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intel_rdt: Option<LinuxIntelRdt>,
+    // Exit code of the container process, if it exited normally
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    // Signal which terminated the container process
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_signal: Option<i32>,
+    // Time the runtime observed the exit of the container process
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    // Specifies if the OOM killer killed a process of the container
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oom_killed: Option<bool>,
+    // Memory cgroup of the container process, which counts its OOM kills
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_cgroup: Option<PathBuf>,
 }
 
 impl State {
@@ -132,25 +158,56 @@ impl State {
             id: container_id.to_string(),
             status,
             pid,
+            init_process_start: None,
             bundle,
             annotations: Some(HashMap::default()),
             created: None,
             creator: None,
             use_systemd: false,
             clean_up_intel_rdt_subdirectory: None,
+            resources: None,
+            intel_rdt: None,
+            exit_code: None,
+            exit_signal: None,
+            finished_at: None,
+            oom_killed: None,
+            memory_cgroup: None,
         }
     }
 
+    /// Writes the state to a temporary file which is then renamed over the
+    /// state file, so readers never see a partially written state.
     #[instrument(level = "trace")]
     pub fn save(&self, container_root: &Path) -> Result<()> {
         let state_file_path = Self::file_path(container_root);
+        let tmp_file_path =
+            container_root.join(format!(".{}.{}", Self::STATE_FILE_PATH, std::process::id()));
+        let result = self.write_file(&tmp_file_path).and_then(|_| {
+            fs::rename(&tmp_file_path, &state_file_path).map_err(|err| {
+                tracing::error!(
+                    ?state_file_path,
+                    %err,
+                    "failed to replace container state file",
+                );
+                StateError::WriteStateFile {
+                    state_file_path: state_file_path.to_owned(),
+                    source: err,
+                }
+            })
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_file_path);
+        }
+
+        result
+    }
+
+    fn write_file(&self, state_file_path: &Path) -> Result<()> {
         let file = fs::OpenOptions::new()
-            .read(true)
             .write(true)
-            .append(false)
             .create(true)
             .truncate(true)
-            .open(&state_file_path)
+            .open(state_file_path)
             .map_err(|err| {
                 tracing::error!(
                     state_file_path = ?state_file_path,
@@ -303,4 +360,27 @@ mod tests {
         assert!(!cstatus.can_pause());
         assert!(cstatus.can_resume());
     }
+
+    #[test]
+    fn test_save_replaces_state_file() -> anyhow::Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        let mut state = State::new(
+            "container",
+            ContainerStatus::Created,
+            Some(1),
+            PathBuf::from("/bundle"),
+        );
+        state.save(tmp_dir.path())?;
+        state.status = ContainerStatus::Running;
+        state.save(tmp_dir.path())?;
+
+        assert_eq!(
+            State::load(tmp_dir.path())?.status,
+            ContainerStatus::Running
+        );
+        // only the state file is left, without temporary files
+        assert_eq!(fs::read_dir(tmp_dir.path())?.count(), 1);
+
+        Ok(())
+    }
 }
@@ -28,7 +28,7 @@ use crate::process::args::ContainerType;
 use crate::user_ns::UserNamespaceConfig;
 use crate::{tty, utils};
 
-const NAMESPACE_TYPES: &[&str] = &["ipc", "uts", "net", "pid", "mnt", "cgroup"];
+const NAMESPACE_TYPES: &[&str] = &["ipc", "uts", "net", "pid", "mnt", "cgroup", "time"];
 const TENANT_NOTIFY: &str = "tenant-notify-";
 const TENANT_TTY: &str = "tenant-tty-";
 
@@ -155,6 +155,7 @@ impl TenantContainerBuilder {
             stdout: self.base.stdout,
             stderr: self.base.stderr,
             as_sibling: self.as_sibling,
+            no_new_keyring: true,
         };
 
         let pid = builder_impl.create()?;
@@ -485,9 +486,18 @@ impl TenantContainerBuilder {
         init_namespaces: HashMap<OsString, Namespace>,
     ) -> Result<Vec<LinuxNamespace>, LibcontainerError> {
         let mut tenant_namespaces = Vec::with_capacity(init_namespaces.len());
+        // Joining a time namespace requires CAP_SYS_ADMIN even if it is the
+        // one of the caller, so it is only joined if the container has its own.
+        let own_time_ns = procfs::process::Process::myself()?
+            .namespaces()?
+            .0
+            .remove(OsStr::new("time"));
 
         for &ns_type in NAMESPACE_TYPES {
             if let Some(init_ns) = init_namespaces.get(OsStr::new(ns_type)) {
+                if ns_type == "time" && own_time_ns.as_ref() == Some(init_ns) {
+                    continue;
+                }
                 let tenant_ns = LinuxNamespaceType::try_from(ns_type)?;
                 tenant_namespaces.push(
                     LinuxNamespaceBuilder::default()
@@ -39,6 +39,8 @@ pub enum LibcontainerError {
     #[error(transparent)]
     UserNamespace(#[from] crate::user_ns::UserNamespaceError),
     #[error(transparent)]
+    Rootfs(#[from] crate::rootfs::RootfsError),
+    #[error(transparent)]
     NotifyListener(#[from] crate::notify_socket::NotifyListenerError),
     #[error(transparent)]
     Config(#[from] crate::config::ConfigError),
@@ -46,6 +48,8 @@ pub enum LibcontainerError {
     Hook(#[from] crate::hooks::HookError),
     #[error(transparent)]
     State(#[from] crate::container::state::StateError),
+    #[error(transparent)]
+    Lock(#[from] crate::container::LockError),
     #[error("oci spec error")]
     Spec(#[from] oci_spec::OciSpecError),
     #[error(transparent)]
@@ -60,6 +64,10 @@ pub enum LibcontainerError {
     CgroupCreate(#[from] libcgroups::common::CreateCgroupSetupError),
     #[error(transparent)]
     CgroupGet(#[from] libcgroups::common::GetCgroupSetupError),
+    #[error(transparent)]
+    CgroupEvents(#[from] libcgroups::events::EventsError),
+    #[error(transparent)]
+    IntelRdt(#[from] crate::process::intel_rdt::IntelRdtError),
     #[error[transparent]]
     Checkpoint(#[from] crate::container::CheckpointError),
     #[error[transparent]]
@@ -98,6 +106,8 @@ pub enum ErrInvalidSpec {
     IoPriority,
     #[error("invalid scheduler config for process")]
     Scheduler,
+    #[error("time offsets require a new time namespace")]
+    TimeOffsets,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -0,0 +1,179 @@
+//! Session keyring of the container processes
+use std::ffi::CString;
+
+use nix::errno::Errno;
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeyringError {
+    #[error("invalid keyring name {0}")]
+    InvalidName(String),
+    #[error("failed to join session keyring {name}")]
+    Join {
+        name: String,
+        #[source]
+        source: nix::Error,
+    },
+    #[error("failed to describe keyring {0}")]
+    Describe(i32, #[source] nix::Error),
+    #[error("invalid keyring description: {0}")]
+    InvalidDescription(String),
+    #[error("failed to set permissions of keyring {0}")]
+    SetPerm(i32, #[source] nix::Error),
+}
+
+type Result<T> = std::result::Result<T, KeyringError>;
+
+/// Permission allowing the user owning a key to find it by searching.
+const KEY_USR_SEARCH: u32 = 0x00080000;
+
+/// Joins a new session keyring named after the container, so the container
+/// processes don't share the session keyring of the caller. This has to be
+/// called after the process entered the user namespace of the container, so
+/// the keyring is owned by the user the container is running as.
+pub fn join_session_keyring(container_id: &str) -> Result<()> {
+    let name = format!("_ses.{container_id}");
+    let key = match keyctl_join_session_keyring(&name) {
+        Ok(key) => key,
+        // The keyring only adds marginal protection on top of the other
+        // isolation, so like runc, the container can still be created on
+        // kernels without keyring support.
+        Err(KeyringError::Join {
+            source: Errno::ENOSYS,
+            ..
+        }) => {
+            tracing::warn!("keyrings are not supported, keeping the session keyring");
+            return Ok(());
+        }
+        Err(err) => return Err(err),
+    };
+
+    // Make the keyring searchable by its owner, which is what runc does as
+    // well. Otherwise the keys linked into it can't be found by the container
+    // process once it's running as a non root user.
+    mod_keyring_perm(key, 0xffffffff, KEY_USR_SEARCH)
+}
+
+fn keyctl_join_session_keyring(name: &str) -> Result<i32> {
+    let c_name = CString::new(name).map_err(|_| KeyringError::InvalidName(name.to_owned()))?;
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_keyctl,
+            libc::KEYCTL_JOIN_SESSION_KEYRING,
+            c_name.as_ptr(),
+        )
+    };
+    if ret < 0 {
+        return Err(KeyringError::Join {
+            name: name.to_owned(),
+            source: Errno::last(),
+        });
+    }
+
+    Ok(ret as i32)
+}
+
+fn mod_keyring_perm(key: i32, mask: u32, set_bits: u32) -> Result<()> {
+    let description = keyctl_describe(key)?;
+    let perm = parse_perm(&description)?;
+    let perm = (perm & mask) | set_bits;
+
+    let ret = unsafe { libc::syscall(libc::SYS_keyctl, libc::KEYCTL_SETPERM, key, perm) };
+    if ret < 0 {
+        return Err(KeyringError::SetPerm(key, Errno::last()));
+    }
+
+    Ok(())
+}
+
+fn keyctl_describe(key: i32) -> Result<String> {
+    let mut buf = vec![0u8; 256];
+    loop {
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_keyctl,
+                libc::KEYCTL_DESCRIBE,
+                key,
+                buf.as_mut_ptr(),
+                buf.len(),
+            )
+        };
+        if ret < 0 {
+            return Err(KeyringError::Describe(key, Errno::last()));
+        }
+
+        // the returned size includes the terminating null byte
+        let len = ret as usize;
+        if len <= buf.len() {
+            buf.truncate(len.saturating_sub(1));
+            return Ok(String::from_utf8_lossy(&buf).into_owned());
+        }
+        buf.resize(len, 0);
+    }
+}
+
+/// Parses the permissions out of a key description, which has the format
+/// `type;uid;gid;perm;description` with the permissions in hex.
+fn parse_perm(description: &str) -> Result<u32> {
+    description
+        .split(';')
+        .nth(3)
+        .and_then(|perm| u32::from_str_radix(perm, 16).ok())
+        .ok_or_else(|| KeyringError::InvalidDescription(description.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, TestCallbackError};
+
+    #[test]
+    fn test_parse_perm() {
+        assert_eq!(
+            parse_perm("keyring;0;0;3f1b0000;_ses.container").unwrap(),
+            0x3f1b0000
+        );
+        assert_eq!(
+            parse_perm("keyring;1000;1000;3f010000;_ses.a;b").unwrap(),
+            0x3f010000
+        );
+        assert!(parse_perm("keyring;0;0").is_err());
+        assert!(parse_perm("keyring;0;0;zz;_ses").is_err());
+    }
+
+    #[test]
+    fn test_invalid_keyring_name() {
+        assert!(matches!(
+            keyctl_join_session_keyring("_ses.\0"),
+            Err(KeyringError::InvalidName(_))
+        ));
+    }
+
+    #[test]
+    fn test_join_session_keyring() -> std::result::Result<(), test_utils::TestError> {
+        // joining a keyring replaces the session keyring of the whole process
+        test_utils::test_in_child_process(|| {
+            match keyctl_describe(libc::KEY_SPEC_SESSION_KEYRING) {
+                Err(KeyringError::Describe(_, Errno::ENOSYS)) => return Ok(()),
+                Err(err) => return Err(TestCallbackError::Other(err.into())),
+                Ok(_) => {}
+            }
+
+            join_session_keyring("test-container").map_err(|err| {
+                TestCallbackError::Custom(format!("failed to join keyring: {err:?}"))
+            })?;
+            let description = keyctl_describe(libc::KEY_SPEC_SESSION_KEYRING).map_err(|err| {
+                TestCallbackError::Custom(format!("failed to describe keyring: {err:?}"))
+            })?;
+            if description.split(';').nth(4) != Some("_ses.test-container") {
+                return Err(format!("unexpected keyring {description}").into());
+            }
+            let perm = parse_perm(&description)
+                .map_err(|err| TestCallbackError::Custom(format!("{err:?}")))?;
+            if perm & KEY_USR_SEARCH == 0 {
+                return Err(format!("keyring is not searchable by its owner: {perm:x}").into());
+            }
+
+            Ok(())
+        })
+    }
+}
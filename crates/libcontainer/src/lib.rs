@@ -5,8 +5,10 @@ pub mod config;
 pub mod container;
 pub mod error;
 pub mod hooks;
+pub mod keyring;
 pub mod namespaces;
 pub mod notify_socket;
+pub mod pidfd;
 pub mod process;
 pub mod rootfs;
 #[cfg(feature = "libseccomp")]
@@ -14,6 +16,7 @@ pub mod seccomp;
 pub mod signal;
 pub mod syscall;
 pub mod test_utils;
+pub mod timing;
 pub mod tty;
 pub mod user_ns;
 pub mod utils;
@@ -5,14 +5,16 @@
 //! Interprocess Communication (Control or communication between processes),
 //! Network (which network devices can be seen by the processes in the namespace), User (User configs),
 //! UTS (hostname and domain information, processes will think they're running on servers with different names),
-//! Cgroup (Resource limits, execution priority etc.)
+//! Cgroup (Resource limits, execution priority etc.),
+//! Time (offsets of the monotonic and boot time clocks)
 
-use std::collections;
+use std::collections::{self, HashMap};
+use std::fs;
 
 use nix::sched::CloneFlags;
 use nix::sys::stat;
 use nix::{fcntl, unistd};
-use oci_spec::runtime::{LinuxNamespace, LinuxNamespaceType};
+use oci_spec::runtime::{LinuxNamespace, LinuxNamespaceType, LinuxTimeOffset};
 
 use crate::syscall::syscall::create_syscall;
 use crate::syscall::Syscall;
@@ -29,8 +31,15 @@ pub enum NamespaceError {
     Syscall(#[from] crate::syscall::SyscallError),
     #[error("Namespace type not supported: {0}")]
     NotSupported(String),
+    #[error("invalid time offset for {clock}: {offset}")]
+    InvalidTimeOffset { clock: String, offset: String },
 }
 
+/// nix does not define the clone flag of time namespaces (Linux 5.6+).
+pub const CLONE_NEWTIME: CloneFlags = CloneFlags::from_bits_retain(libc::CLONE_NEWTIME);
+
+const TIMENS_OFFSETS_PATH: &str = "/proc/self/timens_offsets";
+
 static ORDERED_NAMESPACES: &[CloneFlags] = &[
     CloneFlags::CLONE_NEWUSER,
     CloneFlags::CLONE_NEWPID,
@@ -56,12 +65,64 @@ fn get_clone_flag(namespace_type: LinuxNamespaceType) -> Result<CloneFlags> {
         LinuxNamespaceType::Network => CloneFlags::CLONE_NEWNET,
         LinuxNamespaceType::Cgroup => CloneFlags::CLONE_NEWCGROUP,
         LinuxNamespaceType::Mount => CloneFlags::CLONE_NEWNS,
-        LinuxNamespaceType::Time => return Err(NamespaceError::NotSupported("time".to_string())),
+        LinuxNamespaceType::Time => CLONE_NEWTIME,
     };
 
     Ok(flag)
 }
 
+/// Returns the namespace types youki is able to create or join.
+pub fn supported_namespaces() -> Vec<LinuxNamespaceType> {
+    [
+        LinuxNamespaceType::Mount,
+        LinuxNamespaceType::Cgroup,
+        LinuxNamespaceType::Uts,
+        LinuxNamespaceType::Ipc,
+        LinuxNamespaceType::User,
+        LinuxNamespaceType::Pid,
+        LinuxNamespaceType::Network,
+        LinuxNamespaceType::Time,
+    ]
+    .into_iter()
+    .filter(|typ| get_clone_flag(*typ).is_ok())
+    .collect()
+}
+
+/// Converts `linux.timeOffsets` into the format of /proc/self/timens_offsets.
+fn format_time_offsets(offsets: &HashMap<String, LinuxTimeOffset>) -> Result<String> {
+    let mut clocks: Vec<_> = offsets.iter().collect();
+    clocks.sort_by_key(|(clock, _)| *clock);
+
+    let mut content = String::new();
+    for (clock, offset) in clocks {
+        let secs = offset.secs().unwrap_or_default();
+        let nanosecs = offset.nanosecs().unwrap_or_default();
+        if (clock != "monotonic" && clock != "boottime") || nanosecs >= 1_000_000_000 {
+            return Err(NamespaceError::InvalidTimeOffset {
+                clock: clock.to_owned(),
+                offset: format!("{secs}s {nanosecs}ns"),
+            });
+        }
+
+        content.push_str(&format!("{clock} {secs} {nanosecs}\n"));
+    }
+
+    Ok(content)
+}
+
+/// Sets the clock offsets of the time namespace the children of the calling
+/// process will be created in. This has to happen after unsharing the time
+/// namespace and before the first process enters it.
+pub fn set_time_offsets(offsets: &HashMap<String, LinuxTimeOffset>) -> Result<()> {
+    let content = format_time_offsets(offsets)?;
+    fs::write(TIMENS_OFFSETS_PATH, content).map_err(|err| {
+        tracing::error!(?err, ?offsets, "failed to write time namespace offsets");
+        err
+    })?;
+
+    Ok(())
+}
+
 impl TryFrom<Option<&Vec<LinuxNamespace>>> for Namespaces {
     type Error = NamespaceError;
 
@@ -200,4 +261,42 @@ mod tests {
         expect.sort();
         assert_eq!(unshare_args, expect)
     }
+
+    #[test]
+    fn test_format_time_offsets() -> Result<()> {
+        let offset = |secs: i64, nanosecs: Option<u32>| {
+            let mut offset = LinuxTimeOffset::default();
+            offset.set_secs(Some(secs)).set_nanosecs(nanosecs);
+            offset
+        };
+        let offsets = HashMap::from([
+            ("monotonic".to_string(), offset(3600, None)),
+            ("boottime".to_string(), offset(-86400, Some(500))),
+        ]);
+        assert_eq!(
+            format_time_offsets(&offsets)?,
+            "boottime -86400 500\nmonotonic 3600 0\n"
+        );
+
+        for (clock, offset) in [
+            ("realtime", offset(10, None)),
+            ("monotonic", offset(10, Some(1_000_000_000))),
+        ] {
+            let offsets = HashMap::from([(clock.to_string(), offset)]);
+            assert!(format_time_offsets(&offsets).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_supported_namespaces() {
+        let supported = supported_namespaces();
+        for typ in &supported {
+            assert!(get_clone_flag(*typ).is_ok());
+        }
+        assert!(supported.contains(&LinuxNamespaceType::Mount));
+        assert!(supported.contains(&LinuxNamespaceType::User));
+        assert!(supported.contains(&LinuxNamespaceType::Time));
+    }
 }
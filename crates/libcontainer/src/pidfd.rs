@@ -0,0 +1,69 @@
+//! Process file descriptors, which keep referring to the same process even
+//! after its pid has been reused
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+
+use nix::errno::Errno;
+use nix::sys::signal::Signal;
+use nix::unistd::Pid;
+
+#[derive(Debug)]
+pub struct PidFd(OwnedFd);
+
+impl PidFd {
+    /// Opens a pidfd for the process with the given pid. Fails with ENOSYS on
+    /// kernels older than 5.3.
+    pub fn open(pid: Pid) -> nix::Result<Self> {
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid.as_raw(), 0) };
+        Errno::result(fd).map(|fd| Self(unsafe { OwnedFd::from_raw_fd(fd as RawFd) }))
+    }
+
+    /// Sends a signal to the process. Fails with ESRCH once the process
+    /// exited, even if its pid is in use by another process by now.
+    pub fn send_signal(&self, signal: Signal) -> nix::Result<()> {
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_pidfd_send_signal,
+                self.0.as_raw_fd(),
+                signal as libc::c_int,
+                std::ptr::null::<libc::siginfo_t>(),
+                0,
+            )
+        };
+        Errno::result(ret).map(drop)
+    }
+}
+
+impl AsFd for PidFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use nix::sys::wait::{waitpid, WaitStatus};
+    use nix::unistd::{fork, ForkResult};
+
+    use super::*;
+
+    #[test]
+    fn test_send_signal() -> Result<()> {
+        let child = match unsafe { fork()? } {
+            ForkResult::Child => loop {
+                nix::unistd::pause();
+            },
+            ForkResult::Parent { child } => child,
+        };
+
+        let pidfd = PidFd::open(child)?;
+        pidfd.send_signal(Signal::SIGKILL)?;
+        assert_eq!(
+            waitpid(child, None)?,
+            WaitStatus::Signaled(child, Signal::SIGKILL, false)
+        );
+        assert_eq!(pidfd.send_signal(Signal::SIGKILL), Err(Errno::ESRCH));
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::os::unix::prelude::RawFd;
 use std::path::PathBuf;
 use std::rc::Rc;
@@ -52,4 +53,8 @@ pub struct ContainerArgs {
     pub stderr: Option<RawFd>,
     // Indicate if the init process should be a sibling of the main process.
     pub as_sibling: bool,
+    /// If the init process should keep the session keyring of the caller
+    pub no_new_keyring: bool,
+    /// Detached idmapped mounts to attach in the rootfs, keyed by destination
+    pub idmapped_mounts: HashMap<PathBuf, RawFd>,
 }
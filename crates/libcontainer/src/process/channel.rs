@@ -4,6 +4,7 @@ use nix::unistd::Pid;
 
 use crate::channel::{channel, Receiver, Sender};
 use crate::process::message::Message;
+use crate::timing::{self, PhaseTiming};
 
 #[derive(Debug, thiserror::Error)]
 pub enum ChannelError {
@@ -79,6 +80,14 @@ impl MainSender {
         Ok(())
     }
 
+    /// Sends the phases the process recorded, which the main process adds to
+    /// its own while it waits for the process to be ready
+    pub fn timings(&mut self, timings: Vec<PhaseTiming>) -> Result<(), ChannelError> {
+        self.sender.send(Message::Timings(timings))?;
+
+        Ok(())
+    }
+
     pub fn exec_failed(&mut self, err: String) -> Result<(), ChannelError> {
         self.sender.send(Message::ExecFailed(err))?;
         Ok(())
@@ -104,13 +113,7 @@ impl MainReceiver {
     /// Waits for associated intermediate process to send ready message
     /// and return the pid of init process which is forked by intermediate process
     pub fn wait_for_intermediate_ready(&mut self) -> Result<Pid, ChannelError> {
-        let msg = self
-            .receiver
-            .recv()
-            .map_err(|err| ChannelError::ReceiveError {
-                msg: "waiting for intermediate process".to_string(),
-                source: err,
-            })?;
+        let msg = self.recv_recording_timings("waiting for intermediate process")?;
 
         match msg {
             Message::IntermediateReady(pid) => Ok(Pid::from_raw(pid)),
@@ -172,19 +175,14 @@ impl MainReceiver {
     /// Waits for associated init process to send ready message
     /// and return the pid of init process which is forked by init process
     pub fn wait_for_init_ready(&mut self) -> Result<(), ChannelError> {
-        let msg = self
-            .receiver
-            .recv()
-            .map_err(|err| ChannelError::ReceiveError {
-                msg: "waiting for init ready".to_string(),
-                source: err,
-            })?;
+        let msg = self.recv_recording_timings("waiting for init ready")?;
         match msg {
             Message::InitReady => Ok(()),
             // this case in unique and known enough to have a special error format
             Message::ExecFailed(err) => Err(ChannelError::ExecError(format!(
                 "error in executing process : {err}"
             ))),
+            Message::OtherError(err) => Err(ChannelError::OtherError(err)),
             msg => Err(ChannelError::UnexpectedMessage {
                 expected: Message::InitReady,
                 received: msg,
@@ -192,6 +190,23 @@ impl MainReceiver {
         }
     }
 
+    /// Receives the next message, recording the timings sent before it
+    fn recv_recording_timings(&mut self, waiting_for: &str) -> Result<Message, ChannelError> {
+        loop {
+            let msg = self
+                .receiver
+                .recv()
+                .map_err(|err| ChannelError::ReceiveError {
+                    msg: waiting_for.to_string(),
+                    source: err,
+                })?;
+            match msg {
+                Message::Timings(timings) => timing::record(timings),
+                msg => return Ok(msg),
+            }
+        }
+    }
+
     pub fn close(&self) -> Result<(), ChannelError> {
         self.receiver.close()?;
 
@@ -414,6 +429,64 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[serial]
+    fn test_channel_init_error() -> Result<()> {
+        let (sender, receiver) = &mut main_channel()?;
+        match unsafe { unistd::fork()? } {
+            unistd::ForkResult::Parent { child } => {
+                wait::waitpid(child, None)?;
+                let ret = receiver.wait_for_init_ready();
+                assert!(
+                    matches!(ret, Err(ChannelError::OtherError(err)) if err == "callback failed")
+                );
+                receiver.close()?;
+            }
+            unistd::ForkResult::Child => {
+                sender
+                    .send_error("callback failed".to_owned())
+                    .with_context(|| "Failed to send error")?;
+                sender.close()?;
+                std::process::exit(0);
+            }
+        };
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_channel_init_timings() -> Result<()> {
+        let (sender, receiver) = &mut main_channel()?;
+        let phase = PhaseTiming {
+            process: timing::PhaseProcess::Init,
+            phase: "rootfs".to_owned(),
+            start_ns: 1,
+            duration_ns: 2,
+        };
+        match unsafe { unistd::fork()? } {
+            unistd::ForkResult::Parent { child } => {
+                wait::waitpid(child, None)?;
+                timing::clear();
+                receiver.wait_for_init_ready()?;
+                assert_eq!(timing::take(), vec![phase]);
+                receiver.close()?;
+            }
+            unistd::ForkResult::Child => {
+                sender
+                    .timings(vec![phase])
+                    .with_context(|| "Failed to send timings")?;
+                sender
+                    .init_ready()
+                    .with_context(|| "Failed to send init ready")?;
+                sender.close()?;
+                std::process::exit(0);
+            }
+        };
+
+        Ok(())
+    }
+
     #[test]
     #[serial]
     fn test_channel_main_graceful_exit() -> Result<()> {
@@ -21,8 +21,9 @@ use crate::rootfs::RootFS;
 #[cfg(feature = "libseccomp")]
 use crate::seccomp;
 use crate::syscall::{Syscall, SyscallError};
+use crate::timing::{self, PhaseProcess};
 use crate::user_ns::UserNamespaceConfig;
-use crate::{apparmor, capabilities, hooks, notify_socket, rootfs, tty, utils, workload};
+use crate::{apparmor, capabilities, hooks, keyring, notify_socket, rootfs, tty, utils, workload};
 
 #[derive(Debug, thiserror::Error)]
 pub enum InitProcessError {
@@ -54,6 +55,8 @@ pub enum InitProcessError {
     SyscallOther(#[source] SyscallError),
     #[error("failed apparmor")]
     AppArmor(#[source] apparmor::AppArmorError),
+    #[error("failed to join session keyring")]
+    Keyring(#[source] keyring::KeyringError),
     #[error("invalid umask")]
     InvalidUmask(u32),
     #[error(transparent)]
@@ -75,6 +78,8 @@ pub enum InitProcessError {
     WorkloadValidation(#[from] workload::ExecutorValidationError),
     #[error(transparent)]
     WorkloadSetEnvs(#[from] workload::ExecutorSetEnvsError),
+    #[error(transparent)]
+    WorkloadCallback(#[from] workload::ExecutorCallbackError),
     #[error("invalid io priority class: {0}")]
     IoPriorityClass(String),
     #[error("call exec sched_setattr error: {0}")]
@@ -358,12 +363,22 @@ pub fn container_init_process(
     let container = args.container.as_ref();
     let namespaces = Namespaces::try_from(linux.namespaces().as_ref())?;
     let notify_listener = &args.notify_listener;
+    let init_phase = timing::phase(PhaseProcess::Init, "init");
 
     setsid().map_err(|err| {
         tracing::error!(?err, "failed to setsid to create a session");
         InitProcessError::NixOther(err)
     })?;
 
+    if !args.no_new_keyring {
+        if let Some(container) = container {
+            keyring::join_session_keyring(container.id()).map_err(|err| {
+                tracing::error!(?err, "failed to join session keyring");
+                InitProcessError::Keyring(err)
+            })?;
+        }
+    }
+
     set_io_priority(syscall.as_ref(), proc.io_priority())?;
 
     setup_scheduler(proc.scheduler())?;
@@ -389,7 +404,9 @@ pub fn container_init_process(
         }
     }
 
+    let phase = timing::phase(PhaseProcess::Init, "namespaces");
     apply_rest_namespaces(&namespaces, spec, syscall.as_ref())?;
+    drop(phase);
 
     if let Some(true) = proc.no_new_privileges() {
         let _ = prctl::set_no_new_privileges(true);
@@ -399,6 +416,7 @@ pub fn container_init_process(
         // create_container hook needs to be called after the namespace setup, but
         // before pivot_root is called. This runs in the container namespaces.
         if let Some(hooks) = hooks {
+            let _phase = timing::phase(PhaseProcess::Init, "create_container_hooks");
             hooks::run_hooks(hooks.create_container().as_ref(), container, None).map_err(
                 |err| {
                     tracing::error!(?err, "failed to run create container hooks");
@@ -410,12 +428,14 @@ pub fn container_init_process(
         let in_user_ns = utils::is_in_new_userns().map_err(InitProcessError::Io)?;
         let bind_service = namespaces.get(LinuxNamespaceType::User)?.is_some() || in_user_ns;
         let rootfs = RootFS::new();
+        let phase = timing::phase(PhaseProcess::Init, "rootfs");
         rootfs
             .prepare_rootfs(
                 spec,
                 rootfs_path,
                 bind_service,
                 namespaces.get(LinuxNamespaceType::Cgroup)?.is_some(),
+                &args.idmapped_mounts,
             )
             .map_err(|err| {
                 tracing::error!(?err, "failed to prepare rootfs");
@@ -427,6 +447,7 @@ pub fn container_init_process(
         // use simple chroot. Scary things will happen if you try to pivot_root
         // in the host mount namespace...
         do_pivot_root(syscall.as_ref(), &namespaces, args.no_pivot, rootfs_path)?;
+        drop(phase);
 
         // As we have changed the root mount, from here on
         // logs are no longer visible in journalctl
@@ -446,6 +467,12 @@ pub fn container_init_process(
         if let Some(kernel_params) = linux.sysctl() {
             sysctl(kernel_params)?;
         }
+
+        let _phase = timing::phase(PhaseProcess::Init, "on_rootfs_ready");
+        args.executor.on_rootfs_ready(spec).map_err(|err| {
+            tracing::error!(?err, "executor failed after the rootfs was ready");
+            err
+        })?;
     }
 
     if let Some(profile) = proc.apparmor_profile() {
@@ -591,6 +618,7 @@ pub fn container_init_process(
     #[cfg(feature = "libseccomp")]
     if let Some(seccomp) = linux.seccomp() {
         if proc.no_new_privileges().is_none() {
+            let _phase = timing::phase(PhaseProcess::Init, "seccomp");
             let notify_fd = seccomp::initialize_seccomp(seccomp).map_err(|err| {
                 tracing::error!(?err, "failed to initialize seccomp");
                 err
@@ -606,6 +634,7 @@ pub fn container_init_process(
         tracing::warn!("seccomp not available, unable to enforce no_new_privileges!")
     }
 
+    let phase = timing::phase(PhaseProcess::Init, "capabilities");
     capabilities::reset_effective(syscall.as_ref()).map_err(|err| {
         tracing::error!(?err, "failed to reset effective capabilities");
         InitProcessError::SyscallOther(err)
@@ -616,6 +645,7 @@ pub fn container_init_process(
             InitProcessError::SyscallOther(err)
         })?;
     }
+    drop(phase);
 
     // Change directory to process.cwd if process.cwd is not empty
     if do_chdir {
@@ -638,6 +668,7 @@ pub fn container_init_process(
     #[cfg(feature = "libseccomp")]
     if let Some(seccomp) = linux.seccomp() {
         if proc.no_new_privileges().is_some() {
+            let _phase = timing::phase(PhaseProcess::Init, "seccomp");
             let notify_fd = seccomp::initialize_seccomp(seccomp).map_err(|err| {
                 tracing::error!(?err, "failed to initialize seccomp");
                 err
@@ -660,13 +691,21 @@ pub fn container_init_process(
         }
     }
 
+    let phase = timing::phase(PhaseProcess::Init, "executor_validate");
     args.executor.validate(spec)?;
     args.executor.setup_envs(envs)?;
+    drop(phase);
+    drop(init_phase);
 
     // Notify main process that the init process is ready to execute the
     // payload.  Note, because we are already inside the pid namespace, the pid
     // outside the pid namespace should be recorded by the intermediate process
-    // already.
+    // already. The main process is gone by the time the container is started,
+    // so the phases after this point are only traced, not reported.
+    main_sender.timings(timing::take()).map_err(|err| {
+        tracing::error!(?err, "failed to send timings of init process");
+        InitProcessError::Channel(err)
+    })?;
     main_sender.init_ready().map_err(|err| {
         tracing::error!(
             ?err,
@@ -689,10 +728,18 @@ pub fn container_init_process(
         err
     })?;
 
+    let span = timing::span(PhaseProcess::Init, "on_start");
+    args.executor.on_start(spec).map_err(|err| {
+        tracing::error!(?err, "executor failed after the start signal");
+        err
+    })?;
+    drop(span);
+
     // create_container hook needs to be called after the namespace setup, but
     // before pivot_root is called. This runs in the container namespaces.
     if matches!(args.container_type, ContainerType::InitContainer) {
         if let Some(hooks) = hooks {
+            let _span = timing::span(PhaseProcess::Init, "start_container_hooks");
             hooks::run_hooks(hooks.start_container().as_ref(), container, None).map_err(|err| {
                 tracing::error!(?err, "failed to run start container hooks");
                 err
@@ -705,6 +752,7 @@ pub fn container_init_process(
         Err(MissingSpecError::Args)?;
     }
 
+    let _span = timing::span(PhaseProcess::Init, "exec");
     args.executor.exec(spec).map_err(|err| {
         tracing::error!(?err, "failed to execute payload");
         err
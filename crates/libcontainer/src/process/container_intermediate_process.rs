@@ -7,11 +7,12 @@ use procfs::process::Process;
 
 use super::args::{ContainerArgs, ContainerType};
 use super::channel::{IntermediateReceiver, MainSender};
-use super::container_init_process::container_init_process;
+use super::container_init_process::{container_init_process, InitProcessError};
 use super::fork::CloneCb;
 use crate::error::MissingSpecError;
-use crate::namespaces::Namespaces;
+use crate::namespaces::{self, Namespaces};
 use crate::process::{channel, fork};
+use crate::timing::{self, PhaseProcess};
 
 #[derive(Debug, thiserror::Error)]
 pub enum IntermediateProcessError {
@@ -62,17 +63,20 @@ pub fn container_intermediate_process(
     // In addition this needs to be done before we enter the cgroup namespace as
     // the cgroup of the process will form the root of the cgroup hierarchy in
     // the cgroup namespace.
+    let phase = timing::phase(PhaseProcess::Intermediate, "cgroups");
     apply_cgroups(
         &cgroup_manager,
         linux.resources().as_ref(),
         matches!(args.container_type, ContainerType::InitContainer),
     )?;
+    drop(phase);
 
     // if new user is specified in specification, this will be true and new
     // namespace will be created, check
     // https://man7.org/linux/man-pages/man7/user_namespaces.7.html for more
     // information
     if let Some(user_namespace) = namespaces.get(LinuxNamespaceType::User)? {
+        let _phase = timing::phase(PhaseProcess::Intermediate, "user_namespace");
         setup_userns(&namespaces, user_namespace, main_sender, inter_receiver)?;
 
         // After UID and GID mapping is configured correctly in the Youki main
@@ -95,17 +99,33 @@ pub fn container_intermediate_process(
         }
     }
 
+    let phase = timing::phase(PhaseProcess::Intermediate, "namespaces");
     // Pid namespace requires an extra fork to enter, so we enter pid namespace now.
     if let Some(pid_namespace) = namespaces.get(LinuxNamespaceType::Pid)? {
         namespaces.unshare_or_setns(pid_namespace)?;
     }
 
+    // Same as the pid namespace, a new time namespace is only entered by the
+    // children of the process creating it. Its clock offsets can only be set
+    // before the first process enters it, so this happens before the init
+    // process is created.
+    if let Some(time_namespace) = namespaces.get(LinuxNamespaceType::Time)? {
+        namespaces.unshare_or_setns(time_namespace)?;
+        if time_namespace.path().is_none() {
+            if let Some(offsets) = linux.time_offsets() {
+                namespaces::set_time_offsets(offsets)?;
+            }
+        }
+    }
+    drop(phase);
+
     let cb: CloneCb = {
         Box::new(|| {
             if let Err(ret) = prctl::set_name("youki:[2:INIT]") {
                 tracing::error!(?ret, "failed to set name for child process");
                 return ret;
             }
+            timing::clear();
 
             // We are inside the forked process here. The first thing we have to do
             // is to close any unused senders, since fork will make a dup for all
@@ -122,7 +142,7 @@ pub fn container_intermediate_process(
                 Ok(_) => 0,
                 Err(e) => {
                     tracing::error!("failed to initialize container process: {e}");
-                    if let Err(err) = main_sender.exec_failed(e.to_string()) {
+                    if let Err(err) = report_init_error(main_sender, &e) {
                         tracing::error!(?err, "failed sending error to main sender");
                     }
                     if let ContainerType::TenantContainer { exec_notify_fd } = args.container_type {
@@ -151,10 +171,12 @@ pub fn container_intermediate_process(
     // configuration. The youki main process can decide what to do with the init
     // process and the intermediate process can just exit safely after the job
     // is done.
+    let phase = timing::phase(PhaseProcess::Intermediate, "clone_init");
     let pid = fork::container_clone_sibling(cb).map_err(|err| {
         tracing::error!("failed to fork init process: {}", err);
         IntermediateProcessError::InitProcess(err)
     })?;
+    drop(phase);
 
     // Close the exec_notify_fd in this process
     if let ContainerType::TenantContainer { exec_notify_fd } = args.container_type {
@@ -164,6 +186,10 @@ pub fn container_intermediate_process(
         })?;
     }
 
+    main_sender.timings(timing::take()).map_err(|err| {
+        tracing::error!("failed to send timings of intermediate process: {}", err);
+        err
+    })?;
     main_sender.intermediate_ready(pid).map_err(|err| {
         tracing::error!("failed to wait on intermediate process: {}", err);
         err
@@ -259,15 +285,31 @@ fn apply_cgroups<
     Ok(())
 }
 
+/// Reports the error of the init process to the main process. Errors of the
+/// executor callbacks aren't exec failures.
+fn report_init_error(
+    main_sender: &mut MainSender,
+    err: &InitProcessError,
+) -> std::result::Result<(), channel::ChannelError> {
+    match err {
+        InitProcessError::WorkloadCallback(_) => main_sender.send_error(err.to_string()),
+        _ => main_sender.exec_failed(err.to_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
     use libcgroups::test_manager::TestManager;
     use nix::unistd::Pid;
-    use oci_spec::runtime::LinuxResources;
+    use oci_spec::runtime::{LinuxResources, Spec};
     use procfs::process::Process;
 
     use super::*;
+    use crate::process::channel::ChannelError;
+    use crate::workload::{
+        Executor, ExecutorCallbackError, ExecutorError, ExecutorValidationError,
+    };
 
     #[test]
     fn apply_cgroup_init() -> Result<()> {
@@ -321,4 +363,84 @@ mod tests {
         assert!(!cmanager.apply_called());
         Ok(())
     }
+
+    /// An executor whose workload and callbacks fail
+    #[derive(Clone)]
+    struct FailingExecutor;
+
+    impl Executor for FailingExecutor {
+        fn exec(&self, _spec: &Spec) -> Result<(), ExecutorError> {
+            Err(ExecutorError::Other("exec failed".to_owned()))
+        }
+
+        fn validate(&self, _spec: &Spec) -> Result<(), ExecutorValidationError> {
+            Ok(())
+        }
+
+        fn on_rootfs_ready(&self, _spec: &Spec) -> Result<(), ExecutorCallbackError> {
+            Err(ExecutorCallbackError::Other("callback failed".to_owned()))
+        }
+
+        fn on_start(&self, _spec: &Spec) -> Result<(), ExecutorCallbackError> {
+            Err(ExecutorCallbackError::Other("callback failed".to_owned()))
+        }
+    }
+
+    /// An executor keeping the default implementations of the callbacks
+    #[derive(Clone)]
+    struct DefaultCallbacksExecutor;
+
+    impl Executor for DefaultCallbacksExecutor {
+        fn exec(&self, _spec: &Spec) -> Result<(), ExecutorError> {
+            Ok(())
+        }
+
+        fn validate(&self, _spec: &Spec) -> Result<(), ExecutorValidationError> {
+            Ok(())
+        }
+    }
+
+    /// Reports the error like the init process does and returns what the
+    /// main process receives
+    fn report(err: InitProcessError) -> Result<Result<(), ChannelError>> {
+        let (mut sender, mut receiver) = channel::main_channel()?;
+        report_init_error(&mut sender, &err)?;
+        sender.close()?;
+        let ret = receiver.wait_for_init_ready();
+        receiver.close()?;
+        Ok(ret)
+    }
+
+    #[test]
+    fn test_report_callback_error() -> Result<()> {
+        let spec = Spec::default();
+        for err in [
+            FailingExecutor.on_rootfs_ready(&spec).unwrap_err(),
+            FailingExecutor.on_start(&spec).unwrap_err(),
+        ] {
+            let ret = report(err.into())?;
+            assert!(
+                matches!(ret, Err(ChannelError::OtherError(ref msg)) if msg.contains("callback failed")),
+                "{ret:?}"
+            );
+        }
+
+        // a failing workload is still reported as an exec failure
+        let ret = report(FailingExecutor.exec(&spec).unwrap_err().into())?;
+        assert!(
+            matches!(ret, Err(ChannelError::ExecError(ref msg)) if msg.contains("exec failed")),
+            "{ret:?}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_callbacks() -> Result<()> {
+        let spec = Spec::default();
+        DefaultCallbacksExecutor.on_rootfs_ready(&spec)?;
+        DefaultCallbacksExecutor.on_start(&spec)?;
+
+        Ok(())
+    }
 }
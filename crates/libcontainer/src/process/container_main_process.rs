@@ -6,6 +6,7 @@ use crate::process::fork::{self, CloneCb};
 use crate::process::intel_rdt::setup_intel_rdt;
 use crate::process::{channel, container_intermediate_process};
 use crate::syscall::SyscallError;
+use crate::timing::{self, PhaseProcess};
 use crate::user_ns::UserNamespaceConfig;
 
 #[derive(Debug, thiserror::Error)]
@@ -49,6 +50,7 @@ pub fn container_main_process(container_args: &ContainerArgs) -> Result<(Pid, bo
                 tracing::error!(?ret, "failed to set name for child process");
                 return ret;
             }
+            timing::clear();
 
             match container_intermediate_process::container_intermediate_process(
                 container_args,
@@ -112,6 +114,7 @@ pub fn container_main_process(container_args: &ContainerArgs) -> Result<(Pid, bo
     // the main process to set up uid and gid mapping, once the intermediate
     // process enters into a new user namespace.
     if let Some(config) = &container_args.user_ns_config {
+        let _phase = timing::phase(PhaseProcess::Main, "user_namespace_mapping");
         main_receiver.wait_for_mapping_request()?;
         setup_mapping(config, intermediate_pid)?;
         inter_sender.mapping_written()?;
@@ -126,12 +129,15 @@ pub fn container_main_process(container_args: &ContainerArgs) -> Result<(Pid, bo
 
     // The intermediate process will send the init pid once it forks the init
     // process.  The intermediate process should exit after this point.
+    let phase = timing::phase(PhaseProcess::Main, "wait_intermediate");
     let init_pid = main_receiver.wait_for_intermediate_ready()?;
+    drop(phase);
     let mut need_to_clean_up_intel_rdt_subdirectory = false;
 
     if let Some(linux) = container_args.spec.linux() {
         #[cfg(feature = "libseccomp")]
         if let Some(seccomp) = linux.seccomp() {
+            let _phase = timing::phase(PhaseProcess::Main, "seccomp_listener");
             let state = crate::container::ContainerProcessState {
                 oci_version: container_args.spec.version().to_string(),
                 // runc hardcode the `seccompFd` name for fds.
@@ -154,6 +160,7 @@ pub fn container_main_process(container_args: &ContainerArgs) -> Result<(Pid, bo
         }
 
         if let Some(intel_rdt) = linux.intel_rdt() {
+            let _phase = timing::phase(PhaseProcess::Main, "intel_rdt");
             let container_id = container_args
                 .container
                 .as_ref()
@@ -170,10 +177,12 @@ pub fn container_main_process(container_args: &ContainerArgs) -> Result<(Pid, bo
         err
     })?;
 
+    let phase = timing::phase(PhaseProcess::Main, "wait_init");
     main_receiver.wait_for_init_ready().map_err(|err| {
         tracing::error!("failed to wait for init ready: {}", err);
         err
     })?;
+    drop(phase);
 
     tracing::debug!("init pid is {:?}", init_pid);
 
@@ -55,6 +55,11 @@ pub fn container_clone(cb: CloneCb) -> Result<Pid, CloneError> {
     clone_internal(cb, 0, Some(SIGCHLD as u64))
 }
 
+// Clone a child process in a new user namespace and execute the callback.
+pub fn container_clone_user_ns(cb: CloneCb) -> Result<Pid, CloneError> {
+    clone_internal(cb, libc::CLONE_NEWUSER as u64, Some(SIGCHLD as u64))
+}
+
 // An internal wrapper to manage the clone3 vs clone fallback logic.
 fn clone_internal(
     mut cb: CloneCb,
@@ -8,6 +8,7 @@ use oci_spec::runtime::LinuxIntelRdt;
 use once_cell::sync::Lazy;
 use procfs::process::Process;
 use regex::Regex;
+use serde::Serialize;
 
 #[derive(Debug, thiserror::Error)]
 pub enum IntelRdtError {
@@ -45,6 +46,16 @@ pub enum IntelRdtError {
     CreateClosIDDirectory(#[source] std::io::Error),
     #[error("failed to canonicalize path")]
     Canonicalize(#[source] std::io::Error),
+    #[error("failed to read {path:?}")]
+    ReadStats {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse {path:?}")]
+    ParseStats {
+        path: PathBuf,
+        source: std::num::ParseIntError,
+    },
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -406,6 +417,130 @@ pub fn setup_intel_rdt(
     Ok(need_to_delete_directory)
 }
 
+/// Writes new schemas to the resctrl group of a running container. Unlike
+/// [`setup_intel_rdt`] the group has to exist already, and the schemas are
+/// written even if the group was given by a closID.
+pub fn update_intel_rdt(
+    group: &str,
+    l3_cache_schema: &Option<String>,
+    mem_bw_schema: &Option<String>,
+) -> Result<()> {
+    let path = find_resctrl_mount_point().map_err(|err| {
+        tracing::error!("failed to find a mounted resctrl file system");
+        err
+    })?;
+    if !path.join(group).is_dir() {
+        return Err(IntelRdtError::NoResctrlSubdirectory);
+    }
+
+    write_resctrl_schemata(&path, group, l3_cache_schema, mem_bw_schema, false, true).map_err(
+        |err| {
+            tracing::error!("failed to write schemata to resctrl schemata file");
+            err
+        },
+    )
+}
+
+/// Reports the allocation and monitoring data of a resctrl group
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct IntelRdtStats {
+    /// The `L3` lines of the schemata of the group
+    pub l3_cache_schema: Option<String>,
+    /// The `MB` line of the schemata of the group
+    pub mem_bw_schema: Option<String>,
+    /// Memory bandwidth monitoring per L3 cache domain
+    pub mbm_stats: Vec<MbmStats>,
+    /// Cache monitoring per L3 cache domain
+    pub cmt_stats: Vec<CmtStats>,
+}
+
+/// Reports the memory bandwidth used by a resctrl group
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct MbmStats {
+    /// Bytes transferred to and from any memory
+    pub mbm_total_bytes: u64,
+    /// Bytes transferred to and from the local memory
+    pub mbm_local_bytes: u64,
+}
+
+/// Reports the cache used by a resctrl group
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CmtStats {
+    /// Bytes of the last level cache used
+    pub llc_occupancy: u64,
+}
+
+/// Reads the stats of the resctrl group of a container, which is named after
+/// its closID or else the container id.
+pub fn intel_rdt_stats(group: &str) -> Result<IntelRdtStats> {
+    let path = find_resctrl_mount_point()?;
+    read_intel_rdt_stats(&path.join(group))
+}
+
+fn read_intel_rdt_stats(group_path: &Path) -> Result<IntelRdtStats> {
+    let read = |path: PathBuf| {
+        fs::read_to_string(&path).map_err(|err| IntelRdtError::ReadStats { path, source: err })
+    };
+
+    let mut stats = IntelRdtStats::default();
+    let schemata = read(group_path.join("schemata"))?;
+    let l3_lines: Vec<&str> = schemata
+        .lines()
+        .filter(|line| line.starts_with("L3"))
+        .collect();
+    if !l3_lines.is_empty() {
+        stats.l3_cache_schema = Some(l3_lines.join("\n"));
+    }
+    stats.mem_bw_schema = schemata
+        .lines()
+        .find(|line| line.starts_with("MB:"))
+        .map(str::to_owned);
+
+    // the monitoring data is only there if the cpu supports it
+    let mon_data = group_path.join("mon_data");
+    if !mon_data.exists() {
+        return Ok(stats);
+    }
+    let mut domains: Vec<PathBuf> = fs::read_dir(&mon_data)
+        .map_err(|err| IntelRdtError::ReadStats {
+            path: mon_data.clone(),
+            source: err,
+        })?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("mon_L3_"))
+        .map(|entry| entry.path())
+        .collect();
+    domains.sort();
+
+    let read_value = |path: PathBuf| -> Result<Option<u64>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let value = read(path.clone())?;
+        value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|err| IntelRdtError::ParseStats { path, source: err })
+    };
+    for domain in domains {
+        if let (Some(mbm_total_bytes), Some(mbm_local_bytes)) = (
+            read_value(domain.join("mbm_total_bytes"))?,
+            read_value(domain.join("mbm_local_bytes"))?,
+        ) {
+            stats.mbm_stats.push(MbmStats {
+                mbm_total_bytes,
+                mbm_local_bytes,
+            });
+        }
+        if let Some(llc_occupancy) = read_value(domain.join("llc_occupancy"))? {
+            stats.cmt_stats.push(CmtStats { llc_occupancy });
+        }
+    }
+
+    Ok(stats)
+}
+
 #[cfg(test)]
 mod test {
     use anyhow::Result;
@@ -610,4 +745,52 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_read_intel_rdt_stats() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        fs::write(
+            tmp.path().join("schemata"),
+            "L3:0=ff;1=ff\nMB:0=100;1=100\n",
+        )?;
+        let stats = read_intel_rdt_stats(tmp.path())?;
+        assert_eq!(stats.l3_cache_schema.as_deref(), Some("L3:0=ff;1=ff"));
+        assert_eq!(stats.mem_bw_schema.as_deref(), Some("MB:0=100;1=100"));
+        assert!(stats.mbm_stats.is_empty());
+
+        for (domain, value) in [("mon_L3_00", 10), ("mon_L3_01", 20)] {
+            let dir = tmp.path().join("mon_data").join(domain);
+            fs::create_dir_all(&dir)?;
+            fs::write(dir.join("mbm_total_bytes"), format!("{value}\n"))?;
+            fs::write(dir.join("mbm_local_bytes"), format!("{}\n", value / 2))?;
+            fs::write(dir.join("llc_occupancy"), format!("{}\n", value * 100))?;
+        }
+        let stats = read_intel_rdt_stats(tmp.path())?;
+        assert_eq!(
+            stats.mbm_stats,
+            vec![
+                MbmStats {
+                    mbm_total_bytes: 10,
+                    mbm_local_bytes: 5
+                },
+                MbmStats {
+                    mbm_total_bytes: 20,
+                    mbm_local_bytes: 10
+                },
+            ]
+        );
+        assert_eq!(
+            stats.cmt_stats,
+            vec![
+                CmtStats {
+                    llc_occupancy: 1000
+                },
+                CmtStats {
+                    llc_occupancy: 2000
+                }
+            ]
+        );
+
+        Ok(())
+    }
 }
@@ -2,6 +2,8 @@ use core::fmt;
 
 use serde::{Deserialize, Serialize};
 
+use crate::timing::PhaseTiming;
+
 /// Used as a wrapper for messages to be sent between child and parent processes
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum Message {
@@ -13,6 +15,7 @@ pub enum Message {
     SeccompNotifyDone,
     ExecFailed(String),
     OtherError(String),
+    Timings(Vec<PhaseTiming>),
 }
 
 impl fmt::Display for Message {
@@ -26,6 +29,7 @@ impl fmt::Display for Message {
             Message::SeccompNotifyDone => write!(f, "SeccompNotifyDone"),
             Message::ExecFailed(s) => write!(f, "ExecFailed({})", s),
             Message::OtherError(s) => write!(f, "OtherError({})", s),
+            Message::Timings(t) => write!(f, "Timings({} phases)", t.len()),
         }
     }
 }
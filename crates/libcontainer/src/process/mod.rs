@@ -6,7 +6,7 @@ pub mod channel;
 pub mod container_init_process;
 pub mod container_intermediate_process;
 pub mod container_main_process;
-mod fork;
+pub(crate) mod fork;
 pub mod intel_rdt;
 mod message;
 #[cfg(feature = "libseccomp")]
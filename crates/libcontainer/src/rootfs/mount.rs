@@ -1,9 +1,11 @@
+#[cfg(feature = "v1")]
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fs::{canonicalize, create_dir_all, OpenOptions};
 use std::mem;
-use std::os::unix::io::AsRawFd;
+use std::os::fd::OwnedFd;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::path::{Path, PathBuf};
-#[cfg(feature = "v1")]
-use std::{borrow::Cow, collections::HashMap};
 
 use libcgroups::common::CgroupSetup::{Hybrid, Legacy, Unified};
 #[cfg(feature = "v1")]
@@ -21,9 +23,10 @@ use safe_path;
 #[cfg(feature = "v1")]
 use super::symlink::Symlink;
 use super::symlink::SymlinkError;
-use super::utils::{parse_mount, MountOptionConfig};
+use super::utils::{parse_mount, MountIdmap, MountOptionConfig};
 use crate::syscall::syscall::create_syscall;
 use crate::syscall::{linux, Syscall, SyscallError};
+use crate::user_ns::UserNamespaceConfig;
 use crate::utils::PathBufExt;
 
 #[derive(Debug, thiserror::Error)]
@@ -58,6 +61,9 @@ pub struct MountOptions<'a> {
     pub label: Option<&'a str>,
     #[allow(dead_code)]
     pub cgroup_ns: bool,
+    /// Detached idmapped mounts created by `open_idmapped_mounts`, keyed by
+    /// their destination.
+    pub idmapped_mounts: &'a HashMap<PathBuf, RawFd>,
 }
 
 pub struct Mount {
@@ -117,17 +123,34 @@ impl Mount {
                         options.root,
                         &mount_option_config,
                         options.label,
+                        None,
                     )
                     .map_err(|err| {
                         tracing::error!("failed to mount /dev: {}", err);
                         err
                     })?;
                 } else {
+                    let idmapped_mount = if mount_option_config.idmap.is_some() {
+                        Some(
+                            *options
+                                .idmapped_mounts
+                                .get(mount.destination())
+                                .ok_or_else(|| {
+                                    MountError::Custom(format!(
+                                        "idmapped mount {:?} was not prepared",
+                                        mount.destination()
+                                    ))
+                                })?,
+                        )
+                    } else {
+                        None
+                    };
                     self.mount_into_container(
                         mount,
                         options.root,
                         &mount_option_config,
                         options.label,
+                        idmapped_mount,
                     )
                     .map_err(|err| {
                         tracing::error!("failed to mount {:?}: {}", mount, err);
@@ -287,6 +310,7 @@ impl Mount {
             flags: MsFlags::MS_NOEXEC | MsFlags::MS_NOSUID | MsFlags::MS_NODEV,
             data: data.to_string(),
             rec_attr: None,
+            idmap: None,
         };
 
         self.mount_into_container(
@@ -294,6 +318,7 @@ impl Mount {
             options.root,
             &mount_options_config,
             options.label,
+            None,
         )
         .map_err(|err| {
             tracing::error!("failed to mount {subsystem_mount:?}: {err}");
@@ -387,6 +412,7 @@ impl Mount {
                 options.root,
                 mount_option_config,
                 options.label,
+                None,
             )
             .is_err()
         {
@@ -433,6 +459,7 @@ impl Mount {
                 options.root,
                 &mount_option_config,
                 options.label,
+                None,
             )
             .map_err(|err| {
                 tracing::error!("failed to bind mount cgroup hierarchy: {}", err);
@@ -483,8 +510,10 @@ impl Mount {
         rootfs: &Path,
         mount_option_config: &MountOptionConfig,
         label: Option<&str>,
+        idmapped_mount: Option<RawFd>,
     ) -> Result<()> {
         let typ = m.typ().as_deref();
+        let is_bind = typ == Some("bind") || idmapped_mount.is_some();
         let mut d = mount_option_config.data.to_string();
 
         if let Some(l) = label {
@@ -508,7 +537,7 @@ impl Mount {
 
         let dest = Path::new(&dest_for_host);
         let source = m.source().as_ref().ok_or(MountError::NoSource)?;
-        let src = if typ == Some("bind") {
+        let src = if is_bind {
             let src = canonicalize(source).map_err(|err| {
                 tracing::error!("failed to canonicalize {:?}: {}", source, err);
                 err
@@ -546,7 +575,20 @@ impl Mount {
             PathBuf::from(source)
         };
 
-        if let Err(err) =
+        if let Some(fd) = idmapped_mount {
+            self.syscall
+                .move_mount(
+                    fd,
+                    Path::new(""),
+                    libc::AT_FDCWD,
+                    dest,
+                    linux::MOVE_MOUNT_F_EMPTY_PATH,
+                )
+                .map_err(|err| {
+                    tracing::error!("failed to attach idmapped mount {src:?} to {dest:?}");
+                    err
+                })?;
+        } else if let Err(err) =
             self.syscall
                 .mount(Some(&*src), dest, typ, mount_option_config.flags, Some(&*d))
         {
@@ -571,7 +613,7 @@ impl Mount {
                 })?;
         }
 
-        if typ == Some("bind")
+        if is_bind
             && mount_option_config.flags.intersects(
                 !(MsFlags::MS_REC
                     | MsFlags::MS_REMOUNT
@@ -611,6 +653,101 @@ impl Mount {
     }
 }
 
+/// Creates detached idmapped mounts for the mounts of the spec with the
+/// `idmap` or `ridmap` option or with id mappings, keyed by their destination.
+/// This has to be done on the host, before the container enters its user
+/// namespace, and the mounts are attached inside the container by
+/// `mount_into_container` afterwards.
+///
+/// Like runc, a mount is idmapped with its own `uidMappings` and
+/// `gidMappings`, and with the id mappings of the container user namespace if
+/// it has none.
+pub fn open_idmapped_mounts(
+    mounts: &[SpecMount],
+    user_ns_config: Option<&UserNamespaceConfig>,
+    syscall: &dyn Syscall,
+) -> Result<HashMap<PathBuf, OwnedFd>> {
+    let mut idmapped = Vec::new();
+    for m in mounts {
+        if let Some(idmap) = parse_mount(m)?.idmap {
+            idmapped.push((m, idmap));
+        }
+    }
+    if idmapped.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let user_ns_config = user_ns_config.ok_or_else(|| {
+        MountError::Custom("idmapped mounts require a new user namespace".to_string())
+    })?;
+    let create_user_ns_fd = |config: &UserNamespaceConfig| {
+        config.create_user_ns_fd().map_err(|err| {
+            tracing::error!(?err, "failed to create user namespace for idmapped mounts");
+            MountError::Other(err.into())
+        })
+    };
+    // the container user namespace is only created if a mount uses it
+    let mut container_user_ns: Option<OwnedFd> = None;
+
+    let mut idmapped_mounts = HashMap::new();
+    for (m, idmap) in idmapped {
+        let source = m.source().as_ref().ok_or(MountError::NoSource)?;
+        let recursive = match idmap {
+            MountIdmap::Idmap => 0,
+            MountIdmap::Ridmap => linux::AT_RECURSIVE,
+        };
+
+        let mount_user_ns;
+        let user_ns = match (m.uid_mappings(), m.gid_mappings()) {
+            (Some(uid_mappings), Some(gid_mappings)) => {
+                let config = UserNamespaceConfig {
+                    uid_mappings: Some(uid_mappings.clone()),
+                    gid_mappings: Some(gid_mappings.clone()),
+                    ..user_ns_config.clone()
+                };
+                mount_user_ns = create_user_ns_fd(&config)?;
+                &mount_user_ns
+            }
+            _ => match container_user_ns {
+                Some(ref user_ns) => user_ns,
+                None => container_user_ns.insert(create_user_ns_fd(user_ns_config)?),
+            },
+        };
+
+        let tree = syscall
+            .open_tree(
+                libc::AT_FDCWD,
+                source,
+                linux::OPEN_TREE_CLONE | linux::OPEN_TREE_CLOEXEC | recursive,
+            )
+            .map_err(|err| {
+                tracing::error!(?source, ?err, "failed to clone mount tree");
+                err
+            })?;
+        syscall
+            .mount_setattr(
+                tree.as_raw_fd(),
+                Path::new(""),
+                linux::AT_EMPTY_PATH | recursive,
+                &linux::MountAttr {
+                    attr_set: linux::MOUNT_ATTR_IDMAP,
+                    attr_clr: 0,
+                    propagation: 0,
+                    userns_fd: user_ns.as_raw_fd() as u64,
+                },
+                mem::size_of::<linux::MountAttr>(),
+            )
+            .map_err(|err| {
+                tracing::error!(?source, ?err, "failed to idmap mount");
+                err
+            })?;
+
+        idmapped_mounts.insert(m.destination().clone(), tree);
+    }
+
+    Ok(idmapped_mounts)
+}
+
 /// Find parent mount of rootfs in given mount infos
 pub fn find_parent_mount(
     rootfs: &Path,
@@ -635,7 +772,7 @@ mod tests {
     use anyhow::{Context, Ok, Result};
 
     use super::*;
-    use crate::syscall::test::{MountArgs, TestHelperSyscall};
+    use crate::syscall::test::{MountArgs, MoveMountArgs, TestHelperSyscall};
 
     #[test]
     fn test_mount_to_container() -> Result<()> {
@@ -662,7 +799,8 @@ mod tests {
                     mount,
                     tmp_dir.path(),
                     &mount_option_config,
-                    Some("defaults")
+                    Some("defaults"),
+                    None,
                 )
                 .is_ok());
 
@@ -700,7 +838,7 @@ mod tests {
                 .open(tmp_dir.path().join("null"))?;
 
             assert!(m
-                .mount_into_container(mount, tmp_dir.path(), &mount_option_config, None)
+                .mount_into_container(mount, tmp_dir.path(), &mount_option_config, None, None)
                 .is_ok());
 
             let want = vec![
@@ -729,6 +867,61 @@ mod tests {
             assert_eq!(want, *got);
             assert_eq!(got.len(), 2);
         }
+        {
+            let m = Mount::new();
+            let mount = &SpecMountBuilder::default()
+                .destination(PathBuf::from("/data"))
+                .typ("bind")
+                .source(tmp_dir.path().join("data"))
+                .options(vec!["rbind".to_string(), "idmap".to_string()])
+                .build()?;
+            let mount_option_config = parse_mount(mount)?;
+            create_dir_all(tmp_dir.path().join("data"))?;
+
+            assert!(m
+                .mount_into_container(mount, tmp_dir.path(), &mount_option_config, None, Some(7))
+                .is_ok());
+
+            let syscall = m
+                .syscall
+                .as_any()
+                .downcast_ref::<TestHelperSyscall>()
+                .unwrap();
+            assert_eq!(
+                syscall.get_move_mount_args(),
+                vec![MoveMountArgs {
+                    from_dirfd: 7,
+                    from_pathname: PathBuf::new(),
+                    to_dirfd: libc::AT_FDCWD,
+                    to_pathname: tmp_dir.path().join("data"),
+                    flags: linux::MOVE_MOUNT_F_EMPTY_PATH,
+                }]
+            );
+            assert!(syscall.get_mount_args().is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_idmapped_mounts_without_user_ns() -> Result<()> {
+        let syscall = TestHelperSyscall::default();
+        let mapping = oci_spec::runtime::LinuxIdMappingBuilder::default()
+            .host_id(1000u32)
+            .container_id(0u32)
+            .size(1u32)
+            .build()?;
+        let mut mount = SpecMountBuilder::default()
+            .destination(PathBuf::from("/data"))
+            .typ("bind")
+            .source(PathBuf::from("/data"))
+            .options(vec!["bind".to_string()])
+            .build()?;
+        assert!(open_idmapped_mounts(&[mount.clone()], None, &syscall)?.is_empty());
+
+        mount.set_uid_mappings(Some(vec![mapping]));
+        mount.set_gid_mappings(Some(vec![mapping]));
+        assert!(open_idmapped_mounts(&[mount], None, &syscall).is_err());
 
         Ok(())
     }
@@ -783,6 +976,7 @@ mod tests {
             root: tmp.path(),
             label: None,
             cgroup_ns: true,
+            idmapped_mounts: &HashMap::new(),
         };
 
         let subsystem_name = "cpu";
@@ -838,6 +1032,7 @@ mod tests {
             root: tmp.path(),
             label: None,
             cgroup_ns: false,
+            idmapped_mounts: &HashMap::new(),
         };
 
         let subsystem_name = "cpu";
@@ -899,6 +1094,7 @@ mod tests {
             root: tmp.path(),
             label: None,
             cgroup_ns: true,
+            idmapped_mounts: &HashMap::new(),
         };
 
         let mounter = Mount::new();
@@ -972,6 +1168,7 @@ mod tests {
             root: tmp.path(),
             label: None,
             cgroup_ns: true,
+            idmapped_mounts: &HashMap::new(),
         };
 
         let mounter = Mount::new();
@@ -982,6 +1179,7 @@ mod tests {
             flags,
             data: String::new(),
             rec_attr: None,
+            idmap: None,
         };
         mounter
             .mount_cgroup_v2(&spec_cgroup_mount, &mount_opts, &mount_option_config)
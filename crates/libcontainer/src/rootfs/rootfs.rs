@@ -1,5 +1,6 @@
-use std::collections::HashSet;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
 
 use nix::mount::MsFlags;
 use oci_spec::runtime::{Linux, Spec};
@@ -37,6 +38,7 @@ impl RootFS {
         spec: &Spec,
         rootfs: &Path,
         cgroup_ns: bool,
+        idmapped_mounts: &HashMap<PathBuf, RawFd>,
     ) -> Result<()> {
         let mut flags = MsFlags::MS_REC;
         match linux.rootfs_propagation().as_deref() {
@@ -82,6 +84,7 @@ impl RootFS {
             root: rootfs,
             label: linux.mount_label().as_deref(),
             cgroup_ns,
+            idmapped_mounts,
         };
 
         if let Some(mounts) = spec.mounts() {
@@ -98,11 +101,12 @@ impl RootFS {
         rootfs: &Path,
         bind_devices: bool,
         cgroup_ns: bool,
+        idmapped_mounts: &HashMap<PathBuf, RawFd>,
     ) -> Result<()> {
         tracing::debug!(?rootfs, "prepare rootfs");
         let linux = spec.linux().as_ref().ok_or(MissingSpecError::Linux)?;
 
-        self.mount_to_rootfs(linux, spec, rootfs, cgroup_ns)?;
+        self.mount_to_rootfs(linux, spec, rootfs, cgroup_ns, idmapped_mounts)?;
 
         let symlinker = Symlink::new();
         symlinker.setup_kcore_symlink(rootfs)?;
@@ -18,6 +18,19 @@ pub struct MountOptionConfig {
 
     /// RecAttr represents mount properties to be applied recursively.
     pub rec_attr: Option<linux::MountAttr>,
+
+    /// Idmap requested for the mount with the `idmap` or `ridmap` options or
+    /// by id mappings on the mount.
+    pub idmap: Option<MountIdmap>,
+}
+
+/// Id mapping applied to a bind mount with mount_setattr(2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MountIdmap {
+    /// Only the mount itself is idmapped.
+    Idmap,
+    /// The mount and all its submounts are idmapped.
+    Ridmap,
 }
 
 pub fn default_devices() -> Vec<LinuxDevice> {
@@ -82,10 +95,67 @@ pub fn to_sflag(dev_type: LinuxDeviceType) -> SFlag {
     }
 }
 
+/// Mount options that `parse_mount` translates into mount(2) flags. The boolean
+/// indicates whether the flag is cleared instead of set.
+const MOUNT_FLAG_OPTIONS: &[(&str, bool, MsFlags)] = &[
+    ("defaults", false, MsFlags::empty()),
+    ("ro", false, MsFlags::MS_RDONLY),
+    ("rw", true, MsFlags::MS_RDONLY),
+    ("suid", true, MsFlags::MS_NOSUID),
+    ("nosuid", false, MsFlags::MS_NOSUID),
+    ("dev", true, MsFlags::MS_NODEV),
+    ("nodev", false, MsFlags::MS_NODEV),
+    ("exec", true, MsFlags::MS_NOEXEC),
+    ("noexec", false, MsFlags::MS_NOEXEC),
+    ("sync", false, MsFlags::MS_SYNCHRONOUS),
+    ("async", true, MsFlags::MS_SYNCHRONOUS),
+    ("dirsync", false, MsFlags::MS_DIRSYNC),
+    ("remount", false, MsFlags::MS_REMOUNT),
+    ("mand", false, MsFlags::MS_MANDLOCK),
+    ("nomand", true, MsFlags::MS_MANDLOCK),
+    ("atime", true, MsFlags::MS_NOATIME),
+    ("noatime", false, MsFlags::MS_NOATIME),
+    ("diratime", true, MsFlags::MS_NODIRATIME),
+    ("nodiratime", false, MsFlags::MS_NODIRATIME),
+    ("bind", false, MsFlags::MS_BIND),
+    ("rbind", false, MsFlags::MS_BIND.union(MsFlags::MS_REC)),
+    ("unbindable", false, MsFlags::MS_UNBINDABLE),
+    (
+        "runbindable",
+        false,
+        MsFlags::MS_UNBINDABLE.union(MsFlags::MS_REC),
+    ),
+    ("private", true, MsFlags::MS_PRIVATE),
+    ("rprivate", true, MsFlags::MS_PRIVATE.union(MsFlags::MS_REC)),
+    ("shared", true, MsFlags::MS_SHARED),
+    ("rshared", true, MsFlags::MS_SHARED.union(MsFlags::MS_REC)),
+    ("slave", true, MsFlags::MS_SLAVE),
+    ("rslave", true, MsFlags::MS_SLAVE.union(MsFlags::MS_REC)),
+    ("relatime", true, MsFlags::MS_RELATIME),
+    ("norelatime", true, MsFlags::MS_RELATIME),
+    ("strictatime", true, MsFlags::MS_STRICTATIME),
+    ("nostrictatime", true, MsFlags::MS_STRICTATIME),
+];
+
+/// Mount options requesting an idmapped mount.
+const IDMAP_OPTIONS: &[&str] = &["idmap", "ridmap"];
+
+/// Returns every mount option understood by `parse_mount`, both the ones
+/// mapped to mount(2) flags and the ones applied with mount_setattr(2).
+pub fn known_mount_options() -> Vec<&'static str> {
+    MOUNT_FLAG_OPTIONS
+        .iter()
+        .map(|(name, _, _)| *name)
+        .chain(MountRecursive::OPTIONS.iter().copied())
+        .chain(IDMAP_OPTIONS.iter().copied())
+        .collect()
+}
+
 pub fn parse_mount(m: &Mount) -> std::result::Result<MountOptionConfig, MountError> {
     let mut flags = MsFlags::empty();
     let mut data = Vec::new();
     let mut mount_attr: Option<linux::MountAttr> = None;
+    let mut idmap: Option<MountIdmap> = None;
 
     if let Some(options) = &m.options() {
         for option in options {
@@ -131,51 +201,26 @@ pub fn parse_mount(m: &Mount) -> std::result::Result<MountOptionConfig, MountErr
                 continue;
             }
 
-            if let Some((is_clear, flag)) = match option.as_str() {
-                "defaults" => Some((false, MsFlags::empty())),
-                "ro" => Some((false, MsFlags::MS_RDONLY)),
-                "rw" => Some((true, MsFlags::MS_RDONLY)),
-                "suid" => Some((true, MsFlags::MS_NOSUID)),
-                "nosuid" => Some((false, MsFlags::MS_NOSUID)),
-                "dev" => Some((true, MsFlags::MS_NODEV)),
-                "nodev" => Some((false, MsFlags::MS_NODEV)),
-                "exec" => Some((true, MsFlags::MS_NOEXEC)),
-                "noexec" => Some((false, MsFlags::MS_NOEXEC)),
-                "sync" => Some((false, MsFlags::MS_SYNCHRONOUS)),
-                "async" => Some((true, MsFlags::MS_SYNCHRONOUS)),
-                "dirsync" => Some((false, MsFlags::MS_DIRSYNC)),
-                "remount" => Some((false, MsFlags::MS_REMOUNT)),
-                "mand" => Some((false, MsFlags::MS_MANDLOCK)),
-                "nomand" => Some((true, MsFlags::MS_MANDLOCK)),
-                "atime" => Some((true, MsFlags::MS_NOATIME)),
-                "noatime" => Some((false, MsFlags::MS_NOATIME)),
-                "diratime" => Some((true, MsFlags::MS_NODIRATIME)),
-                "nodiratime" => Some((false, MsFlags::MS_NODIRATIME)),
-                "bind" => Some((false, MsFlags::MS_BIND)),
-                "rbind" => Some((false, MsFlags::MS_BIND | MsFlags::MS_REC)),
-                "unbindable" => Some((false, MsFlags::MS_UNBINDABLE)),
-                "runbindable" => Some((false, MsFlags::MS_UNBINDABLE | MsFlags::MS_REC)),
-                "private" => Some((true, MsFlags::MS_PRIVATE)),
-                "rprivate" => Some((true, MsFlags::MS_PRIVATE | MsFlags::MS_REC)),
-                "shared" => Some((true, MsFlags::MS_SHARED)),
-                "rshared" => Some((true, MsFlags::MS_SHARED | MsFlags::MS_REC)),
-                "slave" => Some((true, MsFlags::MS_SLAVE)),
-                "rslave" => Some((true, MsFlags::MS_SLAVE | MsFlags::MS_REC)),
-                "relatime" => Some((true, MsFlags::MS_RELATIME)),
-                "norelatime" => Some((true, MsFlags::MS_RELATIME)),
-                "strictatime" => Some((true, MsFlags::MS_STRICTATIME)),
-                "nostrictatime" => Some((true, MsFlags::MS_STRICTATIME)),
-                unknown => {
-                    if unknown == "idmap" || unknown == "ridmap" {
-                        return Err(MountError::UnsupportedMountOption(unknown.to_string()));
-                    }
-                    None
+            match option.as_str() {
+                "idmap" => {
+                    idmap.get_or_insert(MountIdmap::Idmap);
+                    continue;
+                }
+                "ridmap" => {
+                    idmap = Some(MountIdmap::Ridmap);
+                    continue;
                 }
-            } {
-                if is_clear {
-                    flags &= !flag;
+                _ => {}
+            }
+
+            if let Some((_, is_clear, flag)) = MOUNT_FLAG_OPTIONS
+                .iter()
+                .find(|(name, _, _)| *name == option.as_str())
+            {
+                if *is_clear {
+                    flags &= !*flag;
                 } else {
-                    flags |= flag;
+                    flags |= *flag;
                 }
                 continue;
             }
@@ -183,17 +228,45 @@ pub fn parse_mount(m: &Mount) -> std::result::Result<MountOptionConfig, MountErr
             data.push(option.as_str());
         }
     }
+    match (m.uid_mappings(), m.gid_mappings()) {
+        // like runc, id mappings on a mount idmap it even without the option
+        (Some(_), Some(_)) => {
+            idmap.get_or_insert(MountIdmap::Idmap);
+        }
+        (None, None) => {}
+        _ => {
+            return Err(MountError::Custom(format!(
+                "idmapped mounts require both uid and gid mappings: {:?}",
+                m.destination()
+            )));
+        }
+    }
+    if idmap.is_some() {
+        if !flags.contains(MsFlags::MS_BIND) && m.typ().as_deref() != Some("bind") {
+            return Err(MountError::Custom(format!(
+                "idmapped mounts are only supported for bind mounts: {:?}",
+                m.destination()
+            )));
+        }
+
+        // like runc, an idmapped rbind mount idmaps the whole tree
+        if flags.contains(MsFlags::MS_REC) {
+            idmap = Some(MountIdmap::Ridmap);
+        }
+    }
+
     Ok(MountOptionConfig {
         flags,
         data: data.join(","),
         rec_attr: mount_attr,
+        idmap,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
-    use oci_spec::runtime::MountBuilder;
+    use oci_spec::runtime::{LinuxIdMappingBuilder, MountBuilder};
 
     use super::*;
     use crate::syscall::linux::MountAttr;
@@ -224,6 +297,7 @@ mod tests {
                 flags: MsFlags::empty(),
                 data: "".to_string(),
                 rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -246,6 +320,7 @@ mod tests {
                 flags: MsFlags::MS_NOSUID,
                 data: "mode=755,size=65536k".to_string(),
                 rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -270,7 +345,8 @@ mod tests {
             MountOptionConfig {
                 flags: MsFlags::MS_NOSUID | MsFlags::MS_NOEXEC,
                 data: "newinstance,ptmxmode=0666,mode=0620,gid=5".to_string(),
-                rec_attr: None
+                rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -293,7 +369,8 @@ mod tests {
             MountOptionConfig {
                 flags: MsFlags::MS_NOSUID | MsFlags::MS_NOEXEC | MsFlags::MS_NODEV,
                 data: "mode=1777,size=65536k".to_string(),
-                rec_attr: None
+                rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -315,7 +392,8 @@ mod tests {
             MountOptionConfig {
                 flags: MsFlags::MS_NOSUID | MsFlags::MS_NOEXEC | MsFlags::MS_NODEV,
                 data: "".to_string(),
-                rec_attr: None
+                rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -341,6 +419,7 @@ mod tests {
                     | MsFlags::MS_RDONLY,
                 data: "".to_string(),
                 rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -366,7 +445,8 @@ mod tests {
                     | MsFlags::MS_NODEV
                     | MsFlags::MS_RDONLY,
                 data: "".to_string(),
-                rec_attr: None
+                rec_attr: None,
+                idmap: None,
             },
             mount_option_config,
         );
@@ -424,6 +504,7 @@ mod tests {
                     | MsFlags::MS_UNBINDABLE,
                 data: "".to_string(),
                 rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -457,11 +538,76 @@ mod tests {
             MountOptionConfig {
                 flags: MsFlags::empty(),
                 data: "".to_string(),
-                rec_attr: Some(MountAttr::all())
+                rec_attr: Some(MountAttr::all()),
+                idmap: None,
             },
             mount_option_config
         );
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_mount_idmap() -> Result<()> {
+        let bind_mount = |options: &[&str]| {
+            MountBuilder::default()
+                .destination(PathBuf::from("/data"))
+                .typ("bind")
+                .source(PathBuf::from("/data"))
+                .options(options.iter().map(|o| o.to_string()).collect::<Vec<_>>())
+                .build()
+        };
+
+        let mount_option_config = parse_mount(&bind_mount(&["bind", "idmap"])?)?;
+        assert_eq!(Some(MountIdmap::Idmap), mount_option_config.idmap);
+        assert!(mount_option_config.data.is_empty());
+
+        let mount_option_config = parse_mount(&bind_mount(&["bind", "ridmap"])?)?;
+        assert_eq!(Some(MountIdmap::Ridmap), mount_option_config.idmap);
+
+        let mount_option_config = parse_mount(&bind_mount(&["rbind", "idmap"])?)?;
+        assert_eq!(Some(MountIdmap::Ridmap), mount_option_config.idmap);
+
+        let mount_option_config = parse_mount(&bind_mount(&["rbind"])?)?;
+        assert_eq!(None, mount_option_config.idmap);
+
+        let mapping = LinuxIdMappingBuilder::default()
+            .host_id(1000u32)
+            .container_id(0u32)
+            .size(1u32)
+            .build()?;
+        let mut mapped = bind_mount(&["bind"])?;
+        mapped.set_uid_mappings(Some(vec![mapping]));
+        assert!(parse_mount(&mapped).is_err());
+        mapped.set_gid_mappings(Some(vec![mapping]));
+        assert_eq!(Some(MountIdmap::Idmap), parse_mount(&mapped)?.idmap);
+
+        let tmpfs = MountBuilder::default()
+            .destination(PathBuf::from("/tmp"))
+            .typ("tmpfs")
+            .source(PathBuf::from("tmpfs"))
+            .options(vec!["idmap".to_string()])
+            .build()?;
+        assert!(parse_mount(&tmpfs).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_known_mount_options() -> Result<()> {
+        for option in known_mount_options() {
+            let mount_option_config = parse_mount(
+                &MountBuilder::default()
+                    .typ("bind")
+                    .options(vec![option.to_string()])
+                    .build()?,
+            )?;
+            assert!(
+                mount_option_config.data.is_empty(),
+                "{option} should not be passed as mount data"
+            );
+        }
+
+        Ok(())
+    }
 }
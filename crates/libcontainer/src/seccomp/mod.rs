@@ -3,6 +3,7 @@ use std::os::unix::io;
 
 use libseccomp::{
     ScmpAction, ScmpArch, ScmpArgCompare, ScmpCompareOp, ScmpFilterContext, ScmpSyscall,
+    ScmpVersion,
 };
 use oci_spec::runtime::{
     Arch, LinuxSeccomp, LinuxSeccompAction, LinuxSeccompFilterFlag, LinuxSeccompOperator,
@@ -43,6 +44,8 @@ pub enum SeccompError {
         source: libseccomp::error::SeccompError,
         flag: LinuxSeccompFilterFlag,
     },
+    #[error("seccomp filter flag {0:?} is not supported")]
+    UnsupportedFilterFlag(LinuxSeccompFilterFlag),
     #[error("failed to set SCMP_FLTATR_CTL_NNP")]
     SetCtlNnp {
         source: libseccomp::error::SeccompError,
@@ -51,6 +54,86 @@ pub enum SeccompError {
 
 type Result<T> = std::result::Result<T, SeccompError>;
 
+/// Seccomp actions that `initialize_seccomp` can translate into libseccomp actions.
+pub const SUPPORTED_ACTIONS: &[LinuxSeccompAction] = &[
+    LinuxSeccompAction::ScmpActKill,
+    LinuxSeccompAction::ScmpActKillProcess,
+    LinuxSeccompAction::ScmpActKillThread,
+    LinuxSeccompAction::ScmpActTrap,
+    LinuxSeccompAction::ScmpActErrno,
+    LinuxSeccompAction::ScmpActTrace,
+    LinuxSeccompAction::ScmpActAllow,
+    LinuxSeccompAction::ScmpActLog,
+    LinuxSeccompAction::ScmpActNotify,
+];
+
+/// Argument comparison operators that `initialize_seccomp` understands.
+pub const SUPPORTED_OPERATORS: &[LinuxSeccompOperator] = &[
+    LinuxSeccompOperator::ScmpCmpNe,
+    LinuxSeccompOperator::ScmpCmpLt,
+    LinuxSeccompOperator::ScmpCmpLe,
+    LinuxSeccompOperator::ScmpCmpEq,
+    LinuxSeccompOperator::ScmpCmpGe,
+    LinuxSeccompOperator::ScmpCmpGt,
+    LinuxSeccompOperator::ScmpCmpMaskedEq,
+];
+
+/// Architectures that can be added to the seccomp filter.
+pub const SUPPORTED_ARCHS: &[Arch] = &[
+    Arch::ScmpArchX86,
+    Arch::ScmpArchX86_64,
+    Arch::ScmpArchX32,
+    Arch::ScmpArchArm,
+    Arch::ScmpArchAarch64,
+    Arch::ScmpArchMips,
+    Arch::ScmpArchMips64,
+    Arch::ScmpArchMips64n32,
+    Arch::ScmpArchMipsel,
+    Arch::ScmpArchMipsel64,
+    Arch::ScmpArchMipsel64n32,
+    Arch::ScmpArchPpc,
+    Arch::ScmpArchPpc64,
+    Arch::ScmpArchPpc64le,
+    Arch::ScmpArchS390,
+    Arch::ScmpArchS390x,
+    Arch::ScmpArchRiscv64,
+];
+
+/// Filter flags that `initialize_seccomp` knows how to apply to the seccomp
+/// filter context. Whether they can be applied depends on the libseccomp
+/// library and the kernel, see `supported_flags`.
+pub const SUPPORTED_FLAGS: &[LinuxSeccompFilterFlag] = &[
+    LinuxSeccompFilterFlag::SeccompFilterFlagTsync,
+    LinuxSeccompFilterFlag::SeccompFilterFlagSpecAllow,
+    LinuxSeccompFilterFlag::SeccompFilterFlagLog,
+];
+
+/// Returns the filter flags that both the libseccomp library youki is linked
+/// against and the running kernel support. The API level reported by
+/// libseccomp reflects the seccomp features of the kernel.
+pub fn supported_flags() -> Vec<LinuxSeccompFilterFlag> {
+    SUPPORTED_FLAGS
+        .iter()
+        .copied()
+        .filter(|flag| {
+            let (level, version) = match flag {
+                LinuxSeccompFilterFlag::SeccompFilterFlagTsync => (2, (2, 2, 0)),
+                LinuxSeccompFilterFlag::SeccompFilterFlagLog => (3, (2, 4, 0)),
+                LinuxSeccompFilterFlag::SeccompFilterFlagSpecAllow => (4, (2, 5, 0)),
+                LinuxSeccompFilterFlag::SeccompFilterFlagWaitKillableRecv => return false,
+            };
+            libseccomp::check_api(level, ScmpVersion::from(version)).unwrap_or(false)
+        })
+        .collect()
+}
+
+/// Returns the version of the libseccomp library youki is linked against.
+pub fn libseccomp_version() -> Option<String> {
+    ScmpVersion::current()
+        .ok()
+        .map(|v| format!("{}.{}.{}", v.major, v.minor, v.micro))
+}
+
 fn translate_arch(arch: Arch) -> ScmpArch {
     match arch {
         Arch::ScmpArchNative => ScmpArch::Native,
@@ -157,6 +240,10 @@ pub fn initialize_seccomp(seccomp: &LinuxSeccomp) -> Result<Option<io::RawFd>> {
                 LinuxSeccompFilterFlag::SeccompFilterFlagLog => ctx.set_ctl_log(true),
                 LinuxSeccompFilterFlag::SeccompFilterFlagTsync => ctx.set_ctl_tsync(true),
                 LinuxSeccompFilterFlag::SeccompFilterFlagSpecAllow => ctx.set_ctl_ssb(true),
+                // libseccomp-rs has no attribute for SCMP_FLTATR_CTL_WAITKILL
+                LinuxSeccompFilterFlag::SeccompFilterFlagWaitKillableRecv => {
+                    return Err(SeccompError::UnsupportedFilterFlag(*flag));
+                }
             }
             .map_err(|err| SeccompError::SetFilterFlag {
                 source: err,
@@ -285,6 +372,7 @@ pub fn is_notify(seccomp: &LinuxSeccomp) -> bool {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
     use std::path;
 
     use anyhow::{Context, Result};
@@ -392,4 +480,46 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_supported_actions() -> Result<()> {
+        for action in SUPPORTED_ACTIONS {
+            translate_action(*action, None)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_supported_archs() -> Result<()> {
+        let mut ctx = ScmpFilterContext::new_filter(ScmpAction::Allow)?;
+        let mut translated = HashSet::new();
+        for arch in SUPPORTED_ARCHS {
+            assert_ne!(*arch, Arch::ScmpArchNative);
+            let scmp_arch = translate_arch(*arch);
+            assert!(translated.insert(scmp_arch), "{arch:?} is listed twice");
+            ctx.add_arch(scmp_arch)
+                .with_context(|| format!("failed to add {arch:?}"))?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_supported_operators() -> Result<()> {
+        let mut ctx = ScmpFilterContext::new_filter(ScmpAction::Allow)?;
+        let syscall = ScmpSyscall::from_name("getpid")?;
+        let mut translated = HashSet::new();
+        for op in SUPPORTED_OPERATORS {
+            let scmp_op = translate_op(*op, Some(1));
+            assert!(translated.insert(scmp_op), "{op:?} is listed twice");
+            let cmp = ScmpArgCompare::new(0, scmp_op, 1);
+            ctx.add_rule_conditional(ScmpAction::Errno(libc::EPERM), syscall, &[cmp])
+                .with_context(|| format!("failed to add a rule with {op:?}"))?;
+        }
+        // the runtime spec defines seven operators, all of them are supported
+        assert_eq!(SUPPORTED_OPERATORS.len(), 7);
+
+        Ok(())
+    }
 }
@@ -1,7 +1,7 @@
 //! Implements Command trait for Linux systems
 use std::any::Any;
 use std::ffi::{CStr, CString, OsStr};
-use std::os::fd::BorrowedFd;
+use std::os::fd::{BorrowedFd, FromRawFd, OwnedFd};
 use std::os::unix::ffi::OsStrExt;
 use std::os::unix::fs::symlink;
 use std::os::unix::io::RawFd;
@@ -37,6 +37,13 @@ const MOUNT_ATTR_NOATIME: u64 = 0x00000010;
 const MOUNT_ATTR_STRICTATIME: u64 = 0x00000020;
 const MOUNT_ATTR_NODIRATIME: u64 = 0x00000080;
 const MOUNT_ATTR_NOSYMFOLLOW: u64 = 0x00200000;
+pub const MOUNT_ATTR_IDMAP: u64 = 0x00100000; // Idmap the mount to the user namespace in userns_fd.
+
+// Flags used in open_tree(2) and move_mount(2).
+pub const OPEN_TREE_CLONE: u32 = 1; // Clone the mount tree instead of opening it.
+pub const OPEN_TREE_CLOEXEC: u32 = libc::O_CLOEXEC as u32;
+pub const MOVE_MOUNT_F_EMPTY_PATH: u32 = 0x00000004; // Move the mount referred to by from_dirfd.
+pub const AT_EMPTY_PATH: u32 = libc::AT_EMPTY_PATH as u32;
 
 /// Constants used by mount_setattr(2).
 pub enum MountRecursive {
@@ -71,6 +78,30 @@ pub enum MountRecursive {
     Nosymfollow(bool, u64),
 }
 
+impl MountRecursive {
+    /// Option names accepted by `MountRecursive::from_str`.
+    pub const OPTIONS: &'static [&'static str] = &[
+        "rro",
+        "rrw",
+        "rnosuid",
+        "rsuid",
+        "rnodev",
+        "rdev",
+        "rnoexec",
+        "rexec",
+        "rnodiratime",
+        "rdiratime",
+        "rrelatime",
+        "rnorelatime",
+        "rnoatime",
+        "ratime",
+        "rstrictatime",
+        "rnostrictatime",
+        "rnosymfollow",
+        "rsymfollow",
+    ];
+}
+
 impl FromStr for MountRecursive {
     type Err = SyscallError;
 
@@ -94,7 +125,8 @@ impl FromStr for MountRecursive {
             "rnostrictatime" => Ok(MountRecursive::StrictAtime(true, MOUNT_ATTR_STRICTATIME)),
             "rnosymfollow" => Ok(MountRecursive::Nosymfollow(false, MOUNT_ATTR_NOSYMFOLLOW)),
             "rsymfollow" => Ok(MountRecursive::Nosymfollow(true, MOUNT_ATTR_NOSYMFOLLOW)),
-            // No support for MOUNT_ATTR_IDMAP yet (needs UserNS FD)
+            // MOUNT_ATTR_IDMAP needs a user namespace fd, so idmap and ridmap
+            // are handled while preparing the idmapped mounts instead.
             _ => Err(SyscallError::UnexpectedMountRecursiveOption(
                 option.to_string(),
             )),
@@ -526,18 +558,7 @@ impl Syscall for LinuxSyscall {
         mount_attr: &MountAttr,
         size: libc::size_t,
     ) -> Result<()> {
-        let path_c_string = pathname
-            .to_path_buf()
-            .to_str()
-            .map(CString::new)
-            .ok_or_else(|| {
-                tracing::error!(path = ?pathname, "failed to convert path to string");
-                nix::Error::EINVAL
-            })?
-            .map_err(|err| {
-                tracing::error!(path = ?pathname, ?err, "failed to convert path to string");
-                nix::Error::EINVAL
-            })?;
+        let path_c_string = path_to_c_string(pathname)?;
 
         match unsafe {
             libc::syscall(
@@ -556,6 +577,43 @@ impl Syscall for LinuxSyscall {
         Ok(())
     }
 
+    fn open_tree(&self, dirfd: RawFd, pathname: &Path, flags: u32) -> Result<OwnedFd> {
+        let path_c_string = path_to_c_string(pathname)?;
+
+        match unsafe { libc::syscall(libc::SYS_open_tree, dirfd, path_c_string.as_ptr(), flags) } {
+            -1 => Err(nix::Error::last().into()),
+            fd => Ok(unsafe { OwnedFd::from_raw_fd(fd as RawFd) }),
+        }
+    }
+
+    fn move_mount(
+        &self,
+        from_dirfd: RawFd,
+        from_pathname: &Path,
+        to_dirfd: RawFd,
+        to_pathname: &Path,
+        flags: u32,
+    ) -> Result<()> {
+        let from_c_string = path_to_c_string(from_pathname)?;
+        let to_c_string = path_to_c_string(to_pathname)?;
+
+        match unsafe {
+            libc::syscall(
+                libc::SYS_move_mount,
+                from_dirfd,
+                from_c_string.as_ptr(),
+                to_dirfd,
+                to_c_string.as_ptr(),
+                flags,
+            )
+        } {
+            0 => Ok(()),
+            -1 => Err(nix::Error::last()),
+            _ => Err(nix::Error::UnknownErrno),
+        }?;
+        Ok(())
+    }
+
     fn set_io_priority(&self, class: i64, priority: i64) -> Result<()> {
         let ioprio_who_progress: libc::c_int = 1;
         let ioprio_who_pid = 0;
@@ -581,6 +639,20 @@ impl Syscall for LinuxSyscall {
     }
 }
 
+fn path_to_c_string(path: &Path) -> Result<CString> {
+    Ok(path
+        .to_str()
+        .map(CString::new)
+        .ok_or_else(|| {
+            tracing::error!(?path, "failed to convert path to string");
+            nix::Error::EINVAL
+        })?
+        .map_err(|err| {
+            tracing::error!(?path, ?err, "failed to convert path to string");
+            nix::Error::EINVAL
+        })?)
+}
+
 #[cfg(test)]
 mod tests {
     // Note: We have to run these tests here as serial. The main issue is that
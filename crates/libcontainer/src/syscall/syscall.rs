@@ -3,6 +3,8 @@
 //! implementation details
 use std::any::Any;
 use std::ffi::OsStr;
+use std::os::fd::OwnedFd;
+use std::os::unix::io::RawFd;
 use std::path::Path;
 use std::sync::Arc;
 
@@ -53,6 +55,15 @@ pub trait Syscall {
         mount_attr: &MountAttr,
         size: libc::size_t,
     ) -> Result<()>;
+    fn open_tree(&self, dirfd: RawFd, pathname: &Path, flags: u32) -> Result<OwnedFd>;
+    fn move_mount(
+        &self,
+        from_dirfd: RawFd,
+        from_pathname: &Path,
+        to_dirfd: RawFd,
+        to_pathname: &Path,
+        flags: u32,
+    ) -> Result<()>;
     fn set_io_priority(&self, class: i64, priority: i64) -> Result<()>;
     fn umount2(&self, target: &Path, flags: MntFlags) -> Result<()>;
 }
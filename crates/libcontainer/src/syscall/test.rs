@@ -2,6 +2,8 @@ use std::any::Any;
 use std::cell::{Ref, RefCell, RefMut};
 use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
+use std::os::fd::OwnedFd;
+use std::os::unix::io::RawFd;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -50,6 +52,15 @@ pub struct UMount2Args {
     pub flags: MntFlags,
 }
 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MoveMountArgs {
+    pub from_dirfd: RawFd,
+    pub from_pathname: PathBuf,
+    pub to_dirfd: RawFd,
+    pub to_pathname: PathBuf,
+    pub flags: u32,
+}
+
 #[derive(Default)]
 struct Mock {
     values: Vec<Box<dyn Any>>,
@@ -71,6 +82,7 @@ pub enum ArgName {
     Capability,
     IoPriority,
     UMount2,
+    MoveMount,
 }
 
 impl ArgName {
@@ -87,6 +99,7 @@ impl ArgName {
             ArgName::Groups,
             ArgName::Capability,
             ArgName::IoPriority,
+            ArgName::MoveMount,
         ]
         .iter()
         .copied()
@@ -260,6 +273,30 @@ impl Syscall for TestHelperSyscall {
         todo!()
     }
 
+    fn open_tree(&self, _: RawFd, _: &Path, _: u32) -> Result<OwnedFd> {
+        todo!()
+    }
+
+    fn move_mount(
+        &self,
+        from_dirfd: RawFd,
+        from_pathname: &Path,
+        to_dirfd: RawFd,
+        to_pathname: &Path,
+        flags: u32,
+    ) -> Result<()> {
+        self.mocks.act(
+            ArgName::MoveMount,
+            Box::new(MoveMountArgs {
+                from_dirfd,
+                from_pathname: from_pathname.to_owned(),
+                to_dirfd,
+                to_pathname: to_pathname.to_owned(),
+                flags,
+            }),
+        )
+    }
+
     fn set_io_priority(&self, class: i64, priority: i64) -> Result<()> {
         self.mocks.act(
             ArgName::IoPriority,
@@ -395,4 +432,13 @@ impl TestHelperSyscall {
             .map(|x| x.downcast_ref::<UMount2Args>().unwrap().clone())
             .collect::<Vec<UMount2Args>>()
     }
+
+    pub fn get_move_mount_args(&self) -> Vec<MoveMountArgs> {
+        self.mocks
+            .fetch(ArgName::MoveMount)
+            .values
+            .iter()
+            .map(|x| x.downcast_ref::<MoveMountArgs>().unwrap().clone())
+            .collect::<Vec<MoveMountArgs>>()
+    }
 }
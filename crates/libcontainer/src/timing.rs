@@ -0,0 +1,146 @@
+//! Timings of the phases of creating and starting a container
+//!
+//! Each phase is measured by a guard, which also runs the phase in a tracing
+//! span. The phases are recorded per process: the intermediate and the init
+//! process send the phases they recorded over the main channel before they
+//! report to be ready, so once a container is created the main process holds
+//! the timings of all three processes and can [`take`] them.
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+static PHASES: Mutex<Vec<PhaseTiming>> = Mutex::new(Vec::new());
+
+/// The process a phase ran in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PhaseProcess {
+    Main,
+    Intermediate,
+    Init,
+}
+
+impl PhaseProcess {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PhaseProcess::Main => "main",
+            PhaseProcess::Intermediate => "intermediate",
+            PhaseProcess::Init => "init",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseTiming {
+    pub process: PhaseProcess,
+    pub phase: String,
+    /// Start of the phase in nanoseconds since the unix epoch. The wall clock
+    /// is used because the monotonic clock of the init process is offset by
+    /// its time namespace.
+    pub start_ns: u64,
+    pub duration_ns: u64,
+}
+
+/// Measures a phase until it is dropped
+#[must_use = "the phase ends when the guard is dropped"]
+pub struct Phase {
+    process: PhaseProcess,
+    name: &'static str,
+    start: SystemTime,
+    started: Instant,
+    _span: tracing::span::EnteredSpan,
+}
+
+/// Starts measuring the phase `name` of `process`
+pub fn phase(process: PhaseProcess, name: &'static str) -> Phase {
+    Phase {
+        process,
+        name,
+        start: SystemTime::now(),
+        started: Instant::now(),
+        _span: span(process, name),
+    }
+}
+
+/// Runs the phase `name` of `process` in a tracing span without recording
+/// it, for the phases after the process reported its timings, like the ones
+/// of the init process on start
+pub fn span(process: PhaseProcess, name: &'static str) -> tracing::span::EnteredSpan {
+    tracing::debug_span!("phase", process = process.as_str(), phase = name).entered()
+}
+
+impl Drop for Phase {
+    fn drop(&mut self) {
+        let duration = self.started.elapsed();
+        tracing::debug!(duration_us = duration.as_micros() as u64, "phase finished");
+        let start_ns = self
+            .start
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        phases().push(PhaseTiming {
+            process: self.process,
+            phase: self.name.to_owned(),
+            start_ns,
+            duration_ns: duration.as_nanos() as u64,
+        });
+    }
+}
+
+/// Adds the phases another process recorded
+pub fn record(timings: Vec<PhaseTiming>) {
+    phases().extend(timings);
+}
+
+/// Removes and returns the recorded phases, ordered by their start
+pub fn take() -> Vec<PhaseTiming> {
+    let mut timings = std::mem::take(&mut *phases());
+    timings.sort_by_key(|timing| timing.start_ns);
+    timings
+}
+
+/// Drops the phases a cloned process inherited from its parent, which the
+/// parent reports itself
+pub fn clear() {
+    phases().clear();
+}
+
+fn phases() -> MutexGuard<'static, Vec<PhaseTiming>> {
+    // a panic while holding the lock can't leave the list inconsistent
+    PHASES.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    #[test]
+    #[serial]
+    fn test_phases() {
+        clear();
+        {
+            let _outer = phase(PhaseProcess::Main, "outer");
+            let inner = phase(PhaseProcess::Main, "inner");
+            drop(inner);
+        }
+        record(vec![PhaseTiming {
+            process: PhaseProcess::Init,
+            phase: "rootfs".to_owned(),
+            start_ns: 0,
+            duration_ns: 10,
+        }]);
+
+        let timings = take();
+        assert_eq!(timings.len(), 3);
+        assert_eq!(timings[0].phase, "rootfs");
+        let find = |name: &str| timings.iter().find(|t| t.phase == name).unwrap();
+        let (outer, inner) = (find("outer"), find("inner"));
+        assert_eq!(outer.process, PhaseProcess::Main);
+        assert!(outer.start_ns <= inner.start_ns);
+        assert!(outer.duration_ns >= inner.duration_ns);
+        assert!(take().is_empty());
+    }
+}
@@ -148,6 +148,26 @@ pub fn setup_console(console_fd: RawFd) -> Result<()> {
     Ok(())
 }
 
+/// Sends the master end of an existing pseudo terminal over the console
+/// socket, like the one criu creates for a restored container.
+pub fn send_pty_master(console_fd: OwnedFd, master: &OwnedFd) -> Result<()> {
+    let pty_name: &[u8] = b"/dev/ptmx";
+    let iov = [IoSlice::new(pty_name)];
+
+    let fds = [master.as_raw_fd()];
+    let cmsg = socket::ControlMessage::ScmRights(&fds);
+    socket::sendmsg::<UnixAddr>(
+        console_fd.as_raw_fd(),
+        &iov,
+        &[cmsg],
+        socket::MsgFlags::empty(),
+        None,
+    )
+    .map_err(|err| TTYError::SendPtyMaster { source: err })?;
+
+    Ok(())
+}
+
 fn connect_stdio(stdin: &RawFd, stdout: &RawFd, stderr: &RawFd) -> Result<()> {
     dup2(stdin.as_raw_fd(), StdIO::Stdin.into()).map_err(|err| TTYError::ConnectStdIO {
         source: err,
@@ -241,4 +261,37 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    #[serial]
+    fn test_send_pty_master() -> Result<()> {
+        let testdir = tempfile::tempdir()?;
+        let socket_path = Path::join(testdir.path(), "test-socket");
+        let lis = UnixListener::bind(&socket_path);
+        assert!(lis.is_ok());
+        let fd = setup_console_socket(testdir.path(), &socket_path, CONSOLE_SOCKET)?;
+        let pty = nix::pty::openpty(None, None)?;
+        send_pty_master(fd, &pty.master)?;
+
+        // the console socket receives a pseudo terminal
+        let (stream, _) = lis?.accept()?;
+        let mut buf = [0u8; 64];
+        let mut cmsg = nix::cmsg_space!(RawFd);
+        let mut iov = [std::io::IoSliceMut::new(&mut buf)];
+        let msg = socket::recvmsg::<UnixAddr>(
+            stream.as_raw_fd(),
+            &mut iov,
+            Some(&mut cmsg),
+            socket::MsgFlags::empty(),
+        )?;
+        let received = msg.cmsgs().find_map(|cmsg| match cmsg {
+            socket::ControlMessageOwned::ScmRights(fds) => fds.first().copied(),
+            _ => None,
+        });
+        let received = received.expect("no fd received");
+        assert!(nix::unistd::isatty(received)?);
+        close(received)?;
+
+        Ok(())
+    }
 }
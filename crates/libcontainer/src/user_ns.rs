@@ -1,12 +1,16 @@
+use std::os::fd::OwnedFd;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::{env, fs};
 
+use nix::sys::signal::{self, Signal};
+use nix::sys::wait::waitpid;
 use nix::unistd::Pid;
 use oci_spec::runtime::{Linux, LinuxIdMapping, LinuxNamespace, LinuxNamespaceType, Mount, Spec};
 
 use crate::error::MissingSpecError;
 use crate::namespaces::{NamespaceError, Namespaces};
+use crate::process::fork::{self, CloneError};
 use crate::utils;
 
 // Wrap the uid/gid path function into a struct for dependency injection. This
@@ -79,6 +83,8 @@ pub enum UserNamespaceError {
     UnknownUnprivilegedUsernsClone(u8),
     #[error(transparent)]
     IDMapping(#[from] MappingError),
+    #[error("failed to create user namespace")]
+    CreateUserNamespace(#[source] CloneError),
     #[error(transparent)]
     OtherIO(#[from] std::io::Error),
 }
@@ -205,6 +211,37 @@ impl UserNamespaceConfig {
     pub fn with_id_mapper(&mut self, mapper: UserNamespaceIDMapper) {
         self.id_mapper = mapper
     }
+
+    /// Creates a user namespace with the configured id mappings and returns a
+    /// file descriptor referring to it. No process is left in the namespace,
+    /// it is kept alive by the descriptor, e.g. to idmap mounts with it.
+    pub fn create_user_ns_fd(&self) -> Result<OwnedFd> {
+        let pid = fork::container_clone_user_ns(Box::new(|| -> i32 {
+            loop {
+                nix::unistd::pause();
+            }
+        }))
+        .map_err(UserNamespaceError::CreateUserNamespace)?;
+
+        let user_ns = self.open_user_ns(pid);
+
+        // the helper process only has to live until the namespace is opened
+        if let Err(err) = signal::kill(pid, Signal::SIGKILL) {
+            tracing::warn!(?pid, ?err, "failed to kill user namespace helper");
+        }
+        let _ = waitpid(pid, None);
+
+        user_ns
+    }
+
+    fn open_user_ns(&self, pid: Pid) -> Result<OwnedFd> {
+        if !self.privileged {
+            fs::write(format!("/proc/{pid}/setgroups"), "deny")?;
+        }
+        self.write_uid_mapping(pid)?;
+        self.write_gid_mapping(pid)?;
+        Ok(fs::File::open(format!("/proc/{pid}/ns/user"))?.into())
+    }
 }
 
 impl TryFrom<&Linux> for UserNamespaceConfig {
@@ -35,6 +35,14 @@ pub enum ExecutorSetEnvsError {
     Other(String),
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum ExecutorCallbackError {
+    #[error("executor callback failed")]
+    Callback(#[from] Box<dyn std::error::Error + Send + Sync>),
+    #[error("{0}")]
+    Other(String),
+}
+
 // Here is an explanation about the complexity below regarding to
 // CloneBoxExecutor and Executor traits. This is one of the places rust actually
 // makes our life harder. The usecase for the executor is to allow users of
@@ -89,6 +97,24 @@ pub trait Executor: CloneBoxExecutor {
 
         Ok(())
     }
+
+    /// Called in the init process of a new container once the rootfs is
+    /// prepared and became the root directory. This runs before the root
+    /// directory is made read-only, before `validate` and before the
+    /// startContainer hooks. Errors are reported to the process creating the
+    /// container, which fails the create. It isn't called for the processes
+    /// executed in an existing container.
+    fn on_rootfs_ready(&self, _spec: &Spec) -> Result<(), ExecutorCallbackError> {
+        Ok(())
+    }
+
+    /// Called after the start signal was received, before the startContainer
+    /// hooks run and the workload is executed. The creating process isn't
+    /// waiting for the container anymore at this point, so errors are logged
+    /// and fail the container process.
+    fn on_start(&self, _spec: &Spec) -> Result<(), ExecutorCallbackError> {
+        Ok(())
+    }
 }
 
 impl<T> CloneBoxExecutor for T
@@ -3,7 +3,8 @@ use clap::Parser;
 /// Show resource statistics for the container
 #[derive(Parser, Debug)]
 pub struct Events {
-    /// Sets the stats collection interval in seconds (default: 5s)
+    /// Sets the stats collection interval in seconds (default: 5s), 0 disables
+    /// the stats and only displays the OOM events
     #[clap(long, default_value = "5")]
     pub interval: u32,
     /// Display the container stats only once
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+/// Restore a container from a previous checkpoint
+/// Reference: https://github.com/opencontainers/runc/blob/main/man/runc-restore.8.md
+#[derive(Parser, Debug)]
+pub struct Restore {
+    /// Path to the bundle directory, containing config.json and root filesystem
+    #[clap(short, long, default_value = ".")]
+    pub bundle: PathBuf,
+    /// Path to the criu image files to restore from
+    #[clap(long, default_value = "checkpoint")]
+    pub image_path: PathBuf,
+    /// Path for saving work files and logs
+    #[clap(long)]
+    pub work_path: Option<PathBuf>,
+    /// Unix socket (file) path , which will receive file descriptor of the writing end of the pseudoterminal
+    #[clap(long)]
+    pub console_socket: Option<PathBuf>,
+    /// File to write pid of the restored container
+    #[clap(long)]
+    pub pid_file: Option<PathBuf>,
+    /// Detach from the container process
+    #[clap(short, long)]
+    pub detach: bool,
+    /// Allow open tcp connections
+    #[clap(long)]
+    pub tcp_established: bool,
+    /// Allow external unix sockets
+    #[clap(long)]
+    pub ext_unix_sk: bool,
+    /// Allow shell jobs
+    #[clap(long)]
+    pub shell_job: bool,
+    /// Allow file locks
+    #[clap(long)]
+    pub file_locks: bool,
+    /// Cgroups mode
+    #[clap(long)]
+    pub manage_cgroups_mode: Option<String>,
+
+    #[clap(value_parser = clap::builder::NonEmptyStringValueParser::new(), required = true)]
+    pub container_id: String,
+}
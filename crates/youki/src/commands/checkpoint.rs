@@ -2,6 +2,7 @@
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
+use libcontainer::container::ManageCgroupsMode;
 use liboci_cli::Checkpoint;
 
 use crate::commands::load_container;
@@ -9,6 +10,17 @@ use crate::commands::load_container;
 pub fn checkpoint(args: Checkpoint, root_path: PathBuf) -> Result<()> {
     tracing::debug!("start checkpointing container {}", args.container_id);
     let mut container = load_container(root_path, &args.container_id)?;
+    let manage_cgroups_mode = args
+        .manage_cgroups_mode
+        .as_deref()
+        .map(str::parse::<ManageCgroupsMode>)
+        .transpose()?
+        .unwrap_or_default();
+    let status_fd = args
+        .status_fd
+        .map(i32::try_from)
+        .transpose()
+        .context("invalid status fd")?;
     let opts = libcontainer::container::CheckpointOptions {
         ext_unix_sk: args.ext_unix_sk,
         file_locks: args.file_locks,
@@ -17,6 +29,14 @@ pub fn checkpoint(args: Checkpoint, root_path: PathBuf) -> Result<()> {
         shell_job: args.shell_job,
         tcp_established: args.tcp_established,
         work_path: args.work_path,
+        parent_path: args.parent_path,
+        pre_dump: args.pre_dump,
+        page_server: args.page_server,
+        lazy_pages: args.lazy_pages,
+        status_fd,
+        auto_dedup: args.auto_dedup,
+        manage_cgroups_mode,
+        empty_ns: args.empty_ns,
     };
     container
         .checkpoint(&opts)
@@ -15,7 +15,7 @@ use crate::workload::executor::default_executor;
 // associated with it like any other process.
 pub fn create(args: Create, root_path: PathBuf, systemd_cgroup: bool) -> Result<()> {
     ContainerBuilder::new(args.container_id.clone(), SyscallType::default())
-        .with_executor(default_executor())
+        .with_executor(default_executor(&root_path))
         .with_pid_file(args.pid_file.as_ref())?
         .with_console_socket(args.console_socket.as_ref())
         .with_root_path(root_path)?
@@ -25,6 +25,7 @@ pub fn create(args: Create, root_path: PathBuf, systemd_cgroup: bool) -> Result<
         .with_systemd(systemd_cgroup)
         .with_detach(true)
         .with_no_pivot(args.no_pivot)
+        .with_no_new_keyring(args.no_new_keyring)
         .build()?;
 
     Ok(())
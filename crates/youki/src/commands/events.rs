@@ -1,13 +1,42 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
+use libcontainer::container::runc_events::Event;
+use libcontainer::container::StatsEvent;
 use liboci_cli::Events;
 
 use crate::commands::load_container;
 
 pub fn events(args: Events, root_path: PathBuf) -> Result<()> {
     let mut container = load_container(root_path, &args.container_id)?;
-    container
-        .events(args.interval, args.stats)
-        .with_context(|| format!("failed to get events from container {}", args.container_id))
+    let print = |event: &Event| -> Result<()> {
+        println!("{}", serde_json::to_string(event)?);
+        Ok(())
+    };
+
+    if args.stats {
+        let stats = container
+            .stats()
+            .with_context(|| format!("failed to get stats of container {}", args.container_id))?;
+        return print(&Event::stats(container.id(), &stats));
+    }
+
+    let stream = container
+        .stats_stream(Duration::from_secs(args.interval.into()))
+        .with_context(|| format!("failed to get events from container {}", args.container_id))?;
+    for event in stream {
+        let event = match event
+            .with_context(|| format!("failed to get events from container {}", args.container_id))?
+        {
+            StatsEvent::Stats { stats, .. } => Event::stats(container.id(), &stats),
+            StatsEvent::Cgroup(event) => match Event::from_cgroup_event(container.id(), event) {
+                Some(event) => event,
+                None => continue,
+            },
+        };
+        print(&event)?;
+    }
+
+    Ok(())
 }
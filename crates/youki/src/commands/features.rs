@@ -1,8 +1,236 @@
 //! Contains Functionality of `features` container command
+use std::collections::BTreeMap;
+
 use anyhow::Result;
+use caps::Capability;
+use libcontainer::namespaces;
+use libcontainer::oci_spec::runtime::{self, LinuxNamespaceType};
+use libcontainer::rootfs::utils::known_mount_options;
 use liboci_cli::Features;
+use serde::Serialize;
+
+/// Minimum version of the runtime-spec supported by youki.
+const OCI_VERSION_MIN: &str = "1.0.0";
+
+/// Hooks run by libcontainer during the container lifecycle.
+const HOOKS: &[&str] = &[
+    "prestart",
+    "createRuntime",
+    "createContainer",
+    "startContainer",
+    "poststart",
+    "poststop",
+];
+
+/// Features document as described by
+/// https://github.com/opencontainers/runtime-spec/blob/main/features.md
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FeaturesInfo {
+    oci_version_min: String,
+    oci_version_max: String,
+    hooks: Vec<String>,
+    mount_options: Vec<String>,
+    linux: LinuxFeatures,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    annotations: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LinuxFeatures {
+    namespaces: Vec<LinuxNamespaceType>,
+    capabilities: Vec<String>,
+    cgroup: CgroupFeatures,
+    seccomp: SeccompFeatures,
+    apparmor: EnabledFeature,
+    selinux: EnabledFeature,
+    intel_rdt: EnabledFeature,
+    mount_extensions: MountExtensions,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CgroupFeatures {
+    v1: bool,
+    v2: bool,
+    systemd: bool,
+    systemd_user: bool,
+    rdma: bool,
+}
 
-/// lists all existing containers
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct SeccompFeatures {
+    enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actions: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operators: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    archs: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    known_flags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    supported_flags: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Debug)]
+struct EnabledFeature {
+    enabled: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct MountExtensions {
+    idmap: EnabledFeature,
+}
+
+/// prints the features supported by youki as json
 pub fn features(_: Features) -> Result<()> {
+    let features = get_features();
+    println!("{}", serde_json::to_string_pretty(&features)?);
     Ok(())
 }
+
+pub fn get_features() -> FeaturesInfo {
+    let mount_options: Vec<String> = known_mount_options()
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let idmap = mount_options.iter().any(|option| option == "idmap");
+
+    #[allow(unused_mut)]
+    let mut annotations = BTreeMap::new();
+    #[cfg(feature = "seccomp")]
+    if let Some(version) = libcontainer::seccomp::libseccomp_version() {
+        annotations.insert("io.github.seccomp.libseccomp.version".to_string(), version);
+    }
+
+    FeaturesInfo {
+        oci_version_min: OCI_VERSION_MIN.to_string(),
+        oci_version_max: runtime::VERSION.to_string(),
+        hooks: HOOKS.iter().map(|hook| hook.to_string()).collect(),
+        mount_options,
+        linux: LinuxFeatures {
+            namespaces: namespaces::supported_namespaces(),
+            capabilities: get_capabilities(),
+            cgroup: CgroupFeatures {
+                v1: cfg!(feature = "v1"),
+                v2: cfg!(feature = "v2"),
+                systemd: cfg!(feature = "systemd"),
+                systemd_user: cfg!(feature = "systemd"),
+                rdma: cgroup_controllers()
+                    .iter()
+                    .any(|controller| controller == "rdma"),
+            },
+            seccomp: get_seccomp(),
+            // libcontainer::apparmor is always compiled in, the init process
+            // applies the profile the spec requests.
+            apparmor: EnabledFeature { enabled: true },
+            // SELinux process labels are not applied by libcontainer.
+            selinux: EnabledFeature { enabled: false },
+            // libcontainer::process::intel_rdt is always compiled in, the main
+            // process configures the resctrl group of the spec.
+            intel_rdt: EnabledFeature { enabled: true },
+            mount_extensions: MountExtensions {
+                idmap: EnabledFeature { enabled: idmap },
+            },
+        },
+        annotations,
+    }
+}
+
+/// Names of the cgroup controllers the compiled in cgroup managers support.
+/// The systemd manager, which implies v2, uses the controllers of cgroup v2.
+fn cgroup_controllers() -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut controllers = Vec::new();
+    #[cfg(feature = "v1")]
+    controllers.extend(
+        libcgroups::v1::CONTROLLERS
+            .iter()
+            .map(|controller| controller.to_string()),
+    );
+    #[cfg(feature = "v2")]
+    controllers.extend(
+        libcgroups::v2::controller_type::CONTROLLER_TYPES
+            .iter()
+            .map(|controller| controller.to_string()),
+    );
+    controllers
+}
+
+fn get_capabilities() -> Vec<String> {
+    let mut capabilities: Vec<Capability> = caps::all().into_iter().collect();
+    capabilities.sort_by_key(|cap| cap.index());
+    capabilities.iter().map(|cap| cap.to_string()).collect()
+}
+
+#[cfg(feature = "seccomp")]
+fn get_seccomp() -> SeccompFeatures {
+    use libcontainer::seccomp;
+
+    // The oci-spec enums serialize to the names used in config.json,
+    // e.g. SCMP_ACT_ALLOW or SCMP_ARCH_X86_64.
+    fn names<T: Serialize>(values: &[T]) -> Option<Vec<String>> {
+        Some(
+            values
+                .iter()
+                .filter_map(|value| match serde_json::to_value(value) {
+                    Ok(serde_json::Value::String(name)) => Some(name),
+                    _ => None,
+                })
+                .collect(),
+        )
+    }
+
+    SeccompFeatures {
+        enabled: true,
+        actions: names(seccomp::SUPPORTED_ACTIONS),
+        operators: names(seccomp::SUPPORTED_OPERATORS),
+        archs: names(seccomp::SUPPORTED_ARCHS),
+        known_flags: names(seccomp::SUPPORTED_FLAGS),
+        supported_flags: names(&seccomp::supported_flags()),
+    }
+}
+
+#[cfg(not(feature = "seccomp"))]
+fn get_seccomp() -> SeccompFeatures {
+    SeccompFeatures::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn test_features_json() -> Result<()> {
+        let value = serde_json::to_value(get_features())?;
+
+        assert_eq!(value["ociVersionMin"], OCI_VERSION_MIN);
+        assert_eq!(value["ociVersionMax"], runtime::VERSION);
+        assert!(value["mountOptions"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("rbind")));
+        assert!(value["linux"]["namespaces"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("mount")));
+        assert!(value["linux"]["capabilities"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("CAP_SYS_ADMIN")));
+        assert_eq!(value["linux"]["cgroup"]["v2"], cfg!(feature = "v2"));
+        // none of the cgroup managers has an rdma controller
+        assert_eq!(value["linux"]["cgroup"]["rdma"], false);
+        assert_eq!(
+            value["linux"]["seccomp"]["enabled"],
+            cfg!(feature = "seccomp")
+        );
+
+        Ok(())
+    }
+}
@@ -2,68 +2,192 @@
 #[cfg(feature = "v2")]
 use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::Parser;
 #[cfg(feature = "v2")]
 use libcgroups::{common::CgroupSetup, v2::controller_type::ControllerType};
+use libcontainer::pidfd::PidFd;
 use libcontainer::user_ns;
+use nix::errno::Errno;
+use nix::libc;
+use nix::unistd::Pid;
 use procfs::{CpuInfo, Current, Meminfo};
+use serde::Serialize;
+
+use crate::workload::executor::EXECUTORS;
+
 /// Show information about the system
 #[derive(Parser, Debug)]
-pub struct Info {}
+pub struct Info {
+    /// Specify the format (text or json)
+    #[clap(long, default_value = "text")]
+    pub format: String,
+}
 
-pub fn info(_: Info) -> Result<()> {
-    print_youki();
-    print_kernel();
-    print_os();
-    print_hardware();
-    print_cgroups();
-    print_namespaces();
-    print_capabilities();
+pub fn info(args: Info) -> Result<()> {
+    let info = SystemInfo::collect()?;
+    match args.format.as_str() {
+        "text" => print_info(&info),
+        "json" => println!("{}", serde_json::to_string(&info)?),
+        format => bail!("unknown format {format}, expected text or json"),
+    }
 
     Ok(())
 }
 
-/// print Version of Youki
-pub fn print_youki() {
-    println!("{:<18}{}", "Version", env!("CARGO_PKG_VERSION"));
-    println!("{:<18}{}", "Commit", env!("VERGEN_GIT_SHA"));
+/// Everything `youki info` reports about the system
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    pub version: &'static str,
+    pub commit: &'static str,
+    /// The executors compiled into youki, in the order they are picked
+    pub executors: Vec<&'static str>,
+    pub kernel: KernelInfo,
+    pub operating_system: Option<String>,
+    pub hardware: HardwareInfo,
+    pub cgroups: CgroupsInfo,
+    /// `None` if the kernel config can't be read
+    pub namespaces: Option<NamespacesInfo>,
+    /// `None` if the capabilities of youki can't be read
+    pub capabilities: Option<Vec<CapabilityInfo>>,
+    pub features: KernelFeatures,
 }
 
-/// Print Kernel Release, Version and Architecture
-pub fn print_kernel() {
-    let uname = nix::sys::utsname::uname().unwrap();
-    println!(
-        "{:<18}{}",
-        "Kernel-Release",
-        uname.release().to_string_lossy()
-    );
-    println!(
-        "{:<18}{}",
-        "Kernel-Version",
-        uname.version().to_string_lossy()
-    );
-    println!(
-        "{:<18}{}",
-        "Architecture",
-        uname.machine().to_string_lossy()
-    );
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KernelInfo {
+    pub release: String,
+    pub version: String,
+    pub architecture: String,
 }
 
-/// Prints OS Distribution information
-// see https://www.freedesktop.org/software/systemd/man/os-release.html
-pub fn print_os() {
-    if let Some(os) = try_read_os_from("/etc/os-release") {
-        println!("{:<18}{}", "Operating System", os);
-    } else if let Some(os) = try_read_os_from("/usr/lib/os-release") {
-        println!("{:<18}{}", "Operating System", os);
-    } else {
-        println!("{:<18}UNKNOWN", "Operating System");
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HardwareInfo {
+    pub cores: Option<usize>,
+    pub total_memory_mib: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CgroupsInfo {
+    pub setup: Option<String>,
+    pub mounts: Vec<CgroupMount>,
+    /// The cgroup v2 controllers, `None` on a legacy setup
+    pub v2_controllers: Option<Vec<ControllerInfo>>,
+    /// Whether devices can be controlled with eBPF on cgroup v2
+    pub v2_devices: Option<FeatureStatus>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CgroupMount {
+    pub subsystem: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControllerInfo {
+    pub name: String,
+    pub attached: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespacesInfo {
+    pub status: FeatureStatus,
+    /// Empty if namespaces are disabled
+    pub namespaces: Vec<NamespaceInfo>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceInfo {
+    pub name: &'static str,
+    pub status: FeatureStatus,
+    /// Whether users other than root can create the namespace, only known
+    /// for the user namespace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unprivileged: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilityInfo {
+    pub name: &'static str,
+    pub available: bool,
+}
+
+/// Kernel features youki makes use of when they are there
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KernelFeatures {
+    pub user_namespaces: FeatureStatus,
+    pub unprivileged_user_namespaces: FeatureStatus,
+    pub idmapped_mounts: FeatureStatus,
+    pub time_namespaces: FeatureStatus,
+    pub clone3: FeatureStatus,
+    pub pidfd: FeatureStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeatureStatus {
+    Enabled,
+    Disabled,
+    Unknown,
+}
+
+impl From<bool> for FeatureStatus {
+    fn from(enabled: bool) -> Self {
+        if enabled {
+            FeatureStatus::Enabled
+        } else {
+            FeatureStatus::Disabled
+        }
+    }
+}
+
+impl SystemInfo {
+    pub fn collect() -> Result<Self> {
+        let kernel = kernel_info()?;
+        let kernel_config = read_kernel_config(&kernel.release);
+
+        Ok(Self {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: env!("VERGEN_GIT_SHA"),
+            executors: EXECUTORS.iter().map(|info| info.name).collect(),
+            operating_system: operating_system(),
+            hardware: hardware_info(),
+            cgroups: cgroups_info(kernel_config.as_deref()),
+            namespaces: kernel_config.as_deref().map(namespaces_info),
+            capabilities: capabilities(),
+            features: kernel_features(kernel_config.as_deref()),
+            kernel,
+        })
     }
 }
 
+/// Reads Kernel Release, Version and Architecture
+fn kernel_info() -> Result<KernelInfo> {
+    let uname = nix::sys::utsname::uname()?;
+    Ok(KernelInfo {
+        release: uname.release().to_string_lossy().into_owned(),
+        version: uname.version().to_string_lossy().into_owned(),
+        architecture: uname.machine().to_string_lossy().into_owned(),
+    })
+}
+
+/// Reads the OS Distribution information
+// see https://www.freedesktop.org/software/systemd/man/os-release.html
+fn operating_system() -> Option<String> {
+    try_read_os_from("/etc/os-release").or_else(|| try_read_os_from("/usr/lib/os-release"))
+}
+
 /// Helper function to read the OS Distribution info
 fn try_read_os_from<P: AsRef<Path>>(path: P) -> Option<String> {
     let os_release = path.as_ref();
@@ -101,59 +225,55 @@ fn find_parameter<'a>(content: &'a str, param_name: &str) -> Option<&'a str> {
         .and_then(|l| l.split_terminator('=').last())
 }
 
-/// Print Hardware information of system
-pub fn print_hardware() {
-    if let Ok(cpu_info) = CpuInfo::current() {
-        println!("{:<18}{}", "Cores", cpu_info.num_cores());
-    }
-
-    if let Ok(mem_info) = Meminfo::current() {
-        println!(
-            "{:<18}{}",
-            "Total Memory",
-            mem_info.mem_total / u64::pow(1024, 2)
-        );
+/// Reads Hardware information of system
+fn hardware_info() -> HardwareInfo {
+    HardwareInfo {
+        cores: CpuInfo::current().ok().map(|cpu_info| cpu_info.num_cores()),
+        total_memory_mib: Meminfo::current()
+            .ok()
+            .map(|mem_info| mem_info.mem_total / u64::pow(1024, 2)),
     }
 }
 
-/// Print cgroups info of system
-pub fn print_cgroups() {
-    print_cgroups_setup();
-    print_cgroup_mounts();
-    #[cfg(feature = "v2")]
-    print_cgroup_v2_controllers();
+/// Reads cgroups info of system
+fn cgroups_info(kernel_config: Option<&str>) -> CgroupsInfo {
+    let mut cgroups = CgroupsInfo {
+        setup: libcgroups::common::get_cgroup_setup()
+            .ok()
+            .map(|setup| setup.to_string()),
+        mounts: cgroup_mounts(),
+        ..Default::default()
+    };
+    cgroup_v2_controllers(&mut cgroups, kernel_config);
+
+    cgroups
 }
 
-pub fn print_cgroups_setup() {
-    let cgroup_setup = libcgroups::common::get_cgroup_setup();
-    if let Ok(cgroup_setup) = &cgroup_setup {
-        println!("{:<18}{}", "Cgroup setup", cgroup_setup);
-    }
-}
-
-pub fn print_cgroup_mounts() {
-    println!("Cgroup mounts");
+fn cgroup_mounts() -> Vec<CgroupMount> {
+    #[allow(unused_mut)]
+    let mut mounts = Vec::new();
     #[cfg(feature = "v1")]
     if let Ok(v1_mounts) = libcgroups::v1::util::list_supported_mount_points() {
-        let mut v1_mounts: Vec<String> = v1_mounts
-            .iter()
-            .map(|kv| format!("  {:<16}{}", kv.0.to_string(), kv.1.display()))
-            .collect();
-
-        v1_mounts.sort();
-        for cgroup_mount in v1_mounts {
-            println!("{cgroup_mount}");
-        }
+        mounts.extend(v1_mounts.into_iter().map(|(subsystem, path)| CgroupMount {
+            subsystem: subsystem.to_string(),
+            path,
+        }));
+        mounts.sort_by(|a, b| a.subsystem.cmp(&b.subsystem));
     }
 
     #[cfg(feature = "v2")]
     if let Ok(mount_point) = libcgroups::v2::util::get_unified_mount_point() {
-        println!("  {:<16}{}", "unified", mount_point.display());
+        mounts.push(CgroupMount {
+            subsystem: "unified".to_owned(),
+            path: mount_point,
+        });
     }
+
+    mounts
 }
 
 #[cfg(feature = "v2")]
-pub fn print_cgroup_v2_controllers() {
+fn cgroup_v2_controllers(cgroups: &mut CgroupsInfo, kernel_config: Option<&str>) {
     let cgroup_setup = libcgroups::common::get_cgroup_setup();
     let unified = libcgroups::v2::util::get_unified_mount_point();
 
@@ -161,35 +281,30 @@ pub fn print_cgroup_v2_controllers() {
         if let Ok(unified) = &unified {
             if matches!(cgroup_setup, CgroupSetup::Hybrid | CgroupSetup::Unified) {
                 if let Ok(controllers) = libcgroups::v2::util::get_available_controllers(unified) {
-                    println!("CGroup v2 controllers");
                     let active_controllers: HashSet<ControllerType> =
                         controllers.into_iter().collect();
-                    for controller in libcgroups::v2::controller_type::CONTROLLER_TYPES {
-                        let status = if active_controllers.contains(controller) {
-                            "attached"
-                        } else {
-                            "detached"
-                        };
-
-                        println!("  {:<16}{}", controller.to_string(), status);
-                    }
+                    let controllers = libcgroups::v2::controller_type::CONTROLLER_TYPES
+                        .iter()
+                        .map(|controller| ControllerInfo {
+                            name: controller.to_string(),
+                            attached: active_controllers.contains(controller),
+                        })
+                        .collect();
+                    cgroups.v2_controllers = Some(controllers);
                 }
 
-                if let Some(config) = read_kernel_config() {
-                    let display = FeatureDisplay::with_status("device", "attached", "detached");
-                    print_feature_status(&config, "CONFIG_CGROUP_BPF", display);
-                }
+                cgroups.v2_devices =
+                    kernel_config.map(|config| config_status(config, "CONFIG_CGROUP_BPF"));
             }
         }
     }
 }
 
-fn read_kernel_config() -> Option<String> {
-    let uname = nix::sys::utsname::uname();
-    let kernel_config = Path::new("/boot").join(format!(
-        "config-{}",
-        uname.unwrap().release().to_string_lossy()
-    ));
+#[cfg(not(feature = "v2"))]
+fn cgroup_v2_controllers(_: &mut CgroupsInfo, _: Option<&str>) {}
+
+fn read_kernel_config(release: &str) -> Option<String> {
+    let kernel_config = Path::new("/boot").join(format!("config-{release}"));
     if !kernel_config.exists() {
         return None;
     }
@@ -197,84 +312,279 @@ fn read_kernel_config() -> Option<String> {
     fs::read_to_string(kernel_config).ok()
 }
 
-pub fn print_namespaces() {
-    if let Some(content) = read_kernel_config() {
-        if let Some(ns_enabled) = find_parameter(&content, "CONFIG_NAMESPACES") {
-            if ns_enabled == "y" {
-                println!("{:<18}enabled", "Namespaces");
-            } else {
-                println!("{:<18}disabled", "Namespaces");
-                return;
-            }
-        } else {
-            println!("{:<18}UNKNOWN", "Namespaces");
-            // we don't return as  we can atleast try and see if anything is enabled
-        }
-
-        // mount namespace is always enabled if namespaces are enabled
-        println!("  {:<16}enabled", "mount");
-        print_feature_status(&content, "CONFIG_UTS_NS", FeatureDisplay::new("uts"));
-        print_feature_status(&content, "CONFIG_IPC_NS", FeatureDisplay::new("ipc"));
+/// Looks up whether a feature is built into the kernel
+fn config_status(config: &str, feature: &str) -> FeatureStatus {
+    match find_parameter(config, feature) {
+        Some(status_flag) => (status_flag == "y").into(),
+        None => FeatureStatus::Unknown,
+    }
+}
 
-        let user_display = match user_ns::unprivileged_user_ns_enabled() {
-            Ok(false) => FeatureDisplay::with_status("user", "enabled (root only)", "disabled"),
-            _ => FeatureDisplay::new("user"),
+fn namespaces_info(config: &str) -> NamespacesInfo {
+    let status = config_status(config, "CONFIG_NAMESPACES");
+    if status == FeatureStatus::Disabled {
+        return NamespacesInfo {
+            status,
+            namespaces: Vec::new(),
         };
-        print_feature_status(&content, "CONFIG_USER_NS", user_display);
-        print_feature_status(&content, "CONFIG_PID_NS", FeatureDisplay::new("pid"));
-        print_feature_status(&content, "CONFIG_NET_NS", FeatureDisplay::new("network"));
+    }
+    // even if the status is unknown, we can atleast try and see if anything
+    // is enabled
+
+    let namespace = |name, feature| NamespaceInfo {
+        name,
+        status: config_status(config, feature),
+        unprivileged: None,
+    };
+    let namespaces = vec![
+        // mount namespace is always enabled if namespaces are enabled
+        NamespaceInfo {
+            name: "mount",
+            status: FeatureStatus::Enabled,
+            unprivileged: None,
+        },
+        namespace("uts", "CONFIG_UTS_NS"),
+        namespace("ipc", "CONFIG_IPC_NS"),
+        NamespaceInfo {
+            unprivileged: user_ns::unprivileged_user_ns_enabled().ok(),
+            ..namespace("user", "CONFIG_USER_NS")
+        },
+        namespace("pid", "CONFIG_PID_NS"),
+        namespace("network", "CONFIG_NET_NS"),
         // While the CONFIG_CGROUP_NS kernel feature exists, it is obsolete and should not be used. CGroup namespaces
         // are instead enabled with CONFIG_CGROUPS.
-        print_feature_status(&content, "CONFIG_CGROUPS", FeatureDisplay::new("cgroup"))
+        namespace("cgroup", "CONFIG_CGROUPS"),
+        namespace("time", "CONFIG_TIME_NS"),
+    ];
+
+    NamespacesInfo { status, namespaces }
+}
+
+fn capabilities() -> Option<Vec<CapabilityInfo>> {
+    let current = caps::read(None, caps::CapSet::Bounding).ok()?;
+    let capabilities = [
+        ("CAP_BPF", caps::Capability::CAP_BPF),
+        ("CAP_PERFMON", caps::Capability::CAP_PERFMON),
+        (
+            "CAP_CHECKPOINT_RESTORE",
+            caps::Capability::CAP_CHECKPOINT_RESTORE,
+        ),
+    ];
+
+    Some(
+        capabilities
+            .into_iter()
+            .map(|(name, cap)| CapabilityInfo {
+                name,
+                available: current.contains(&cap),
+            })
+            .collect(),
+    )
+}
+
+/// Probes the kernel features. The kernel config only tells what the kernel
+/// was built with, so the features are probed on the running kernel where
+/// possible and the config is the fallback.
+fn kernel_features(kernel_config: Option<&str>) -> KernelFeatures {
+    let unprivileged_user_namespaces = match user_ns::unprivileged_user_ns_enabled() {
+        Ok(enabled) => enabled.into(),
+        Err(_) => FeatureStatus::Unknown,
+    };
+
+    KernelFeatures {
+        user_namespaces: namespace_status("user", kernel_config, "CONFIG_USER_NS"),
+        unprivileged_user_namespaces,
+        // idmapped mounts came with mount_setattr in 5.12. The file systems
+        // supporting them vary, so this only tells the kernel can do them.
+        idmapped_mounts: syscall_status(unsafe {
+            libc::syscall(
+                libc::SYS_mount_setattr,
+                -1,
+                c"".as_ptr(),
+                0,
+                std::ptr::null::<libc::c_void>(),
+                0,
+            )
+        }),
+        time_namespaces: namespace_status("time", kernel_config, "CONFIG_TIME_NS"),
+        // an empty argument struct is never valid
+        clone3: syscall_status(unsafe {
+            libc::syscall(libc::SYS_clone3, std::ptr::null::<libc::c_void>(), 0)
+        }),
+        pidfd: match PidFd::open(Pid::this()) {
+            Err(Errno::ENOSYS) => FeatureStatus::Disabled,
+            _ => FeatureStatus::Enabled,
+        },
     }
 }
 
-#[inline]
-fn is_cap_available(caps: &caps::CapsHashSet, cap: caps::Capability) -> &'static str {
-    if caps.contains(&cap) {
-        "available"
-    } else {
-        "unavailable"
+/// Tells whether the running kernel has a namespace, falling back to the kernel
+/// config when procfs isn't there
+fn namespace_status(name: &str, kernel_config: Option<&str>, feature: &str) -> FeatureStatus {
+    let ns_dir = Path::new("/proc/self/ns");
+    if ns_dir.exists() {
+        return ns_dir.join(name).exists().into();
     }
+
+    kernel_config
+        .map(|config| config_status(config, feature))
+        .unwrap_or(FeatureStatus::Unknown)
 }
 
-pub fn print_capabilities() {
-    println!("Capabilities");
-    if let Ok(current) = caps::read(None, caps::CapSet::Bounding) {
-        println!(
-            "{:<17} {}",
-            "CAP_BPF",
-            is_cap_available(&current, caps::Capability::CAP_BPF)
-        );
-        println!(
-            "{:<17} {}",
-            "CAP_PERFMON",
-            is_cap_available(&current, caps::Capability::CAP_PERFMON)
-        );
-        println!(
-            "{:<17} {}",
-            "CAP_CHECKPOINT_RESTORE",
-            is_cap_available(&current, caps::Capability::CAP_CHECKPOINT_RESTORE)
-        );
-    } else {
-        println!("<cannot find cap info>");
+/// Tells whether a probe syscall is implemented. The probes are called with
+/// invalid arguments, so they fail with ENOSYS on kernels without the syscall
+/// and with another error otherwise.
+fn syscall_status(ret: libc::c_long) -> FeatureStatus {
+    (Errno::result(ret) != Err(Errno::ENOSYS)).into()
+}
+
+/// Prints the information in the human readable format
+fn print_info(info: &SystemInfo) {
+    print_youki(info);
+    print_executors(info);
+    print_kernel(&info.kernel);
+    print_os(info);
+    print_hardware(&info.hardware);
+    print_cgroups(&info.cgroups);
+    if let Some(namespaces) = &info.namespaces {
+        print_namespaces(namespaces);
     }
+    print_capabilities(info.capabilities.as_deref());
+    print_features(&info.features);
 }
 
-fn print_feature_status(config: &str, feature: &str, display: FeatureDisplay) {
-    if let Some(status_flag) = find_parameter(config, feature) {
-        let status = if status_flag == "y" {
-            display.enabled
-        } else {
-            display.disabled
+/// print Version of Youki
+fn print_youki(info: &SystemInfo) {
+    println!("{:<18}{}", "Version", info.version);
+    println!("{:<18}{}", "Commit", info.commit);
+}
+
+/// Print the executors compiled into youki, in the order they are picked
+fn print_executors(info: &SystemInfo) {
+    println!("{:<18}{}", "Executors", info.executors.join(", "));
+}
+
+/// Print Kernel Release, Version and Architecture
+fn print_kernel(kernel: &KernelInfo) {
+    println!("{:<18}{}", "Kernel-Release", kernel.release);
+    println!("{:<18}{}", "Kernel-Version", kernel.version);
+    println!("{:<18}{}", "Architecture", kernel.architecture);
+}
+
+/// Prints OS Distribution information
+fn print_os(info: &SystemInfo) {
+    println!(
+        "{:<18}{}",
+        "Operating System",
+        info.operating_system.as_deref().unwrap_or("UNKNOWN")
+    );
+}
+
+/// Print Hardware information of system
+fn print_hardware(hardware: &HardwareInfo) {
+    if let Some(cores) = hardware.cores {
+        println!("{:<18}{}", "Cores", cores);
+    }
+
+    if let Some(total_memory) = hardware.total_memory_mib {
+        println!("{:<18}{}", "Total Memory", total_memory);
+    }
+}
+
+/// Print cgroups info of system
+fn print_cgroups(cgroups: &CgroupsInfo) {
+    if let Some(setup) = &cgroups.setup {
+        println!("{:<18}{}", "Cgroup setup", setup);
+    }
+
+    println!("Cgroup mounts");
+    for mount in &cgroups.mounts {
+        println!("  {:<16}{}", mount.subsystem, mount.path.display());
+    }
+
+    if let Some(controllers) = &cgroups.v2_controllers {
+        println!("CGroup v2 controllers");
+        for controller in controllers {
+            let status = if controller.attached {
+                "attached"
+            } else {
+                "detached"
+            };
+            println!("  {:<16}{}", controller.name, status);
+        }
+    }
+
+    if let Some(devices) = cgroups.v2_devices {
+        let display = FeatureDisplay::with_status("device", "attached", "detached");
+        print_feature_status(devices, display);
+    }
+}
+
+fn print_namespaces(namespaces: &NamespacesInfo) {
+    match namespaces.status {
+        FeatureStatus::Enabled => println!("{:<18}enabled", "Namespaces"),
+        FeatureStatus::Disabled => println!("{:<18}disabled", "Namespaces"),
+        FeatureStatus::Unknown => println!("{:<18}UNKNOWN", "Namespaces"),
+    }
+
+    for namespace in &namespaces.namespaces {
+        let display = match namespace.unprivileged {
+            Some(false) => {
+                FeatureDisplay::with_status(namespace.name, "enabled (root only)", "disabled")
+            }
+            _ => FeatureDisplay::new(namespace.name),
         };
+        print_feature_status(namespace.status, display);
+    }
+}
 
-        println!("  {:<16}{}", display.name, status);
+fn print_capabilities(capabilities: Option<&[CapabilityInfo]>) {
+    println!("Capabilities");
+    if let Some(capabilities) = capabilities {
+        for capability in capabilities {
+            let status = if capability.available {
+                "available"
+            } else {
+                "unavailable"
+            };
+            println!("{:<17} {}", capability.name, status);
+        }
     } else {
-        println!("  {:<16}UNKNOWN", display.name);
+        println!("<cannot find cap info>");
     }
 }
 
+fn print_features(features: &KernelFeatures) {
+    println!("Kernel features");
+    let user_display = match features.unprivileged_user_namespaces {
+        FeatureStatus::Disabled => {
+            FeatureDisplay::with_status("user namespace", "enabled (root only)", "disabled")
+        }
+        _ => FeatureDisplay::new("user namespace"),
+    };
+    print_feature_status(features.user_namespaces, user_display);
+    print_feature_status(
+        features.idmapped_mounts,
+        FeatureDisplay::new("idmapped mounts"),
+    );
+    print_feature_status(
+        features.time_namespaces,
+        FeatureDisplay::new("time namespace"),
+    );
+    print_feature_status(features.clone3, FeatureDisplay::new("clone3"));
+    print_feature_status(features.pidfd, FeatureDisplay::new("pidfd"));
+}
+
+fn print_feature_status(status: FeatureStatus, display: FeatureDisplay) {
+    let status = match status {
+        FeatureStatus::Enabled => display.enabled,
+        FeatureStatus::Disabled => display.disabled,
+        FeatureStatus::Unknown => "UNKNOWN",
+    };
+
+    println!("  {:<16}{}", display.name, status);
+}
+
 struct FeatureDisplay<'a> {
     name: &'a str,
     enabled: &'a str,
@@ -298,3 +608,54 @@ impl<'a> FeatureDisplay<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn test_namespaces_info() {
+        let config = "CONFIG_NAMESPACES=y\nCONFIG_UTS_NS=y\nCONFIG_IPC_NS=n\n";
+        let info = namespaces_info(config);
+        assert_eq!(info.status, FeatureStatus::Enabled);
+        let status = |name| {
+            info.namespaces
+                .iter()
+                .find(|ns| ns.name == name)
+                .map(|ns| ns.status)
+        };
+        assert_eq!(status("mount"), Some(FeatureStatus::Enabled));
+        assert_eq!(status("uts"), Some(FeatureStatus::Enabled));
+        assert_eq!(status("ipc"), Some(FeatureStatus::Disabled));
+        assert_eq!(status("time"), Some(FeatureStatus::Unknown));
+
+        let info = namespaces_info("CONFIG_NAMESPACES=n\n");
+        assert_eq!(info.status, FeatureStatus::Disabled);
+        assert!(info.namespaces.is_empty());
+    }
+
+    #[test]
+    fn test_json_info() -> Result<()> {
+        let info: Value = serde_json::to_value(SystemInfo::collect()?)?;
+        assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+        assert!(info["kernel"]["release"].is_string());
+        for feature in [
+            "userNamespaces",
+            "unprivilegedUserNamespaces",
+            "idmappedMounts",
+            "timeNamespaces",
+            "clone3",
+            "pidfd",
+        ] {
+            let status = info["features"][feature].as_str().unwrap_or_default();
+            assert!(
+                ["enabled", "disabled", "unknown"].contains(&status),
+                "unexpected status {status} of {feature}"
+            );
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,11 @@
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
 use libcgroups::common::AnyCgroupManager;
-use libcontainer::container::Container;
+use libcontainer::container::{Container, DEFAULT_LOCK_TIMEOUT};
 
 pub mod checkpoint;
 pub mod completion;
@@ -17,12 +19,27 @@ pub mod kill;
 pub mod list;
 pub mod pause;
 pub mod ps;
+pub mod restore;
 pub mod resume;
 pub mod run;
 pub mod spec_json;
 pub mod start;
 pub mod state;
 pub mod update;
+pub mod wait;
+pub mod wasm_cache;
+
+static LOCK_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+/// Sets how long the commands wait for the lock of a container held by
+/// another process. Only the first call has an effect.
+pub fn set_lock_timeout(timeout: Duration) {
+    let _ = LOCK_TIMEOUT.set(timeout);
+}
+
+fn lock_timeout() -> Duration {
+    LOCK_TIMEOUT.get().copied().unwrap_or(DEFAULT_LOCK_TIMEOUT)
+}
 
 fn construct_container_root<P: AsRef<Path>>(root_path: P, container_id: &str) -> Result<PathBuf> {
     // resolves relative paths, symbolic links etc. and get complete path
@@ -43,8 +60,10 @@ fn load_container<P: AsRef<Path>>(root_path: P, container_id: &str) -> Result<Co
         bail!("container {} does not exist.", container_id)
     }
 
-    Container::load(container_root)
-        .with_context(|| format!("could not load state for container {container_id}"))
+    let mut container = Container::load(container_root)
+        .with_context(|| format!("could not load state for container {container_id}"))?;
+    container.set_lock_timeout(lock_timeout());
+    Ok(container)
 }
 
 fn container_exists<P: AsRef<Path>>(root_path: P, container_id: &str) -> Result<bool> {
@@ -0,0 +1,53 @@
+//! Contains functionality of restore container command
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use libcontainer::container::{Container, ManageCgroupsMode, RestoreOptions};
+use liboci_cli::Restore;
+
+use crate::commands::construct_container_root;
+use crate::commands::run::{exit_code, handle_foreground};
+
+pub fn restore(args: Restore, root_path: PathBuf, systemd_cgroup: bool) -> Result<i32> {
+    tracing::debug!("start restoring container {}", args.container_id);
+    let container_root = construct_container_root(root_path, &args.container_id)?;
+    let manage_cgroups_mode = args
+        .manage_cgroups_mode
+        .as_deref()
+        .map(str::parse::<ManageCgroupsMode>)
+        .transpose()?
+        .unwrap_or_default();
+    let opts = RestoreOptions {
+        ext_unix_sk: args.ext_unix_sk,
+        file_locks: args.file_locks,
+        image_path: args.image_path,
+        shell_job: args.shell_job,
+        tcp_established: args.tcp_established,
+        work_path: args.work_path,
+        manage_cgroups_mode,
+        console_socket: args.console_socket,
+        pid_file: args.pid_file,
+        use_systemd: systemd_cgroup,
+    };
+    let mut container =
+        Container::restore(&args.container_id, &container_root, &args.bundle, &opts)
+            .with_context(|| format!("failed to restore container {}", args.container_id))?;
+
+    if args.detach {
+        return Ok(0);
+    }
+
+    // A restored container always records the pid of its init process, the
+    // same invariant as in `run`.
+    debug_assert!(
+        container.pid().is_some(),
+        "expects a container init pid in the container state"
+    );
+    let foreground_result = handle_foreground(container.pid().unwrap());
+    if let Ok(status) = foreground_result {
+        container.set_exit_status(status).save()?;
+    }
+    // execute the destruction action after the container finishes running
+    container.delete(true)?;
+    foreground_result.map(exit_code)
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use libcontainer::container::builder::ContainerBuilder;
@@ -11,9 +11,14 @@ use nix::unistd::Pid;
 
 use crate::workload::executor::default_executor;
 
-pub fn run(args: Run, root_path: PathBuf, systemd_cgroup: bool) -> Result<i32> {
+pub fn run(
+    args: Run,
+    root_path: PathBuf,
+    systemd_cgroup: bool,
+    timing_report: Option<&Path>,
+) -> Result<i32> {
     let mut container = ContainerBuilder::new(args.container_id.clone(), SyscallType::default())
-        .with_executor(default_executor())
+        .with_executor(default_executor(&root_path))
         .with_pid_file(args.pid_file.as_ref())?
         .with_console_socket(args.console_socket.as_ref())
         .with_root_path(root_path)?
@@ -23,11 +28,16 @@ pub fn run(args: Run, root_path: PathBuf, systemd_cgroup: bool) -> Result<i32> {
         .with_systemd(systemd_cgroup)
         .with_detach(args.detach)
         .with_no_pivot(args.no_pivot)
+        .with_no_new_keyring(args.no_new_keyring)
         .build()?;
+    container.set_lock_timeout(super::lock_timeout());
 
     container
         .start()
         .with_context(|| format!("failed to start container {}", args.container_id))?;
+    if let Some(path) = timing_report {
+        crate::observability::write_timing_report(path);
+    }
 
     if args.detach {
         return Ok(0);
@@ -41,10 +51,25 @@ pub fn run(args: Run, root_path: PathBuf, systemd_cgroup: bool) -> Result<i32> {
         "expects a container init pid in the container state"
     );
     let foreground_result = handle_foreground(container.pid().unwrap());
+    if let Ok(status) = foreground_result {
+        // record the exit so it's part of the state passed to poststop hooks
+        container.set_exit_status(status).save()?;
+    }
     // execute the destruction action after the container finishes running
     container.delete(true)?;
     // return result
-    foreground_result
+    foreground_result.map(exit_code)
+}
+
+// The exit code youki returns for the exited container init process. Like
+// runc and `youki wait`, a process killed by a signal exits with 128 plus the
+// signal number.
+pub(crate) fn exit_code(status: WaitStatus) -> i32 {
+    match status {
+        WaitStatus::Exited(_, code) => code,
+        WaitStatus::Signaled(_, signal, _) => 128 + signal as i32,
+        _ => 0,
+    }
 }
 
 // handle_foreground will match the `runc` behavior running the foreground mode.
@@ -52,7 +77,7 @@ pub fn run(args: Run, root_path: PathBuf, systemd_cgroup: bool) -> Result<i32> {
 // youki main process also forwards most of the signals to the container init
 // process.
 #[tracing::instrument(level = "trace")]
-fn handle_foreground(init_pid: Pid) -> Result<i32> {
+pub(crate) fn handle_foreground(init_pid: Pid) -> Result<WaitStatus> {
     tracing::trace!("waiting for container init process to exit");
     // We mask all signals here and forward most of the signals to the container
     // init process.
@@ -72,16 +97,16 @@ fn handle_foreground(init_pid: Pid) -> Result<i32> {
                 tracing::trace!("reaping child processes");
                 loop {
                     match waitpid(None, Some(WaitPidFlag::WNOHANG))? {
-                        WaitStatus::Exited(pid, status) => {
+                        status @ WaitStatus::Exited(pid, _) => {
                             if pid.eq(&init_pid) {
                                 return Ok(status);
                             }
 
                             // Else, some random child process exited, ignoring...
                         }
-                        WaitStatus::Signaled(pid, signal, _) => {
+                        status @ WaitStatus::Signaled(pid, _, _) => {
                             if pid.eq(&init_pid) {
-                                return Ok(signal as i32);
+                                return Ok(status);
                             }
 
                             // Else, some random child process exited, ignoring...
@@ -6,7 +6,11 @@ use liboci_cli::State;
 use crate::commands::load_container;
 
 pub fn state(args: State, root_path: PathBuf) -> Result<()> {
-    let container = load_container(root_path, &args.container_id)?;
+    let mut container = load_container(root_path, &args.container_id)?;
+    // the exit status is lost once the container process is reaped
+    if let Err(err) = container.save_exit() {
+        tracing::warn!(?err, id = ?args.container_id, "failed to save the container exit");
+    }
     println!("{}", serde_json::to_string_pretty(&container.state)?);
     std::process::exit(0);
 }
@@ -1,39 +1,238 @@
 use std::path::PathBuf;
 use std::{fs, io};
 
-use anyhow::Result;
-use libcgroups::common::{CgroupManager, ControllerOpt};
-use libcgroups::{self};
-use libcontainer::oci_spec::runtime::{LinuxPidsBuilder, LinuxResources, LinuxResourcesBuilder};
+use anyhow::{Context, Result};
+use libcontainer::oci_spec::runtime::{LinuxMemory, LinuxMemoryBuilder, LinuxResources};
 use liboci_cli::Update;
 
-use crate::commands::create_cgroup_manager;
+use crate::commands::load_container;
 
 pub fn update(args: Update, root_path: PathBuf) -> Result<()> {
-    let cmanager = create_cgroup_manager(root_path, &args.container_id)?;
+    let mut container = load_container(root_path, &args.container_id)?;
 
-    let linux_res: LinuxResources;
-    if let Some(resources_path) = args.resources {
-        linux_res = if resources_path.to_string_lossy() == "-" {
+    let linux_res: LinuxResources = if let Some(resources_path) = &args.resources {
+        if resources_path.to_string_lossy() == "-" {
             serde_json::from_reader(io::stdin())?
         } else {
             let file = fs::File::open(resources_path)?;
             let reader = io::BufReader::new(file);
             serde_json::from_reader(reader)?
-        };
-    } else {
-        let mut builder = LinuxResourcesBuilder::default();
-        if let Some(new_pids_limit) = args.pids_limit {
-            builder = builder.pids(LinuxPidsBuilder::default().limit(new_pids_limit).build()?);
         }
-        linux_res = builder.build()?;
+    } else {
+        // The cgroup managers apply the whole resource struct, so the flags
+        // are merged into the resources the container currently has to keep
+        // the limits which are not updated.
+        let current = container.resources().cloned().unwrap_or_default();
+        merge_resources(&args, current)?
+    };
+
+    container
+        .update(&linux_res)
+        .with_context(|| format!("failed to update container {}", args.container_id))?;
+
+    if args.l3_cache_schema.is_some() || args.mem_bw_schema.is_some() {
+        container
+            .update_intel_rdt(args.l3_cache_schema, args.mem_bw_schema)
+            .with_context(|| {
+                format!(
+                    "failed to update intel rdt of container {}",
+                    args.container_id
+                )
+            })?;
     }
 
-    cmanager.apply(&ControllerOpt {
-        resources: &linux_res,
-        disable_oom_killer: false,
-        oom_score_adj: None,
-        freezer_state: None,
-    })?;
     Ok(())
 }
+
+fn merge_resources(args: &Update, mut resources: LinuxResources) -> Result<LinuxResources> {
+    if let Some(weight) = args.blkio_weight {
+        let weight = u16::try_from(weight).context("invalid blkio weight")?;
+        resources
+            .block_io_mut()
+            .get_or_insert_with(Default::default)
+            .set_weight(Some(weight));
+    }
+
+    let cpu = resources.cpu_mut().get_or_insert_with(Default::default);
+    if let Some(shares) = args.cpu_share {
+        cpu.set_shares(Some(shares));
+    }
+    if let Some(period) = args.cpu_period {
+        cpu.set_period(Some(period));
+    }
+    if let Some(quota) = args.cpu_quota {
+        cpu.set_quota(Some(i64::try_from(quota).context("invalid cpu quota")?));
+    }
+    if let Some(rt_period) = args.cpu_rt_period {
+        cpu.set_realtime_period(Some(rt_period));
+    }
+    if let Some(rt_runtime) = args.cpu_rt_runtime {
+        cpu.set_realtime_runtime(Some(
+            i64::try_from(rt_runtime).context("invalid cpu realtime runtime")?,
+        ));
+    }
+    if let Some(cpus) = &args.cpuset_cpus {
+        cpu.set_cpus(Some(cpus.clone()));
+    }
+    if let Some(mems) = &args.cpuset_mems {
+        cpu.set_mems(Some(mems.clone()));
+    }
+    if resources
+        .cpu()
+        .as_ref()
+        .map_or(false, |cpu| cpu == &Default::default())
+    {
+        resources.set_cpu(None);
+    }
+
+    // LinuxMemory has no setters, so the block is rebuilt from the current
+    // values with the flags on top
+    let mut memory = memory_builder(resources.memory().unwrap_or_default());
+    if let Some(limit) = args.memory {
+        memory = memory.limit(i64::try_from(limit).context("invalid memory limit")?);
+    }
+    if let Some(reservation) = args.memory_reservation {
+        memory =
+            memory.reservation(i64::try_from(reservation).context("invalid memory reservation")?);
+    }
+    if let Some(swap) = args.memory_swap {
+        memory = memory.swap(swap);
+    }
+    let memory = memory.build()?;
+    resources.set_memory((memory != Default::default()).then_some(memory));
+
+    if let Some(limit) = args.pids_limit {
+        resources
+            .pids_mut()
+            .get_or_insert_with(Default::default)
+            .set_limit(limit);
+    }
+
+    Ok(resources)
+}
+
+fn memory_builder(memory: LinuxMemory) -> LinuxMemoryBuilder {
+    let mut builder = LinuxMemoryBuilder::default();
+    if let Some(limit) = memory.limit() {
+        builder = builder.limit(limit);
+    }
+    if let Some(reservation) = memory.reservation() {
+        builder = builder.reservation(reservation);
+    }
+    if let Some(swap) = memory.swap() {
+        builder = builder.swap(swap);
+    }
+    if let Some(kernel) = memory.kernel() {
+        builder = builder.kernel(kernel);
+    }
+    if let Some(kernel_tcp) = memory.kernel_tcp() {
+        builder = builder.kernel_tcp(kernel_tcp);
+    }
+    if let Some(swappiness) = memory.swappiness() {
+        builder = builder.swappiness(swappiness);
+    }
+    if let Some(disable_oom_killer) = memory.disable_oom_killer() {
+        builder = builder.disable_oom_killer(disable_oom_killer);
+    }
+    if let Some(use_hierarchy) = memory.use_hierarchy() {
+        builder = builder.use_hierarchy(use_hierarchy);
+    }
+    if let Some(check_before_update) = memory.check_before_update() {
+        builder = builder.check_before_update(check_before_update);
+    }
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use libcontainer::oci_spec::runtime::LinuxResourcesBuilder;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Update {
+        let mut argv = vec!["update"];
+        argv.extend_from_slice(args);
+        argv.push("container");
+        Update::parse_from(argv)
+    }
+
+    #[test]
+    fn test_merge_all_flags() -> Result<()> {
+        let args = parse(&[
+            "--blkio-weight=500",
+            "--cpu-period=100000",
+            "--cpu-quota=50000",
+            "--cpu-rt-period=1000000",
+            "--cpu-rt-runtime=950000",
+            "--cpu-share=512",
+            "--cpuset-cpus=0-3",
+            "--cpuset-mems=0",
+            "--memory=1048576",
+            "--memory-reservation=524288",
+            "--memory-swap=2097152",
+            "--pids-limit=100",
+        ]);
+        let resources = merge_resources(&args, LinuxResources::default())?;
+
+        assert_eq!(resources.block_io().as_ref().unwrap().weight(), Some(500));
+        let cpu = resources.cpu().as_ref().unwrap();
+        assert_eq!(cpu.period(), Some(100000));
+        assert_eq!(cpu.quota(), Some(50000));
+        assert_eq!(cpu.realtime_period(), Some(1000000));
+        assert_eq!(cpu.realtime_runtime(), Some(950000));
+        assert_eq!(cpu.shares(), Some(512));
+        assert_eq!(cpu.cpus().as_deref(), Some("0-3"));
+        assert_eq!(cpu.mems().as_deref(), Some("0"));
+        let memory = resources.memory().as_ref().unwrap();
+        assert_eq!(memory.limit(), Some(1048576));
+        assert_eq!(memory.reservation(), Some(524288));
+        assert_eq!(memory.swap(), Some(2097152));
+        assert_eq!(resources.pids().unwrap().limit(), 100);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_keeps_current_resources() -> Result<()> {
+        let current = LinuxResourcesBuilder::default()
+            .memory(
+                LinuxMemoryBuilder::default()
+                    .limit(1048576)
+                    .swap(2097152)
+                    .build()?,
+            )
+            .build()?;
+        let resources = merge_resources(&parse(&["--memory=2097152"]), current)?;
+
+        let memory = resources.memory().as_ref().unwrap();
+        assert_eq!(memory.limit(), Some(2097152));
+        assert_eq!(memory.swap(), Some(2097152));
+        assert!(resources.cpu().is_none());
+        assert!(resources.pids().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_keeps_other_memory_limits() -> Result<()> {
+        let current = LinuxResourcesBuilder::default()
+            .memory(
+                LinuxMemoryBuilder::default()
+                    .limit(1048576)
+                    .swappiness(10u64)
+                    .disable_oom_killer(true)
+                    .build()?,
+            )
+            .build()?;
+        let resources = merge_resources(&parse(&["--memory-reservation=524288"]), current)?;
+
+        let memory = resources.memory().unwrap();
+        assert_eq!(memory.limit(), Some(1048576));
+        assert_eq!(memory.reservation(), Some(524288));
+        assert_eq!(memory.swappiness(), Some(10));
+        assert_eq!(memory.disable_oom_killer(), Some(true));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+//! Contains functionality of wait container command
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+
+use crate::commands::load_container;
+
+/// Wait for the container process to exit and print its exit status
+#[derive(Parser, Debug)]
+pub struct Wait {
+    /// Give up waiting after the given number of seconds
+    #[clap(long)]
+    pub timeout: Option<u64>,
+    #[clap(value_parser = clap::builder::NonEmptyStringValueParser::new(), required = true)]
+    pub container_id: String,
+}
+
+pub fn wait(args: Wait, root_path: PathBuf) -> Result<i32> {
+    let mut container = load_container(root_path, &args.container_id)?;
+    let status = container
+        .wait(args.timeout.map(Duration::from_secs))
+        .with_context(|| format!("failed to wait for container {}", args.container_id))?;
+
+    let Some(status) = status else {
+        bail!("timed out waiting for container {}", args.container_id);
+    };
+    // only the parent of the init process can read its exit status once it
+    // reaped it, unless it recorded the status in the container state
+    let Some(exit_code) = status.code() else {
+        bail!(
+            "exit status of container {} is not available, it's only known to the parent of its init process",
+            args.container_id
+        );
+    };
+
+    println!("{exit_code}");
+    Ok(exit_code)
+}
@@ -0,0 +1,61 @@
+//! Contains functionality of the wasm-cache maintenance command
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use clap::{Parser, Subcommand};
+use tabwriter::TabWriter;
+
+use crate::workload::cache::{self, CacheEntry};
+
+/// Manage the cache of compiled wasm modules
+#[derive(Parser, Debug)]
+pub struct WasmCache {
+    #[clap(subcommand)]
+    pub command: WasmCacheCmd,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WasmCacheCmd {
+    /// List the cached modules
+    List,
+    /// Remove cached modules
+    Prune {
+        /// Only remove the modules which weren't used for the given number of
+        /// seconds
+        #[clap(long)]
+        older_than: Option<u64>,
+    },
+}
+
+pub fn wasm_cache(args: WasmCache, root_path: PathBuf) -> Result<()> {
+    match args.command {
+        WasmCacheCmd::List => print_entries(&cache::entries(&root_path)?),
+        WasmCacheCmd::Prune { older_than } => {
+            let removed = cache::prune(&root_path, older_than.map(Duration::from_secs))?;
+            let size: u64 = removed.iter().map(|entry| entry.size).sum();
+            println!("removed {} cached modules ({} bytes)", removed.len(), size);
+            Ok(())
+        }
+    }
+}
+
+fn print_entries(entries: &[CacheEntry]) -> Result<()> {
+    let mut tab_writer = TabWriter::new(io::stdout());
+    writeln!(&mut tab_writer, "NAME\tSIZE\tLAST USED")?;
+    for entry in entries {
+        let last_used: DateTime<Local> = DateTime::from(entry.last_used);
+        writeln!(
+            &mut tab_writer,
+            "{}\t{}\t{}",
+            entry.name,
+            entry.size,
+            last_used.to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+        )?;
+    }
+    tab_writer.flush()?;
+
+    Ok(())
+}
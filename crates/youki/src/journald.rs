@@ -0,0 +1,229 @@
+//! Sends the logs to the systemd journal
+//!
+//! The entries are written in the native protocol of journald: a datagram of
+//! `KEY=value` lines, with a binary length prefix for values spanning several
+//! lines. Entries too large for a datagram are passed in a sealed memfd.
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+
+use nix::errno::Errno;
+use nix::fcntl::{self, FcntlArg, SealFlag};
+use nix::sys::memfd::{self, MemFdCreateFlag};
+use nix::sys::socket::{self, ControlMessage, MsgFlags, UnixAddr};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// The socket journald receives native protocol entries on
+pub const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+/// Prefix of the fields of the events, which keeps them apart from the fields
+/// journald and youki set
+const EVENT_FIELD_PREFIX: &str = "F_";
+
+pub struct JournaldLayer {
+    socket: UnixDatagram,
+    /// The encoded fields which are the same for every entry
+    common_fields: Vec<u8>,
+}
+
+impl JournaldLayer {
+    /// Connects to the journal socket. `fields` are added to every entry.
+    pub fn connect(socket_path: &Path, fields: &[(&str, &str)]) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(socket_path)?;
+
+        let mut common_fields = Vec::new();
+        for (name, value) in fields {
+            put_field(&mut common_fields, name, value.as_bytes());
+        }
+
+        Ok(Self {
+            socket,
+            common_fields,
+        })
+    }
+
+    fn send(&self, entry: &[u8]) -> io::Result<()> {
+        match self.socket.send(entry) {
+            Ok(_) => Ok(()),
+            Err(err) if err.raw_os_error() == Some(Errno::EMSGSIZE as i32) => {
+                self.send_memfd(entry)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn send_memfd(&self, entry: &[u8]) -> io::Result<()> {
+        let fd = memfd::memfd_create(
+            c"youki-journal",
+            MemFdCreateFlag::MFD_CLOEXEC | MemFdCreateFlag::MFD_ALLOW_SEALING,
+        )?;
+        let mut file = File::from(fd);
+        file.write_all(entry)?;
+        // journald only accepts memfds which can't be modified anymore
+        fcntl::fcntl(
+            file.as_raw_fd(),
+            FcntlArg::F_ADD_SEALS(
+                SealFlag::F_SEAL_SHRINK
+                    | SealFlag::F_SEAL_GROW
+                    | SealFlag::F_SEAL_WRITE
+                    | SealFlag::F_SEAL_SEAL,
+            ),
+        )?;
+        socket::sendmsg::<UnixAddr>(
+            self.socket.as_raw_fd(),
+            &[],
+            &[ControlMessage::ScmRights(&[file.as_raw_fd()])],
+            MsgFlags::empty(),
+            None,
+        )?;
+
+        Ok(())
+    }
+}
+
+impl<S: Subscriber> Layer<S> for JournaldLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut entry = self.common_fields.clone();
+        put_field(
+            &mut entry,
+            "PRIORITY",
+            priority(metadata.level()).as_bytes(),
+        );
+        put_field(&mut entry, "TARGET", metadata.target().as_bytes());
+        if let Some(file) = metadata.file() {
+            put_field(&mut entry, "CODE_FILE", file.as_bytes());
+        }
+        if let Some(line) = metadata.line() {
+            put_field(&mut entry, "CODE_LINE", line.to_string().as_bytes());
+        }
+        event.record(&mut EntryVisitor(&mut entry));
+
+        // there is nowhere to report a failure to log
+        let _ = self.send(&entry);
+    }
+}
+
+/// Maps the levels to the syslog priorities journald uses
+fn priority(level: &Level) -> &'static str {
+    match *level {
+        Level::ERROR => "3",
+        Level::WARN => "4",
+        Level::INFO => "5",
+        Level::DEBUG => "6",
+        Level::TRACE => "7",
+    }
+}
+
+struct EntryVisitor<'a>(&'a mut Vec<u8>);
+
+impl EntryVisitor<'_> {
+    fn put(&mut self, field: &Field, value: &[u8]) {
+        if field.name() == "message" {
+            put_field(self.0, "MESSAGE", value);
+            return;
+        }
+
+        // field names of the journal only consist of uppercase letters,
+        // digits and underscores
+        let name: String = field
+            .name()
+            .chars()
+            .map(|c| match c {
+                'a'..='z' => c.to_ascii_uppercase(),
+                'A'..='Z' | '0'..='9' => c,
+                _ => '_',
+            })
+            .collect();
+        put_field(self.0, &format!("{EVENT_FIELD_PREFIX}{name}"), value);
+    }
+}
+
+impl Visit for EntryVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.put(field, value.as_bytes());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.put(field, format!("{value:?}").as_bytes());
+    }
+}
+
+/// Appends a field in the native protocol encoding
+fn put_field(entry: &mut Vec<u8>, name: &str, value: &[u8]) {
+    entry.extend_from_slice(name.as_bytes());
+    if value.contains(&b'\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value);
+    entry.push(b'\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use tracing_subscriber::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_put_field() {
+        let mut entry = Vec::new();
+        put_field(&mut entry, "MESSAGE", b"hello");
+        put_field(&mut entry, "MESSAGE", b"two\nlines");
+        let mut expected = b"MESSAGE=hello\nMESSAGE\n".to_vec();
+        expected.extend_from_slice(&9u64.to_le_bytes());
+        expected.extend_from_slice(b"two\nlines\n");
+        assert_eq!(entry, expected);
+    }
+
+    #[test]
+    fn test_send_entries() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let socket_path = tmp.path().join("journal.socket");
+        let journal = UnixDatagram::bind(&socket_path)?;
+        let layer = JournaldLayer::connect(
+            &socket_path,
+            &[
+                ("SYSLOG_IDENTIFIER", "youki"),
+                ("CONTAINER_ID", "test-container"),
+            ],
+        )?;
+
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!(exit_code = 1, "container failed");
+        });
+
+        let mut buf = vec![0u8; 4096];
+        let len = journal.recv(&mut buf)?;
+        let entry = String::from_utf8_lossy(&buf[..len]);
+        let lines: Vec<&str> = entry.lines().collect();
+        for expected in [
+            "SYSLOG_IDENTIFIER=youki",
+            "CONTAINER_ID=test-container",
+            "PRIORITY=3",
+            "MESSAGE=container failed",
+            "F_EXIT_CODE=1",
+        ] {
+            assert!(lines.contains(&expected), "{expected} missing in {entry}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_socket() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        assert!(JournaldLayer::connect(&tmp.path().join("missing"), &[]).is_err());
+        Ok(())
+    }
+}
@@ -2,10 +2,16 @@
 //! Container Runtime written in Rust, inspired by [railcar](https://github.com/oracle/railcar)
 //! This crate provides a container runtime which can be used by a high-level container runtime to run containers.
 mod commands;
+mod journald;
 mod observability;
+mod otlp;
 mod rootpath;
+mod runc_log;
 mod workload;
 
+use std::path::PathBuf;
+use std::time::Duration;
+
 use anyhow::{Context, Result};
 use clap::{crate_version, CommandFactory, Parser};
 use liboci_cli::{CommonCmd, GlobalOpts, StandardCmd};
@@ -21,6 +27,19 @@ struct YoukiExtendOpts {
     /// set the log level (default is 'error')
     #[clap(long)]
     pub log_level: Option<String>,
+    /// Write the timings of the phases of creating and starting a container to
+    /// a JSON file
+    #[clap(long)]
+    pub timing_report: Option<PathBuf>,
+    /// Export the tracing spans to a file in the OTLP/JSON format. The spans
+    /// continue the trace context passed in the TRACEPARENT environment
+    /// variable.
+    #[clap(long)]
+    pub otlp_file: Option<PathBuf>,
+    /// How many seconds to wait for the lock of a container held by another
+    /// youki command (default is 30)
+    #[clap(long, value_name = "SECONDS")]
+    pub lock_timeout: Option<u64>,
 }
 
 /// output Youki version in Moby compatible format
@@ -69,6 +88,73 @@ enum SubCommand {
     // Youki specific extensions
     Info(info::Info),
     Completion(commands::completion::Completion),
+    Wait(commands::wait::Wait),
+    WasmCache(commands::wasm_cache::WasmCache),
+}
+
+impl SubCommand {
+    /// The name of the command, which is reported as the subcommand of the
+    /// spans. The checkpoint command is reported as `checkpoint`, although
+    /// liboci-cli names it `checkpointt` on the command line.
+    fn name(&self) -> &'static str {
+        match self {
+            SubCommand::Standard(cmd) => match cmd.as_ref() {
+                StandardCmd::Create(_) => "create",
+                StandardCmd::Start(_) => "start",
+                StandardCmd::Kill(_) => "kill",
+                StandardCmd::Delete(_) => "delete",
+                StandardCmd::State(_) => "state",
+            },
+            SubCommand::Common(cmd) => match cmd.as_ref() {
+                CommonCmd::Checkpointt(_) => "checkpoint",
+                CommonCmd::Events(_) => "events",
+                CommonCmd::Exec(_) => "exec",
+                CommonCmd::Features(_) => "features",
+                CommonCmd::List(_) => "list",
+                CommonCmd::Pause(_) => "pause",
+                CommonCmd::Ps(_) => "ps",
+                CommonCmd::Restore(_) => "restore",
+                CommonCmd::Resume(_) => "resume",
+                CommonCmd::Run(_) => "run",
+                CommonCmd::Update(_) => "update",
+                CommonCmd::Spec(_) => "spec",
+            },
+            SubCommand::Info(_) => "info",
+            SubCommand::Completion(_) => "completion",
+            SubCommand::Wait(_) => "wait",
+            SubCommand::WasmCache(_) => "wasm-cache",
+        }
+    }
+
+    /// The id of the container the command operates on
+    fn container_id(&self) -> Option<&str> {
+        let id = match self {
+            SubCommand::Standard(cmd) => match cmd.as_ref() {
+                StandardCmd::Create(create) => &create.container_id,
+                StandardCmd::Start(start) => &start.container_id,
+                StandardCmd::Kill(kill) => &kill.container_id,
+                StandardCmd::Delete(delete) => &delete.container_id,
+                StandardCmd::State(state) => &state.container_id,
+            },
+            SubCommand::Common(cmd) => match cmd.as_ref() {
+                CommonCmd::Checkpointt(checkpoint) => &checkpoint.container_id,
+                CommonCmd::Events(events) => &events.container_id,
+                CommonCmd::Exec(exec) => &exec.container_id,
+                CommonCmd::Pause(pause) => &pause.container_id,
+                CommonCmd::Ps(ps) => &ps.container_id,
+                CommonCmd::Restore(restore) => &restore.container_id,
+                CommonCmd::Resume(resume) => &resume.container_id,
+                CommonCmd::Run(run) => &run.container_id,
+                CommonCmd::Update(update) => &update.container_id,
+                CommonCmd::Features(_) | CommonCmd::List(_) | CommonCmd::Spec(_) => return None,
+            },
+            SubCommand::Wait(wait) => &wait.container_id,
+            SubCommand::Info(_) | SubCommand::Completion(_) | SubCommand::WasmCache(_) => {
+                return None
+            }
+        };
+        Some(id)
+    }
 }
 
 /// This is the entry point in the container runtime. The binary is run by a high-level container runtime,
@@ -101,6 +187,10 @@ fn main() -> Result<()> {
     );
     let root_path = rootpath::determine(opts.global.root)?;
     let systemd_cgroup = opts.global.systemd_cgroup;
+    let timing_report = opts.youki_extend.timing_report;
+    if let Some(timeout) = opts.youki_extend.lock_timeout {
+        commands::set_lock_timeout(Duration::from_secs(timeout));
+    }
 
     let cmd_result = match opts.subcmd {
         SubCommand::Standard(cmd) => match *cmd {
@@ -120,7 +210,7 @@ fn main() -> Result<()> {
             CommonCmd::Exec(exec) => match commands::exec::exec(exec, root_path) {
                 Ok(exit_code) => std::process::exit(exit_code),
                 Err(e) => {
-                    tracing::error!("error in executing command: {:?}", e);
+                    observability::log_command_error(&e);
                     eprintln!("exec failed : {e}");
                     std::process::exit(-1);
                 }
@@ -129,15 +219,27 @@ fn main() -> Result<()> {
             CommonCmd::List(list) => commands::list::list(list, root_path),
             CommonCmd::Pause(pause) => commands::pause::pause(pause, root_path),
             CommonCmd::Ps(ps) => commands::ps::ps(ps, root_path),
+            CommonCmd::Restore(restore) => {
+                match commands::restore::restore(restore, root_path, systemd_cgroup) {
+                    Ok(exit_code) => std::process::exit(exit_code),
+                    Err(e) => {
+                        observability::log_command_error(&e);
+                        eprintln!("restore failed : {e}");
+                        std::process::exit(-1);
+                    }
+                }
+            }
             CommonCmd::Resume(resume) => commands::resume::resume(resume, root_path),
-            CommonCmd::Run(run) => match commands::run::run(run, root_path, systemd_cgroup) {
-                Ok(exit_code) => std::process::exit(exit_code),
-                Err(e) => {
-                    tracing::error!("error in executing command: {:?}", e);
-                    eprintln!("run failed : {e}");
-                    std::process::exit(-1);
+            CommonCmd::Run(run) => {
+                match commands::run::run(run, root_path, systemd_cgroup, timing_report.as_deref()) {
+                    Ok(exit_code) => std::process::exit(exit_code),
+                    Err(e) => {
+                        observability::log_command_error(&e);
+                        eprintln!("run failed : {e}");
+                        std::process::exit(-1);
+                    }
                 }
-            },
+            }
             CommonCmd::Spec(spec) => commands::spec_json::spec(spec),
             CommonCmd::Update(update) => commands::update::update(update, root_path),
         },
@@ -146,10 +248,25 @@ fn main() -> Result<()> {
         SubCommand::Completion(completion) => {
             commands::completion::completion(completion, &mut app)
         }
+        SubCommand::Wait(wait) => match commands::wait::wait(wait, root_path) {
+            Ok(exit_code) => std::process::exit(exit_code),
+            Err(e) => {
+                observability::log_command_error(&e);
+                eprintln!("wait failed : {e}");
+                std::process::exit(-1);
+            }
+        },
+        SubCommand::WasmCache(wasm_cache) => {
+            commands::wasm_cache::wasm_cache(wasm_cache, root_path)
+        }
     };
 
+    if let (Ok(()), Some(path)) = (&cmd_result, &timing_report) {
+        observability::write_timing_report(path);
+    }
+
     if let Err(ref e) = cmd_result {
-        tracing::error!("error in executing command: {:?}", e);
+        observability::log_command_error(e);
         eprintln!("error in executing command: {:?}", e);
     }
     cmd_result
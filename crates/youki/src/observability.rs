@@ -1,12 +1,23 @@
 use std::borrow::Cow;
 use std::fs::OpenOptions;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::{bail, Context, Result};
+use libcontainer::timing::{self, PhaseTiming};
+use serde::Serialize;
 use tracing::Level;
+use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::prelude::*;
 
+use crate::journald::{JournaldLayer, JOURNALD_SOCKET};
+use crate::otlp::{OtlpFileLayer, TraceContext};
+use crate::runc_log::{self, RuncJsonLayer};
+
+/// Whether the logs are written in the format of runc
+static RUNC_LOG_FORMAT: AtomicBool = AtomicBool::new(false);
+
 const LOG_FORMAT_TEXT: &str = "text";
 const LOG_FORMAT_JSON: &str = "json";
 enum LogFormat {
@@ -49,8 +60,16 @@ pub struct ObservabilityConfig {
     pub log_level: Option<String>,
     pub log_file: Option<PathBuf>,
     pub log_format: Option<String>,
-    #[allow(dead_code)]
     pub systemd_log: bool,
+    /// The journal socket, [`JOURNALD_SOCKET`] if not set
+    pub journald_socket: Option<PathBuf>,
+    /// File to export the tracing spans to in the OTLP/JSON format
+    pub otlp_file: Option<PathBuf>,
+    /// Id of the container the command operates on, attached to the spans and
+    /// journal entries
+    pub container_id: Option<String>,
+    /// Name of the command, attached to the journal entries
+    pub subcommand: Option<String>,
 }
 
 impl From<&crate::Opts> for ObservabilityConfig {
@@ -61,6 +80,10 @@ impl From<&crate::Opts> for ObservabilityConfig {
             log_file: opts.global.log.to_owned(),
             log_format: opts.global.log_format.to_owned(),
             systemd_log: opts.youki_extend.systemd_log,
+            journald_socket: None,
+            otlp_file: opts.youki_extend.otlp_file.to_owned(),
+            container_id: opts.subcmd.container_id().map(str::to_owned),
+            subcommand: Some(opts.subcmd.name().to_owned()),
         }
     }
 }
@@ -72,31 +95,50 @@ where
     let config = config.into();
     let level = detect_log_level(config.log_level, config.log_debug_flag)
         .with_context(|| "failed to parse log level")?;
-    let log_level_filter = tracing_subscriber::filter::LevelFilter::from(level);
+    let log_level_filter = LevelFilter::from(level);
     let log_format = detect_log_format(config.log_format.as_deref())
         .with_context(|| "failed to detect log format")?;
 
-    #[cfg(debug_assertions)]
-    let journald = true;
-    #[cfg(not(debug_assertions))]
-    let journald = config.systemd_log;
-
-    let systemd_journald = if journald {
-        match tracing_journald::layer() {
-            Ok(layer) => Some(layer.with_syslog_identifier("youki".to_string())),
+    // Without a journal, e.g. in docker-in-docker, the logs only go to the log
+    // file, and the warning about it is logged once the logger is set up.
+    let mut journald_error = None;
+    let systemd_journald = if config.systemd_log {
+        let socket = config
+            .journald_socket
+            .as_deref()
+            .unwrap_or(Path::new(JOURNALD_SOCKET));
+        let mut fields = vec![("SYSLOG_IDENTIFIER", "youki")];
+        if let Some(container_id) = &config.container_id {
+            fields.push(("CONTAINER_ID", container_id));
+        }
+        if let Some(subcommand) = &config.subcommand {
+            fields.push(("SUBCOMMAND", subcommand));
+        }
+        match JournaldLayer::connect(socket, &fields) {
+            Ok(layer) => Some(layer),
             Err(err) => {
-                // Do not fail if we can't open syslog, just print a warning.
-                // This is the case in, e.g., docker-in-docker.
-                eprintln!("failed to initialize syslog logging: {:?}", err);
+                journald_error = Some(err);
                 None
             }
         }
     } else {
         None
     };
+
+    // The exported spans are filtered apart from the logs, so the debug spans
+    // measuring the phases of a container are exported at the default log
+    // level too.
+    let otlp = config
+        .otlp_file
+        .as_deref()
+        .map(|path| OtlpFileLayer::new(path, TraceContext::from_env(), config.container_id))
+        .transpose()
+        .with_context(|| "failed to initialize the OTLP exporter")?;
+    let otlp_level_filter = log_level_filter.max(LevelFilter::DEBUG);
+
     let subscriber = tracing_subscriber::registry()
-        .with(log_level_filter)
-        .with(systemd_journald);
+        .with(otlp.with_filter(otlp_level_filter))
+        .with(systemd_journald.with_filter(log_level_filter));
 
     // I really dislike how we have to specify individual branch for each
     // combination, but I can't find any better way to do this. The tracing
@@ -109,7 +151,8 @@ where
                 .with(
                     tracing_subscriber::fmt::layer()
                         .without_time()
-                        .with_writer(std::io::stderr),
+                        .with_writer(std::io::stderr)
+                        .with_filter(log_level_filter),
                 )
                 .try_init()
                 .map_err(|e| anyhow::anyhow!("failed to init logger: {}", e))?;
@@ -122,7 +165,8 @@ where
                         .json()
                         .flatten_event(true)
                         .with_span_list(false)
-                        .with_writer(std::io::stderr),
+                        .with_writer(std::io::stderr)
+                        .with_filter(log_level_filter),
                 )
                 .try_init()
                 .map_err(|e| anyhow::anyhow!("failed to init logger: {}", e))?;
@@ -136,7 +180,11 @@ where
                 .open(path)
                 .with_context(|| "failed to open log file")?;
             subscriber
-                .with(tracing_subscriber::fmt::layer().with_writer(file))
+                .with(
+                    tracing_subscriber::fmt::layer()
+                        .with_writer(file)
+                        .with_filter(log_level_filter),
+                )
                 .try_init()
                 .map_err(|e| anyhow::anyhow!("failed to init logger: {}", e))?;
         }
@@ -148,27 +196,65 @@ where
                 .truncate(false)
                 .open(path)
                 .with_context(|| "failed to open log file")?;
+            // Only the shims pass a log file with the JSON format, and they
+            // parse it with the format of runc
             subscriber
-                .with(
-                    tracing_subscriber::fmt::layer()
-                        .json()
-                        .flatten_event(true)
-                        .with_span_list(false)
-                        .with_writer(file),
-                )
+                .with(RuncJsonLayer::new(file).with_filter(log_level_filter))
                 .try_init()
                 .map_err(|e| anyhow::anyhow!("failed to init logger: {}", e))?;
+            RUNC_LOG_FORMAT.store(true, Ordering::Relaxed);
         }
     }
 
+    if let Some(err) = journald_error {
+        tracing::warn!(
+            ?err,
+            "journald is not available, logging to the log file only"
+        );
+    }
+
     Ok(())
 }
 
+/// Logs the error a command failed with. With the log format of runc, the last
+/// error in the log is a single line summary of it, which the shims report.
+pub fn log_command_error(err: &anyhow::Error) {
+    tracing::error!("error in executing command: {:?}", err);
+    if RUNC_LOG_FORMAT.load(Ordering::Relaxed) {
+        tracing::error!("{}", runc_log::summarize(err));
+    }
+}
+
+/// Phases of creating and starting a container, as written by --timing-report
+#[derive(Serialize)]
+struct TimingReport {
+    phases: Vec<PhaseTiming>,
+}
+
+/// Writes the phases measured by this youki process and the container
+/// processes it created to `path` as JSON. The container exists at this point,
+/// so failures are only logged.
+pub fn write_timing_report(path: &Path) {
+    let report = TimingReport {
+        phases: timing::take(),
+    };
+    let written = serde_json::to_vec_pretty(&report)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| {
+            std::fs::write(path, json)
+                .with_context(|| format!("failed to write {}", path.display()))
+        });
+    if let Err(err) = written {
+        tracing::warn!(?err, "failed to write timing report");
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::Path;
 
     use libcontainer::test_utils::TestCallbackError;
+    use serial_test::serial;
 
     use super::*;
 
@@ -274,6 +360,101 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_systemd_log() -> Result<()> {
+        libcontainer::test_utils::test_in_child_process(|| {
+            let temp_dir = tempfile::tempdir().expect("failed to create temp dir");
+            let socket_path = temp_dir.path().join("journal.socket");
+            let journal = std::os::unix::net::UnixDatagram::bind(&socket_path)
+                .map_err(|err| format!("failed to bind journal socket: {err:?}"))?;
+            let config = ObservabilityConfig {
+                log_file: Some(temp_dir.path().join("test.log")),
+                systemd_log: true,
+                journald_socket: Some(socket_path),
+                container_id: Some("test-container".to_owned()),
+                subcommand: Some("create".to_owned()),
+                ..Default::default()
+            };
+            init(config).map_err(|err| TestCallbackError::Other(err.into()))?;
+            tracing::error!("testing journald");
+
+            let mut buf = vec![0u8; 4096];
+            let len = journal
+                .recv(&mut buf)
+                .map_err(|err| format!("failed to receive journal entry: {err:?}"))?;
+            let entry = String::from_utf8_lossy(&buf[..len]).into_owned();
+            for field in [
+                "SYSLOG_IDENTIFIER=youki",
+                "CONTAINER_ID=test-container",
+                "SUBCOMMAND=create",
+                "MESSAGE=testing journald",
+            ] {
+                if !entry.lines().any(|line| line == field) {
+                    Err(format!("{field} missing in journal entry {entry}"))?;
+                }
+            }
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_systemd_log_fallback() -> Result<()> {
+        libcontainer::test_utils::test_in_child_process(|| {
+            let temp_dir = tempfile::tempdir().expect("failed to create temp dir");
+            let log_file = temp_dir.path().join("test.log");
+            let config = ObservabilityConfig {
+                log_file: Some(log_file.clone()),
+                log_level: Some("warn".to_owned()),
+                systemd_log: true,
+                journald_socket: Some(temp_dir.path().join("missing.socket")),
+                ..Default::default()
+            };
+            init(config).map_err(|err| TestCallbackError::Other(err.into()))?;
+            tracing::error!("testing fallback");
+
+            let data = std::fs::read_to_string(&log_file)
+                .map_err(|err| format!("failed to read the logfile: {err:?}"))?;
+            if !data.contains("journald is not available") || !data.contains("testing fallback") {
+                Err(format!("expected the logs in the log file, got: {data}"))?;
+            }
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_runc_error_summary() -> Result<()> {
+        libcontainer::test_utils::test_in_child_process(|| {
+            let temp_dir = tempfile::tempdir().expect("failed to create temp dir");
+            let log_file = Path::join(temp_dir.path(), "test.log");
+            let config = ObservabilityConfig {
+                log_file: Some(log_file.clone()),
+                log_format: Some(LOG_FORMAT_JSON.to_owned()),
+                ..Default::default()
+            };
+            init(config).map_err(|err| TestCallbackError::Other(err.into()))?;
+            let err = anyhow::anyhow!("permission denied").context("failed to create container");
+            log_command_error(&err);
+
+            let data = std::fs::read_to_string(&log_file)
+                .map_err(|err| format!("failed to read the logfile: {err:?}"))?;
+            let last = data.lines().last().ok_or("logfile should not be empty")?;
+            let entry = serde_json::from_str::<serde_json::Value>(last)
+                .map_err(|err| format!("failed to parse {last}: {err:?}"))?;
+            if entry["level"] != "error"
+                || entry["msg"] != "failed to create container: permission denied"
+            {
+                Err(format!("unexpected last error {last}"))?;
+            }
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_json_logfile() -> Result<()> {
         libcontainer::test_utils::test_in_child_process(|| {
@@ -310,4 +491,31 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    #[serial]
+    fn test_write_timing_report() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let report = temp_dir.path().join("timings.json");
+        timing::clear();
+        timing::record(vec![PhaseTiming {
+            process: timing::PhaseProcess::Init,
+            phase: "rootfs".to_owned(),
+            start_ns: 1,
+            duration_ns: 2,
+        }]);
+        write_timing_report(&report);
+
+        let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&report)?)?;
+        assert_eq!(
+            json["phases"][0],
+            serde_json::json!({
+                "process": "init",
+                "phase": "rootfs",
+                "start_ns": 1,
+                "duration_ns": 2,
+            })
+        );
+        Ok(())
+    }
 }
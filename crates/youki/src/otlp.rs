@@ -0,0 +1,486 @@
+//! Exports the tracing spans to a file in the OTLP/JSON format
+//!
+//! Every line of the file is an OTLP `ExportTraceServiceRequest` holding one
+//! span, which an OpenTelemetry collector can pick up with its `otlpjsonfile`
+//! receiver. Spans are written when they are closed rather than when youki
+//! exits, so the spans of the intermediate and init processes, which inherit
+//! the exporter and never return to `main`, are exported as well. The file is
+//! opened with `O_APPEND`, so the lines of the processes don't interleave.
+//!
+//! The spans continue the trace of the caller if it passes a W3C trace context
+//! in the `TRACEPARENT` environment variable, and all of them carry the id of
+//! the container the command operates on.
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use anyhow::{Context as _, Result};
+use serde::Serialize;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Environment variable carrying the W3C trace context of the caller
+pub const TRACEPARENT_ENV: &str = "TRACEPARENT";
+
+/// Span kind INTERNAL of the OTLP protocol
+const SPAN_KIND_INTERNAL: u8 = 1;
+/// Status code ERROR of the OTLP protocol
+const STATUS_CODE_ERROR: u8 = 2;
+
+/// The trace the spans of this youki invocation belong to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: String,
+    /// The span of the caller the root spans of youki are children of
+    pub parent_span_id: Option<String>,
+}
+
+impl TraceContext {
+    /// Continues the trace of a W3C `traceparent` header value, e.g.
+    /// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`
+    pub fn from_traceparent(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let parent_id = parts.next()?;
+        let flags = parts.next()?;
+        // later versions may append fields, version 00 must not
+        if version == "ff" || (version == "00" && parts.next().is_some()) {
+            return None;
+        }
+        let valid = |field: &str, len: usize| {
+            field.len() == len
+                && field
+                    .bytes()
+                    .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        };
+        if !valid(version, 2) || !valid(flags, 2) {
+            return None;
+        }
+        let non_zero = |field: &str| field.bytes().any(|b| b != b'0');
+        if !valid(trace_id, 32)
+            || !non_zero(trace_id)
+            || !valid(parent_id, 16)
+            || !non_zero(parent_id)
+        {
+            return None;
+        }
+
+        Some(Self {
+            trace_id: trace_id.to_owned(),
+            parent_span_id: Some(parent_id.to_owned()),
+        })
+    }
+
+    /// Starts a new trace
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: format!("{:016x}{:016x}", random_id(), random_id()),
+            parent_span_id: None,
+        }
+    }
+
+    /// Continues the trace of the `TRACEPARENT` environment variable, or
+    /// starts a new one if it isn't set or invalid
+    pub fn from_env() -> Self {
+        std::env::var(TRACEPARENT_ENV)
+            .ok()
+            .and_then(|traceparent| Self::from_traceparent(&traceparent))
+            .unwrap_or_else(Self::new_root)
+    }
+}
+
+/// Returns a random, non-zero id. The ids have to be unique across the youki
+/// processes, and the cloned container processes inherit the state of the
+/// random generator, so the pid and the time are mixed in.
+fn random_id() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.write_u64(unix_nanos(SystemTime::now()));
+    hasher.finish().max(1)
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
+
+pub struct OtlpFileLayer {
+    file: File,
+    context: TraceContext,
+    container_id: Option<String>,
+}
+
+impl OtlpFileLayer {
+    pub fn new(path: &Path, context: TraceContext, container_id: Option<String>) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+
+        Ok(Self {
+            file,
+            context,
+            container_id,
+        })
+    }
+
+    fn export(&self, span: OtlpSpan) {
+        let request = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Resource {
+                    attributes: vec![
+                        KeyValue::string("service.name", "youki"),
+                        KeyValue::string("service.version", env!("CARGO_PKG_VERSION")),
+                    ],
+                },
+                scope_spans: vec![ScopeSpans {
+                    scope: Scope {
+                        name: "youki",
+                        version: env!("CARGO_PKG_VERSION"),
+                    },
+                    spans: vec![span],
+                }],
+            }],
+        };
+        // a span which can't be exported must not fail the operation it
+        // measured, and there is no log to report it to but this one
+        if let Ok(mut line) = serde_json::to_vec(&request) {
+            line.push(b'\n');
+            let _ = (&self.file).write_all(&line);
+        }
+    }
+}
+
+impl<S> Layer<S> for OtlpFileLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let parent_span_id = match span.parent() {
+            Some(parent) => parent
+                .extensions()
+                .get::<OtlpSpan>()
+                .map(|parent| parent.span_id.clone()),
+            None => self.context.parent_span_id.clone(),
+        };
+
+        let metadata = attrs.metadata();
+        let mut attributes = vec![
+            KeyValue::string("code.namespace", metadata.target()),
+            KeyValue::int("process.pid", std::process::id() as i64),
+        ];
+        if let Some(container_id) = &self.container_id {
+            attributes.push(KeyValue::string("container.id", container_id));
+        }
+        attrs.record(&mut AttributeVisitor(&mut attributes));
+
+        span.extensions_mut().insert(OtlpSpan {
+            trace_id: self.context.trace_id.clone(),
+            span_id: format!("{:016x}", random_id()),
+            parent_span_id,
+            name: metadata.name().to_owned(),
+            kind: SPAN_KIND_INTERNAL,
+            start_time_unix_nano: unix_nanos(SystemTime::now()).to_string(),
+            end_time_unix_nano: String::new(),
+            attributes,
+            events: Vec::new(),
+            status: Status::default(),
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(data) = span.extensions_mut().get_mut::<OtlpSpan>() {
+                values.record(&mut AttributeVisitor(&mut data.attributes));
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.event_span(event) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        let Some(data) = extensions.get_mut::<OtlpSpan>() else {
+            return;
+        };
+
+        let level = *event.metadata().level();
+        let mut attributes = vec![KeyValue::string("level", &level.to_string())];
+        event.record(&mut AttributeVisitor(&mut attributes));
+        // the message is the name of the event rather than an attribute
+        let name = match attributes.iter().position(|kv| kv.key == "message") {
+            Some(index) => match attributes.remove(index).value {
+                AnyValue::String(message) => message,
+                value => format!("{value:?}"),
+            },
+            None => event.metadata().name().to_owned(),
+        };
+        if level == Level::ERROR {
+            data.status = Status {
+                code: Some(STATUS_CODE_ERROR),
+                message: Some(name.clone()),
+            };
+        }
+        data.events.push(SpanEvent {
+            time_unix_nano: unix_nanos(SystemTime::now()).to_string(),
+            name,
+            attributes,
+        });
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(mut data) = span.extensions_mut().remove::<OtlpSpan>() else {
+            return;
+        };
+        data.end_time_unix_nano = unix_nanos(SystemTime::now()).to_string();
+        self.export(data);
+    }
+}
+
+struct AttributeVisitor<'a>(&'a mut Vec<KeyValue>);
+
+impl Visit for AttributeVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push(KeyValue::string(field.name(), value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.push(KeyValue::int(field.name(), value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match i64::try_from(value) {
+            Ok(value) => self.0.push(KeyValue::int(field.name(), value)),
+            Err(_) => self
+                .0
+                .push(KeyValue::string(field.name(), &value.to_string())),
+        }
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.push(KeyValue {
+            key: field.name().to_owned(),
+            value: AnyValue::Bool(value),
+        });
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.push(KeyValue {
+            key: field.name().to_owned(),
+            value: AnyValue::Double(value),
+        });
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .push(KeyValue::string(field.name(), &format!("{value:?}")));
+    }
+}
+
+// The messages of the OTLP/JSON encoding: the field names are lowerCamelCase,
+// 64 bit integers are strings and the ids are hex encoded.
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportTraceServiceRequest {
+    resource_spans: Vec<ResourceSpans>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceSpans {
+    resource: Resource,
+    scope_spans: Vec<ScopeSpans>,
+}
+
+#[derive(Serialize)]
+struct Resource {
+    attributes: Vec<KeyValue>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScopeSpans {
+    scope: Scope,
+    spans: Vec<OtlpSpan>,
+}
+
+#[derive(Serialize)]
+struct Scope {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OtlpSpan {
+    trace_id: String,
+    span_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent_span_id: Option<String>,
+    name: String,
+    kind: u8,
+    start_time_unix_nano: String,
+    end_time_unix_nano: String,
+    attributes: Vec<KeyValue>,
+    events: Vec<SpanEvent>,
+    status: Status,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpanEvent {
+    time_unix_nano: String,
+    name: String,
+    attributes: Vec<KeyValue>,
+}
+
+#[derive(Serialize, Default)]
+struct Status {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+#[derive(Serialize)]
+struct KeyValue {
+    key: String,
+    value: AnyValue,
+}
+
+impl KeyValue {
+    fn string(key: &str, value: &str) -> Self {
+        Self {
+            key: key.to_owned(),
+            value: AnyValue::String(value.to_owned()),
+        }
+    }
+
+    fn int(key: &str, value: i64) -> Self {
+        Self {
+            key: key.to_owned(),
+            value: AnyValue::Int(value.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+enum AnyValue {
+    #[serde(rename = "stringValue")]
+    String(String),
+    #[serde(rename = "intValue")]
+    Int(String),
+    #[serde(rename = "boolValue")]
+    Bool(bool),
+    #[serde(rename = "doubleValue")]
+    Double(f64),
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use tracing_subscriber::prelude::*;
+
+    use super::*;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const PARENT_ID: &str = "00f067aa0ba902b7";
+
+    #[test]
+    fn test_from_traceparent() {
+        assert_eq!(
+            TraceContext::from_traceparent(&format!("00-{TRACE_ID}-{PARENT_ID}-01")),
+            Some(TraceContext {
+                trace_id: TRACE_ID.to_owned(),
+                parent_span_id: Some(PARENT_ID.to_owned()),
+            })
+        );
+        for invalid in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-00",
+        ] {
+            assert_eq!(TraceContext::from_traceparent(invalid), None, "{invalid}");
+        }
+
+        let root = TraceContext::new_root();
+        assert_eq!(root.trace_id.len(), 32);
+        assert_eq!(root.parent_span_id, None);
+    }
+
+    #[test]
+    fn test_export_spans() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("trace.json");
+        let context = TraceContext::from_traceparent(&format!("00-{TRACE_ID}-{PARENT_ID}-01"))
+            .expect("valid trace context");
+        let layer = OtlpFileLayer::new(&path, context, Some("test-container".to_owned()))?;
+
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            let _outer = tracing::info_span!("outer", phase = "create").entered();
+            let _inner = tracing::info_span!("inner").entered();
+            tracing::error!(code = 3, "failed");
+        });
+
+        let spans: Vec<Value> = std::fs::read_to_string(&path)?
+            .lines()
+            .map(|line| {
+                let request: Value = serde_json::from_str(line).expect("valid json");
+                request["resourceSpans"][0]["scopeSpans"][0]["spans"][0].clone()
+            })
+            .collect();
+        // the inner span is closed first
+        let [inner, outer] = spans.as_slice() else {
+            panic!("expected two spans, got {spans:?}");
+        };
+
+        assert_eq!(outer["name"], "outer");
+        assert_eq!(outer["traceId"], TRACE_ID);
+        assert_eq!(outer["parentSpanId"], PARENT_ID);
+        assert_eq!(inner["traceId"], TRACE_ID);
+        assert_eq!(inner["parentSpanId"], outer["spanId"]);
+        for span in [inner, outer] {
+            let attributes = span["attributes"].as_array().unwrap();
+            assert!(attributes.contains(&serde_json::json!({
+                "key": "container.id",
+                "value": {"stringValue": "test-container"},
+            })));
+        }
+        assert!(outer["attributes"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!({"key": "phase", "value": {"stringValue": "create"}})));
+
+        assert_eq!(inner["events"][0]["name"], "failed");
+        assert_eq!(inner["status"]["code"], STATUS_CODE_ERROR);
+        assert_eq!(outer["status"], serde_json::json!({}));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+//! Writes the logs in the JSON format of runc
+//!
+//! containerd and CRI-O pass `--log <file> --log-format json` to the runtime
+//! and parse the file with the format of runc, which logs with logrus: every
+//! line is an object with the `level`, `msg` and `time` keys and the fields of
+//! the entry. When the runtime fails, the shims report the `msg` of the last
+//! error in the file.
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+
+use chrono::{Local, SecondsFormat};
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+pub struct RuncJsonLayer {
+    file: File,
+}
+
+impl RuncJsonLayer {
+    pub fn new(file: File) -> Self {
+        Self { file }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for RuncJsonLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut entry = Map::new();
+        event.record(&mut EntryVisitor(&mut entry));
+        // the keys of logrus take precedence over fields of the same name
+        entry.insert("level".to_owned(), level(event.metadata().level()).into());
+        let msg = entry.remove("message").unwrap_or_else(|| "".into());
+        entry.insert("msg".to_owned(), msg);
+        entry.insert(
+            "time".to_owned(),
+            Local::now()
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+                .into(),
+        );
+
+        if let Ok(mut line) = serde_json::to_vec(&entry) {
+            line.push(b'\n');
+            // a single write, so the lines of the container processes sharing
+            // the file don't interleave
+            let _ = (&self.file).write_all(&line);
+        }
+    }
+}
+
+/// The names logrus uses for the levels
+fn level(level: &Level) -> &'static str {
+    match *level {
+        Level::ERROR => "error",
+        Level::WARN => "warning",
+        Level::INFO => "info",
+        Level::DEBUG => "debug",
+        Level::TRACE => "trace",
+    }
+}
+
+/// Returns the error and its causes on a single line, which is what the shims
+/// show to the users
+pub fn summarize(err: &anyhow::Error) -> String {
+    format!("{err:#}").replace('\n', " ")
+}
+
+struct EntryVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for EntryVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_owned(), format!("{value:?}").into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{anyhow, Context as _, Result};
+    use tracing_subscriber::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_runc_json_format() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("log.json");
+        let layer = RuncJsonLayer::new(File::create(&path)?);
+
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(pid = 42, "container exited");
+            tracing::error!("failed\nto start");
+        });
+
+        let entries: Vec<Value> = std::fs::read_to_string(&path)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["level"], "warning");
+        assert_eq!(entries[0]["msg"], "container exited");
+        assert_eq!(entries[0]["pid"], 42);
+        assert_eq!(entries[1]["level"], "error");
+        assert_eq!(entries[1]["msg"], "failed\nto start");
+
+        let time = entries[0]["time"].as_str().expect("time is a string");
+        chrono::DateTime::parse_from_rfc3339(time)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summarize() {
+        let err = Err::<(), _>(anyhow!("permission denied\n(os error 13)"))
+            .context("failed to create cgroup")
+            .context("failed to create container")
+            .unwrap_err();
+        assert_eq!(
+            summarize(&err),
+            "failed to create container: failed to create cgroup: permission denied (os error 13)"
+        );
+    }
+}
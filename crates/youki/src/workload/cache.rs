@@ -0,0 +1,353 @@
+//! Cache of the ahead-of-time compiled wasm modules
+//!
+//! Compiling a module dominates the start time of a wasm container, so the
+//! executors keep the compiled artifacts in a directory of the youki root which
+//! is shared by all containers. An entry is named after the digest of the module
+//! and a hash of everything changing the compiled code, like the engine version,
+//! the target features and the engine settings.
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{fs, io};
+
+use anyhow::{Context, Result};
+
+/// Directory of the cache in the youki root directory. The leading dot keeps it
+/// apart from the container directories.
+pub const CACHE_DIR: &str = ".wasm-cache";
+/// Prefix of the files an entry is written to before it's renamed into place
+const TMP_PREFIX: &str = ".tmp-";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub name: String,
+    pub size: u64,
+    /// Last time the entry was stored or loaded
+    pub last_used: SystemTime,
+}
+
+pub fn cache_dir(root_path: &Path) -> PathBuf {
+    root_path.join(CACHE_DIR)
+}
+
+/// Lists the entries of the cache in the youki root directory
+pub fn entries(root_path: &Path) -> Result<Vec<CacheEntry>> {
+    let mut entries: Vec<CacheEntry> = read_entries(&cache_dir(root_path))?
+        .into_iter()
+        .filter(|entry| !entry.name.starts_with(TMP_PREFIX))
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Removes the entries which weren't used for longer than `max_age`, or all of
+/// them without one, including the leftovers of interrupted writes. Returns the
+/// removed entries.
+pub fn prune(root_path: &Path, max_age: Option<Duration>) -> Result<Vec<CacheEntry>> {
+    let dir = cache_dir(root_path);
+    let now = SystemTime::now();
+    let mut removed = Vec::new();
+    for entry in read_entries(&dir)? {
+        let age = now.duration_since(entry.last_used).unwrap_or_default();
+        if max_age.is_some_and(|max_age| age <= max_age) {
+            continue;
+        }
+
+        match fs::remove_file(dir.join(&entry.name)) {
+            Ok(()) => removed.push(entry),
+            // removed by a concurrent prune
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to remove {}", entry.name))
+            }
+        }
+    }
+    removed.retain(|entry| !entry.name.starts_with(TMP_PREFIX));
+    removed.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(removed)
+}
+
+fn read_entries(dir: &Path) -> Result<Vec<CacheEntry>> {
+    let read_dir = match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", dir.display())),
+    };
+
+    let mut entries = Vec::new();
+    for entry in read_dir {
+        let entry = entry?;
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        entries.push(CacheEntry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            size: metadata.len(),
+            last_used: metadata.modified()?,
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(any(feature = "wasm-wasmer", feature = "wasm-wasmtime"))]
+pub use module_cache::{key, ModuleCache};
+
+#[cfg(any(feature = "wasm-wasmer", feature = "wasm-wasmtime"))]
+mod module_cache {
+    use std::fmt::Write as _;
+    use std::fs::File;
+    use std::hash::{Hash, Hasher};
+    use std::io::{Read, Write};
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+    use std::os::unix::fs::DirBuilderExt;
+    use std::sync::{Arc, Mutex};
+
+    use nix::fcntl::{self, OFlag};
+    use nix::sys::stat::Mode;
+    use nix::unistd::{self, UnlinkatFlags};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+
+    /// Returns the name of the entry of a module compiled by `engine`. The
+    /// `config` has to cover everything the compiled code depends on besides
+    /// the module itself.
+    pub fn key(engine: &str, config: impl Hash, module: &[u8]) -> String {
+        let mut hasher = DigestHasher(Sha256::new());
+        config.hash(&mut hasher);
+
+        let mut key = String::with_capacity(96);
+        for byte in Sha256::digest(module) {
+            let _ = write!(key, "{byte:02x}");
+        }
+        let _ = write!(key, "-{engine}-");
+        for byte in &hasher.0.finalize()[..8] {
+            let _ = write!(key, "{byte:02x}");
+        }
+        key
+    }
+
+    /// Feeds the hashed bytes into a digest. Unlike the hashers of the
+    /// standard library its output doesn't change between Rust releases, so
+    /// the keys of the entries stay the same.
+    struct DigestHasher(Sha256);
+
+    impl Hasher for DigestHasher {
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.update(bytes);
+        }
+
+        fn finish(&self) -> u64 {
+            let digest = self.0.clone().finalize();
+            u64::from_be_bytes(digest[..8].try_into().unwrap())
+        }
+    }
+
+    /// Handle on the cache directory for the executors. The directory is opened
+    /// before the container is created, because the init process can't reach
+    /// the youki root directory anymore once it changed into the rootfs.
+    #[derive(Clone)]
+    pub struct ModuleCache {
+        dir: Arc<Mutex<Option<OwnedFd>>>,
+    }
+
+    impl ModuleCache {
+        /// Opens the cache directory of the youki root directory, creating it
+        /// if needed
+        pub fn open(root_path: &Path) -> Result<Self> {
+            let path = cache_dir(root_path);
+            fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(&path)
+                .with_context(|| format!("failed to create {}", path.display()))?;
+            // File::open opens the directory with O_CLOEXEC, so it isn't
+            // inherited by the workloads the default executor runs
+            let dir =
+                File::open(&path).with_context(|| format!("failed to open {}", path.display()))?;
+
+            Ok(Self {
+                dir: Arc::new(Mutex::new(Some(dir.into()))),
+            })
+        }
+
+        /// Returns the compiled artifact stored under `key`
+        pub fn load(&self, key: &str) -> Option<Vec<u8>> {
+            let mut file = self.open_at(key, OFlag::O_RDONLY, Mode::empty()).ok()?;
+            let mut artifact = Vec::new();
+            if let Err(err) = file.read_to_end(&mut artifact) {
+                tracing::warn!(?err, key, "failed to read cached wasm module");
+                return None;
+            }
+            // the modification time tells prune when the entry was last used
+            if let Err(err) = file.set_modified(SystemTime::now()) {
+                tracing::debug!(?err, key, "failed to update cached wasm module");
+            }
+
+            tracing::debug!(key, "loaded compiled wasm module from cache");
+            Some(artifact)
+        }
+
+        /// Stores the compiled artifact under `key`. The artifact is written to
+        /// a temporary file first and renamed into place, so concurrent readers
+        /// never see a partial entry and concurrent writers of the same module
+        /// simply replace each other's identical entry. Failures only lose the
+        /// cache entry, so they are logged instead of returned.
+        pub fn store(&self, key: &str, artifact: &[u8]) {
+            // the init processes of different containers usually all have pid 1
+            // in their pid namespace, so the pid alone isn't unique
+            let nanos = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .subsec_nanos();
+            let tmp_name = format!("{TMP_PREFIX}{}.{nanos}-{key}", std::process::id());
+            let mut file = match self.open_at(
+                &tmp_name,
+                OFlag::O_WRONLY | OFlag::O_CREAT | OFlag::O_EXCL,
+                Mode::from_bits_truncate(0o600),
+            ) {
+                Ok(file) => file,
+                Err(err) => {
+                    tracing::warn!(?err, key, "failed to store compiled wasm module in cache");
+                    return;
+                }
+            };
+
+            match file
+                .write_all(artifact)
+                .and_then(|_| self.rename_at(&tmp_name, key))
+            {
+                Ok(()) => tracing::debug!(key, "stored compiled wasm module in cache"),
+                Err(err) => {
+                    tracing::warn!(?err, key, "failed to store compiled wasm module in cache");
+                    let _ = self.with_dir(|dir| {
+                        Ok(unistd::unlinkat(
+                            Some(dir),
+                            tmp_name.as_str(),
+                            UnlinkatFlags::NoRemoveDir,
+                        )?)
+                    });
+                }
+            }
+        }
+
+        /// Closes the cache directory. The executors call this before they run
+        /// the module, so processes joining the container can't reach the youki
+        /// root directory through the file descriptors of the init process.
+        pub fn close(&self) {
+            if let Ok(mut dir) = self.dir.lock() {
+                dir.take();
+            }
+        }
+
+        fn with_dir<T>(&self, f: impl FnOnce(RawFd) -> io::Result<T>) -> io::Result<T> {
+            let dir = self
+                .dir
+                .lock()
+                .map_err(|_| io::Error::new(io::ErrorKind::Other, "cache lock poisoned"))?;
+            match dir.as_ref() {
+                Some(dir) => f(dir.as_raw_fd()),
+                None => Err(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    "cache directory is closed",
+                )),
+            }
+        }
+
+        fn open_at(&self, name: &str, flags: OFlag, mode: Mode) -> io::Result<File> {
+            self.with_dir(|dir| {
+                let fd = fcntl::openat(
+                    Some(dir),
+                    name,
+                    flags | OFlag::O_CLOEXEC | OFlag::O_NOFOLLOW,
+                    mode,
+                )?;
+                // SAFETY: openat returned a new file descriptor owned by nobody else
+                Ok(File::from(unsafe { OwnedFd::from_raw_fd(fd) }))
+            })
+        }
+
+        fn rename_at(&self, from: &str, to: &str) -> io::Result<()> {
+            self.with_dir(|dir| Ok(fcntl::renameat(Some(dir), from, Some(dir), to)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn test_prune_by_age() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let dir = cache_dir(root.path());
+        fs::create_dir(&dir)?;
+        fs::write(dir.join("old"), b"old")?;
+        fs::write(dir.join("new"), b"new")?;
+        fs::write(dir.join(format!("{TMP_PREFIX}1-old")), b"tmp")?;
+        let old = SystemTime::now() - Duration::from_secs(3600);
+        for name in ["old".to_owned(), format!("{TMP_PREFIX}1-old")] {
+            File::options()
+                .write(true)
+                .open(dir.join(name))?
+                .set_modified(old)?;
+        }
+
+        let names = |entries: Vec<CacheEntry>| -> Vec<String> {
+            entries.into_iter().map(|entry| entry.name).collect()
+        };
+        assert_eq!(names(entries(root.path())?), vec!["new", "old"]);
+        assert_eq!(
+            names(prune(root.path(), Some(Duration::from_secs(60)))?),
+            vec!["old"]
+        );
+        assert_eq!(names(entries(root.path())?), vec!["new"]);
+        assert!(!dir.join(format!("{TMP_PREFIX}1-old")).exists());
+        assert_eq!(names(prune(root.path(), None)?), vec!["new"]);
+        assert!(entries(root.path())?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_cache_dir() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        assert!(entries(root.path())?.is_empty());
+        assert!(prune(root.path(), None)?.is_empty());
+
+        Ok(())
+    }
+
+    #[cfg(any(feature = "wasm-wasmer", feature = "wasm-wasmtime"))]
+    #[test]
+    fn test_store_and_load() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let cache = ModuleCache::open(root.path())?;
+        let name = key("engine", ("1.0", 42), b"\0asm");
+        assert_eq!(name, key("engine", ("1.0", 42), b"\0asm"));
+        assert_ne!(name, key("engine", ("1.1", 42), b"\0asm"));
+        // the key must not depend on the toolchain, as the cache outlives it
+        assert_eq!(
+            key("engine", "1.0", b"\0asm"),
+            "cd5d4935a48c0672cb06407bb443bc0087aff947c6b864bac886982c73b3027f-engine-1de31b2d46227bf6"
+        );
+
+        assert_eq!(cache.load(&name), None);
+        cache.store(&name, b"artifact");
+        assert_eq!(cache.load(&name), Some(b"artifact".to_vec()));
+        assert_eq!(entries(root.path())?.len(), 1);
+
+        cache.close();
+        assert_eq!(cache.load(&name), None);
+
+        Ok(())
+    }
+}
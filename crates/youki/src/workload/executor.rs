@@ -1,59 +1,279 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
 use libcontainer::oci_spec::runtime::Spec;
 use libcontainer::workload::{Executor, ExecutorError, ExecutorValidationError};
 
+#[cfg(any(feature = "wasm-wasmer", feature = "wasm-wasmtime"))]
+use super::cache::ModuleCache;
+
+/// Annotation selecting the executor of a container: `wasm` runs the workload
+/// on the first wasm executor, `wasm/<name>` on the named one
+pub const HANDLER_ANNOTATION: &str = "run.oci.handler";
+/// Annotation of the wasm images built for runtimes running wasm workloads
+const WASM_VARIANT_ANNOTATION: &str = "module.wasm.image/variant";
+/// The first bytes of a wasm binary module
+const WASM_MAGIC: &[u8; 4] = b"\0asm";
+
+/// Workloads an executor runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkloadKind {
+    /// Executables started with execve
+    Native,
+    /// WebAssembly modules
+    Wasm,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExecutorInfo {
+    pub name: &'static str,
+    pub kind: WorkloadKind,
+}
+
+/// The executors compiled into youki. Wasm workloads which don't name an
+/// executor run on the first wasm executor of the list.
+pub const EXECUTORS: &[ExecutorInfo] = &[
+    #[cfg(feature = "wasm-wasmer")]
+    ExecutorInfo {
+        name: super::wasmer::EXECUTOR_NAME,
+        kind: WorkloadKind::Wasm,
+    },
+    #[cfg(feature = "wasm-wasmedge")]
+    ExecutorInfo {
+        name: super::wasmedge::EXECUTOR_NAME,
+        kind: WorkloadKind::Wasm,
+    },
+    #[cfg(feature = "wasm-wasmtime")]
+    ExecutorInfo {
+        name: super::wasmtime::EXECUTOR_NAME,
+        kind: WorkloadKind::Wasm,
+    },
+    ExecutorInfo {
+        name: "default",
+        kind: WorkloadKind::Native,
+    },
+];
+
+/// Runs the workload on the executor the spec selects
 #[derive(Clone)]
-pub struct DefaultExecutor {}
+pub struct DefaultExecutor {
+    #[cfg(any(feature = "wasm-wasmer", feature = "wasm-wasmtime"))]
+    cache: Option<ModuleCache>,
+}
 
 impl Executor for DefaultExecutor {
     fn exec(&self, spec: &Spec) -> Result<(), ExecutorError> {
-        #[cfg(feature = "wasm-wasmer")]
-        match super::wasmer::get_executor().exec(spec) {
-            Ok(_) => return Ok(()),
-            Err(ExecutorError::CantHandle(_)) => (),
-            Err(err) => return Err(err),
-        }
-        #[cfg(feature = "wasm-wasmedge")]
-        match super::wasmedge::get_executor().exec(spec) {
-            Ok(_) => return Ok(()),
-            Err(ExecutorError::CantHandle(_)) => (),
-            Err(err) => return Err(err),
+        let info = select(spec).map_err(ExecutorError::Other)?;
+        tracing::debug!(executor = info.name, "executing workload");
+        self.executor(info).exec(spec)
+    }
+
+    fn validate(&self, spec: &Spec) -> Result<(), ExecutorValidationError> {
+        let info = select(spec).map_err(ExecutorValidationError::ArgValidationError)?;
+        self.executor(info).validate(spec)
+    }
+}
+
+impl DefaultExecutor {
+    fn executor(&self, info: &ExecutorInfo) -> Box<dyn Executor> {
+        match info.name {
+            #[cfg(feature = "wasm-wasmer")]
+            super::wasmer::EXECUTOR_NAME => {
+                Box::new(super::wasmer::get_executor(self.cache.clone()))
+            }
+            #[cfg(feature = "wasm-wasmedge")]
+            super::wasmedge::EXECUTOR_NAME => Box::new(super::wasmedge::get_executor()),
+            #[cfg(feature = "wasm-wasmtime")]
+            super::wasmtime::EXECUTOR_NAME => {
+                Box::new(super::wasmtime::get_executor(self.cache.clone()))
+            }
+            _ => libcontainer::workload::default::get_executor(),
         }
-        #[cfg(feature = "wasm-wasmtime")]
-        match super::wasmtime::get_executor().exec(spec) {
-            Ok(_) => return Ok(()),
-            Err(ExecutorError::CantHandle(_)) => (),
-            Err(err) => return Err(err),
+    }
+}
+
+/// Returns the executor for the containers of the youki root directory
+/// `root_path`, whose wasm executors cache the compiled modules in it
+pub fn default_executor(root_path: &Path) -> DefaultExecutor {
+    #[cfg(any(feature = "wasm-wasmer", feature = "wasm-wasmtime"))]
+    let cache = match ModuleCache::open(root_path) {
+        Ok(cache) => Some(cache),
+        Err(err) => {
+            tracing::warn!(?err, "wasm module cache is not available");
+            None
         }
+    };
+    #[cfg(not(any(feature = "wasm-wasmer", feature = "wasm-wasmtime")))]
+    let _ = root_path;
 
-        // Leave the default executor as the last option, which executes normal
-        // container workloads.
-        libcontainer::workload::default::get_executor().exec(spec)
+    DefaultExecutor {
+        #[cfg(any(feature = "wasm-wasmer", feature = "wasm-wasmtime"))]
+        cache,
     }
+}
 
-    fn validate(&self, spec: &Spec) -> Result<(), ExecutorValidationError> {
-        #[cfg(feature = "wasm-wasmer")]
-        match super::wasmer::get_executor().validate(spec) {
-            Ok(_) => return Ok(()),
-            Err(ExecutorValidationError::CantHandle(_)) => (),
-            Err(err) => return Err(err),
+/// Picks the executor for the workload of the spec. The handler annotation
+/// takes precedence, otherwise wasm images and entrypoints are recognized by
+/// their annotation and magic bytes. This runs in the init process after it
+/// changed into the working directory, so the entrypoint can be inspected.
+fn select(spec: &Spec) -> Result<&'static ExecutorInfo, String> {
+    let annotations = spec.annotations().as_ref();
+    match annotations.and_then(|a| a.get(HANDLER_ANNOTATION)) {
+        Some(handler) if handler == "wasm" => find(WorkloadKind::Wasm, None),
+        Some(handler) => match handler.strip_prefix("wasm/") {
+            Some(name) => find(WorkloadKind::Wasm, Some(name)),
+            None => find(WorkloadKind::Native, None),
+        },
+        None => {
+            let detected = annotations
+                .and_then(|a| a.get(WASM_VARIANT_ANNOTATION))
+                .map_or(false, |variant| variant == "compat")
+                || is_wasm_entrypoint(spec);
+            if detected {
+                match find(WorkloadKind::Wasm, None) {
+                    Ok(info) => return Ok(info),
+                    // the module is exec'd like any other binary, which works
+                    // with a binfmt_misc handler for wasm
+                    Err(err) => tracing::debug!(err, "running the wasm workload natively"),
+                }
+            }
+            find(WorkloadKind::Native, None)
         }
-        #[cfg(feature = "wasm-wasmedge")]
-        match super::wasmedge::get_executor().validate(spec) {
-            Ok(_) => return Ok(()),
-            Err(ExecutorValidationError::CantHandle(_)) => (),
-            Err(err) => return Err(err),
-        }
-        #[cfg(feature = "wasm-wasmtime")]
-        match super::wasmtime::get_executor().validate(spec) {
-            Ok(_) => return Ok(()),
-            Err(ExecutorValidationError::CantHandle(_)) => (),
-            Err(err) => return Err(err),
+    }
+}
+
+fn find(kind: WorkloadKind, name: Option<&str>) -> Result<&'static ExecutorInfo, String> {
+    EXECUTORS
+        .iter()
+        .find(|info| info.kind == kind && name.map_or(true, |name| info.name == name))
+        .ok_or_else(|| {
+            let available: Vec<&str> = EXECUTORS
+                .iter()
+                .filter(|info| info.kind == kind)
+                .map(|info| info.name)
+                .collect();
+            format!(
+                "{} executor is not available, youki was built with [{}]",
+                name.unwrap_or("a wasm"),
+                available.join(", ")
+            )
+        })
+}
+
+/// Checks whether the entrypoint of the container is a wasm binary module.
+/// Relative entrypoints are resolved against the working directory, like the
+/// wasm executors do.
+fn is_wasm_entrypoint(spec: &Spec) -> bool {
+    let Some(entrypoint) = spec
+        .process()
+        .as_ref()
+        .and_then(|process| process.args().as_ref())
+        .and_then(|args| args.first())
+    else {
+        return false;
+    };
+
+    let mut magic = [0u8; 4];
+    File::open(entrypoint)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|_| &magic == WASM_MAGIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use anyhow::Result;
+    use libcontainer::oci_spec::runtime::{ProcessBuilder, SpecBuilder};
+
+    use super::*;
+
+    fn spec(annotations: &[(&str, &str)], args: &[&str]) -> Result<Spec> {
+        let annotations: HashMap<String, String> = annotations
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        Ok(SpecBuilder::default()
+            .annotations(annotations)
+            .process(
+                ProcessBuilder::default()
+                    .args(args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+                    .build()?,
+            )
+            .build()?)
+    }
+
+    fn first_wasm() -> Option<&'static str> {
+        EXECUTORS
+            .iter()
+            .find(|info| info.kind == WorkloadKind::Wasm)
+            .map(|info| info.name)
+    }
+
+    #[test]
+    fn test_select_native() -> Result<()> {
+        assert_eq!(select(&spec(&[], &["sh"])?).unwrap().name, "default");
+        assert_eq!(
+            select(&spec(&[(HANDLER_ANNOTATION, "krun")], &["sh"])?)
+                .unwrap()
+                .name,
+            "default"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_wasm_annotations() -> Result<()> {
+        let selected = select(&spec(&[(HANDLER_ANNOTATION, "wasm")], &["app.wasm"])?);
+        assert_eq!(selected.ok().map(|info| info.name), first_wasm());
+        // a detected wasm image falls back to the native executor
+        let selected = select(&spec(
+            &[(WASM_VARIANT_ANNOTATION, "compat")],
+            &["app.wasm"],
+        )?);
+        assert_eq!(selected.unwrap().name, first_wasm().unwrap_or("default"));
+
+        assert!(select(&spec(
+            &[(HANDLER_ANNOTATION, "wasm/unknown")],
+            &["app.wasm"]
+        )?)
+        .is_err());
+        for info in EXECUTORS
+            .iter()
+            .filter(|info| info.kind == WorkloadKind::Wasm)
+        {
+            let handler = format!("wasm/{}", info.name);
+            let selected = select(&spec(
+                &[(HANDLER_ANNOTATION, handler.as_str())],
+                &["app.wasm"],
+            )?);
+            assert_eq!(selected, Ok(info));
         }
 
-        libcontainer::workload::default::get_executor().validate(spec)
+        Ok(())
     }
-}
 
-pub fn default_executor() -> DefaultExecutor {
-    DefaultExecutor {}
+    #[test]
+    fn test_select_wasm_magic() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let module = tmp.path().join("app");
+        fs::write(&module, b"\0asm\x01\0\0\0")?;
+        let script = tmp.path().join("script");
+        fs::write(&script, b"#!/bin/sh\n")?;
+
+        assert!(is_wasm_entrypoint(&spec(&[], &[module.to_str().unwrap()])?));
+        assert!(!is_wasm_entrypoint(&spec(
+            &[],
+            &[script.to_str().unwrap()]
+        )?));
+        assert!(!is_wasm_entrypoint(&spec(&[], &["/non/existent"])?));
+
+        let selected = select(&spec(&[], &[module.to_str().unwrap()])?);
+        assert_eq!(selected.unwrap().name, first_wasm().unwrap_or("default"));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,138 @@
+//! Resource limits the wasm executors enforce inside the engine, so a module
+//! exceeding them stops with a trap instead of being killed by the cgroup
+use std::io::Write;
+use std::time::Duration;
+
+use libcontainer::oci_spec::runtime::Spec;
+
+/// Annotation limiting the fuel of a module, which is roughly the number of
+/// wasm instructions it can execute
+pub const FUEL_ANNOTATION: &str = "run.oci.wasm.fuel";
+/// Annotation limiting the run time of a module in milliseconds
+pub const TIMEOUT_ANNOTATION: &str = "run.oci.wasm.timeout";
+
+/// Exit code of a module stopped by a trap. Like with the wasmtime cli, this
+/// is the exit code of a process aborted by SIGABRT.
+pub const TRAP_EXIT_CODE: i32 = 134;
+
+#[cfg(any(feature = "wasm-wasmer", feature = "wasm-wasmedge", test))]
+const WASM_PAGE_SIZE: u64 = 64 * 1024;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WasmLimits {
+    /// Maximum size of a linear memory in bytes, from the memory limit of
+    /// the container
+    pub memory: Option<u64>,
+    pub fuel: Option<u64>,
+    pub timeout: Option<Duration>,
+}
+
+impl WasmLimits {
+    pub fn from_spec(spec: &Spec) -> Result<Self, String> {
+        // a negative limit means unlimited
+        let memory = spec
+            .linux()
+            .as_ref()
+            .and_then(|linux| linux.resources().as_ref())
+            .and_then(|resources| resources.memory().as_ref())
+            .and_then(|memory| memory.limit())
+            .and_then(|limit| u64::try_from(limit).ok())
+            .filter(|limit| *limit > 0);
+
+        Ok(Self {
+            memory,
+            fuel: annotation(spec, FUEL_ANNOTATION)?,
+            timeout: annotation(spec, TIMEOUT_ANNOTATION)?.map(Duration::from_millis),
+        })
+    }
+
+    /// Memory limit in wasm pages of 64KiB
+    #[cfg(any(feature = "wasm-wasmer", feature = "wasm-wasmedge", test))]
+    pub fn memory_pages(&self) -> Option<u32> {
+        self.memory
+            .map(|memory| u32::try_from(memory / WASM_PAGE_SIZE).unwrap_or(u32::MAX))
+    }
+}
+
+fn annotation(spec: &Spec, name: &str) -> Result<Option<u64>, String> {
+    spec.annotations()
+        .as_ref()
+        .and_then(|annotations| annotations.get(name))
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|err| format!("invalid {name} annotation {value:?}: {err}"))
+        })
+        .transpose()
+}
+
+/// Ends the container process with the exit code of the module. The init
+/// process must not return from the executor once the module ran.
+pub fn exit(code: i32) -> ! {
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+    std::process::exit(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use anyhow::Result;
+    use libcontainer::oci_spec::runtime::{
+        LinuxBuilder, LinuxMemoryBuilder, LinuxResourcesBuilder, SpecBuilder,
+    };
+
+    use super::*;
+
+    fn spec(limit: i64, annotations: &[(&str, &str)]) -> Result<Spec> {
+        let annotations: HashMap<String, String> = annotations
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        Ok(SpecBuilder::default()
+            .annotations(annotations)
+            .linux(
+                LinuxBuilder::default()
+                    .resources(
+                        LinuxResourcesBuilder::default()
+                            .memory(LinuxMemoryBuilder::default().limit(limit).build()?)
+                            .build()?,
+                    )
+                    .build()?,
+            )
+            .build()?)
+    }
+
+    #[test]
+    fn test_limits_from_spec() -> Result<()> {
+        let limits = WasmLimits::from_spec(&spec(
+            1 << 20,
+            &[(FUEL_ANNOTATION, "10000"), (TIMEOUT_ANNOTATION, "1500")],
+        )?)
+        .unwrap();
+        assert_eq!(
+            limits,
+            WasmLimits {
+                memory: Some(1 << 20),
+                fuel: Some(10000),
+                timeout: Some(Duration::from_millis(1500)),
+            }
+        );
+        assert_eq!(limits.memory_pages(), Some(16));
+
+        let limits = WasmLimits::from_spec(&spec(-1, &[])?).unwrap();
+        assert_eq!(limits, WasmLimits::default());
+        assert_eq!(limits.memory_pages(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_annotation() -> Result<()> {
+        assert!(WasmLimits::from_spec(&spec(-1, &[(FUEL_ANNOTATION, "-1")])?).is_err());
+        assert!(WasmLimits::from_spec(&spec(-1, &[(TIMEOUT_ANNOTATION, "1s")])?).is_err());
+
+        Ok(())
+    }
+}
@@ -1,4 +1,17 @@
+pub mod cache;
 pub mod executor;
+#[cfg(any(
+    feature = "wasm-wasmer",
+    feature = "wasm-wasmedge",
+    feature = "wasm-wasmtime"
+))]
+mod limits;
+#[cfg(any(
+    feature = "wasm-wasmer",
+    feature = "wasm-wasmedge",
+    feature = "wasm-wasmtime"
+))]
+mod wasi;
 #[cfg(feature = "wasm-wasmedge")]
 mod wasmedge;
 #[cfg(feature = "wasm-wasmer")]
@@ -0,0 +1,187 @@
+//! Filesystem access of the WASI modules run by the wasm executors
+use std::path::{Path, PathBuf};
+
+use libcontainer::oci_spec::runtime::{Mount, Spec};
+
+/// A directory the WASI module gets access to. The executors run in the init
+/// process after the rootfs of the container became its root directory, so
+/// the directories are opened at the same path as the module sees them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preopen {
+    pub guest_path: String,
+    pub host_path: PathBuf,
+    pub readonly: bool,
+}
+
+impl Preopen {
+    /// Preopen in the `GUEST_PATH:HOST_PATH[:readonly]` format
+    #[cfg(any(feature = "wasm-wasmedge", test))]
+    pub fn to_mapping(&self) -> String {
+        let mut mapping = format!("{}:{}", self.guest_path, self.host_path.display());
+        if self.readonly {
+            mapping.push_str(":readonly");
+        }
+        mapping
+    }
+}
+
+/// Returns the directories to preopen for the container process: the root
+/// directory, the destination of every directory mount and the working
+/// directory as `.` for the relative paths.
+pub fn preopens(spec: &Spec) -> Vec<Preopen> {
+    let root_readonly = spec
+        .root()
+        .as_ref()
+        .and_then(|root| root.readonly())
+        .unwrap_or(false);
+    let mut preopens = vec![Preopen {
+        guest_path: "/".to_owned(),
+        host_path: PathBuf::from("/"),
+        readonly: root_readonly,
+    }];
+
+    for mount in spec.mounts().iter().flatten() {
+        let destination = mount.destination();
+        // files can't be preopened, but are still reachable through the
+        // preopen of the root directory
+        if destination == Path::new("/") || !destination.is_dir() {
+            continue;
+        }
+        preopens.push(Preopen {
+            guest_path: destination.to_string_lossy().into_owned(),
+            host_path: destination.to_owned(),
+            // a mount can be writable on a read-only rootfs, e.g. a tmpfs
+            readonly: is_readonly(mount),
+        });
+    }
+
+    let cwd = cwd(spec);
+    if cwd != Path::new("/") {
+        // the working directory has the permissions of the mount it is in
+        let readonly = preopens
+            .iter()
+            .filter(|preopen| cwd.starts_with(&preopen.host_path))
+            .max_by_key(|preopen| preopen.host_path.as_os_str().len())
+            .is_some_and(|preopen| preopen.readonly);
+        preopens.push(Preopen {
+            guest_path: ".".to_owned(),
+            host_path: cwd,
+            readonly,
+        });
+    }
+
+    preopens
+}
+
+/// Working directory of the container process. The init process already
+/// changed into it before running the executor.
+pub fn cwd(spec: &Spec) -> PathBuf {
+    spec.process()
+        .as_ref()
+        .map(|process| process.cwd().to_owned())
+        .filter(|cwd| !cwd.as_os_str().is_empty())
+        .unwrap_or_else(|| PathBuf::from("/"))
+}
+
+// Like for the mount itself, the last of the ro and rw options wins.
+fn is_readonly(mount: &Mount) -> bool {
+    mount
+        .options()
+        .iter()
+        .flatten()
+        .rev()
+        .find(|option| *option == "ro" || *option == "rw")
+        .is_some_and(|option| option == "ro")
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use libcontainer::oci_spec::runtime::{MountBuilder, ProcessBuilder, RootBuilder, SpecBuilder};
+
+    use super::*;
+
+    #[test]
+    fn test_preopens() -> Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        let data = tmp_dir.path().join("data");
+        let config = tmp_dir.path().join("config");
+        std::fs::create_dir(&data)?;
+        std::fs::create_dir(&config)?;
+        std::fs::write(tmp_dir.path().join("file"), "")?;
+
+        let spec = SpecBuilder::default()
+            .root(
+                RootBuilder::default()
+                    .path("rootfs")
+                    .readonly(false)
+                    .build()?,
+            )
+            .mounts(vec![
+                MountBuilder::default()
+                    .destination(&data)
+                    .typ("bind")
+                    .options(vec!["rbind".to_owned(), "rw".to_owned()])
+                    .build()?,
+                MountBuilder::default()
+                    .destination(&config)
+                    .typ("bind")
+                    .options(vec!["rw".to_owned(), "ro".to_owned()])
+                    .build()?,
+                MountBuilder::default()
+                    .destination(tmp_dir.path().join("file"))
+                    .typ("bind")
+                    .build()?,
+            ])
+            .process(ProcessBuilder::default().cwd(config.join("app")).build()?)
+            .build()?;
+
+        let opened = preopens(&spec);
+        let expected = [
+            ("/", PathBuf::from("/"), false),
+            (data.to_str().unwrap(), data.clone(), false),
+            (config.to_str().unwrap(), config.clone(), true),
+            (".", config.join("app"), true),
+        ];
+        assert_eq!(opened.len(), expected.len());
+        for (preopen, (guest_path, host_path, readonly)) in opened.iter().zip(expected) {
+            assert_eq!(preopen.guest_path, guest_path);
+            assert_eq!(preopen.host_path, host_path);
+            assert_eq!(preopen.readonly, readonly);
+        }
+        assert_eq!(
+            opened[2].to_mapping(),
+            format!("{}:{}:readonly", config.display(), config.display())
+        );
+
+        let spec = SpecBuilder::default()
+            .root(
+                RootBuilder::default()
+                    .path("rootfs")
+                    .readonly(true)
+                    .build()?,
+            )
+            .mounts(vec![MountBuilder::default()
+                .destination(&data)
+                .typ("tmpfs")
+                .options(vec!["nosuid".to_owned()])
+                .build()?])
+            .process(ProcessBuilder::default().cwd(data.join("app")).build()?)
+            .build()?;
+
+        let opened = preopens(&spec);
+        let expected = [
+            ("/", PathBuf::from("/"), true),
+            (data.to_str().unwrap(), data.clone(), false),
+            (".", data.join("app"), false),
+        ];
+        assert_eq!(opened.len(), expected.len());
+        for (preopen, (guest_path, host_path, readonly)) in opened.iter().zip(expected) {
+            assert_eq!(preopen.guest_path, guest_path);
+            assert_eq!(preopen.host_path, host_path);
+            assert_eq!(preopen.readonly, readonly);
+        }
+
+        Ok(())
+    }
+}
@@ -2,59 +2,93 @@ use std::collections::HashMap;
 
 use libcontainer::oci_spec::runtime::Spec;
 use libcontainer::workload::{Executor, ExecutorError, ExecutorValidationError};
+use wasmedge_sdk::config::{CommonConfigOptions, ConfigBuilder, RuntimeConfigOptions};
 use wasmedge_sdk::wasi::WasiModule;
 use wasmedge_sdk::{params, Module, Store, Vm};
 
-const EXECUTOR_NAME: &str = "wasmedge";
+use super::limits::{self, WasmLimits, FUEL_ANNOTATION, TIMEOUT_ANNOTATION, TRAP_EXIT_CODE};
+use super::wasi::{self, Preopen};
+
+pub const EXECUTOR_NAME: &str = "wasmedge";
 
 #[derive(Clone)]
 pub struct WasmedgeExecutor {}
 
 impl Executor for WasmedgeExecutor {
     fn exec(&self, spec: &Spec) -> Result<(), ExecutorError> {
-        if !can_handle(spec) {
-            return Err(ExecutorError::CantHandle(EXECUTOR_NAME));
-        }
-
         tracing::debug!("executing workload with wasmedge handler");
 
         // parse wasi parameters
         let args = get_args(spec);
-        let mut cmd = args[0].clone();
-        if let Some(stripped) = args[0].strip_prefix(std::path::MAIN_SEPARATOR) {
-            cmd = stripped.to_string();
-        }
+        // relative module paths are resolved against process.cwd, which is
+        // the working directory of the init process at this point
+        let cmd = &args[0];
         let envs = env_to_wasi(spec);
+        let preopens: Vec<String> = wasi::preopens(spec)
+            .iter()
+            .map(Preopen::to_mapping)
+            .collect();
 
         // initialize the wasi module with the parsed parameters
         let mut wasi_module = WasiModule::create(
             Some(args.iter().map(|s| s as &str).collect()),
             Some(envs.iter().map(|s| s as &str).collect()),
-            None,
+            Some(preopens.iter().map(|s| s as &str).collect()),
         )
         .map_err(|err| ExecutorError::Other(format!("failed to create wasi module: {:?}", err)))?;
 
+        let limits = WasmLimits::from_spec(spec).map_err(ExecutorError::Other)?;
+        let config = limits
+            .memory_pages()
+            .map(|pages| {
+                ConfigBuilder::new(CommonConfigOptions::default())
+                    .with_runtime_config(RuntimeConfigOptions::default().max_memory_pages(pages))
+                    .build()
+            })
+            .transpose()
+            .map_err(|err| ExecutorError::Other(format!("failed to create config: {}", err)))?;
+
         let mut instances = HashMap::new();
         instances.insert(wasi_module.name().to_string(), wasi_module.as_mut());
 
         // create a vm
         let mut vm = Vm::new(
-            Store::new(None, instances)
+            Store::new(config.as_ref(), instances)
                 .map_err(|err| ExecutorError::Other(format!("failed to create store: {}", err)))?,
         );
 
-        let module = Module::from_file(None, cmd).unwrap();
+        let module = Module::from_file(config.as_ref(), cmd).unwrap();
         vm.register_module(Some("main"), module).unwrap();
 
-        vm.run_func(Some("main"), "_start", params!())
-            .map_err(|err| ExecutorError::Execution(err))?;
-
-        Ok(())
+        let result = match limits.timeout {
+            #[cfg(all(target_os = "linux", not(target_env = "musl")))]
+            Some(timeout) => vm.run_func_with_timeout(Some("main"), "_start", params!(), timeout),
+            _ => vm.run_func(Some("main"), "_start", params!()),
+        };
+        // the vm borrows the wasi module, which records the proc_exit code
+        drop(vm);
+
+        match result {
+            Ok(_) => limits::exit(wasi_module.exit_code() as i32),
+            Err(err) => {
+                tracing::error!(err = ?err, "wasm module trapped");
+                limits::exit(TRAP_EXIT_CODE)
+            }
+        }
     }
 
     fn validate(&self, spec: &Spec) -> Result<(), ExecutorValidationError> {
-        if !can_handle(spec) {
-            return Err(ExecutorValidationError::CantHandle(EXECUTOR_NAME));
+        let limits =
+            WasmLimits::from_spec(spec).map_err(ExecutorValidationError::ArgValidationError)?;
+        if limits.fuel.is_some() {
+            return Err(ExecutorValidationError::ArgValidationError(format!(
+                "the {EXECUTOR_NAME} executor doesn't support the {FUEL_ANNOTATION} annotation"
+            )));
+        }
+        if limits.timeout.is_some() && !cfg!(all(target_os = "linux", not(target_env = "musl"))) {
+            return Err(ExecutorValidationError::ArgValidationError(format!(
+                "the {EXECUTOR_NAME} executor doesn't support the {TIMEOUT_ANNOTATION} annotation on this target"
+            )));
         }
 
         Ok(())
@@ -65,20 +99,6 @@ pub fn get_executor() -> WasmedgeExecutor {
     WasmedgeExecutor {}
 }
 
-fn can_handle(spec: &Spec) -> bool {
-    if let Some(annotations) = spec.annotations() {
-        if let Some(handler) = annotations.get("run.oci.handler") {
-            return handler == "wasm";
-        }
-
-        if let Some(variant) = annotations.get("module.wasm.image/variant") {
-            return variant == "compat";
-        }
-    }
-
-    false
-}
-
 fn get_args(spec: &Spec) -> &[String] {
     let p = match spec.process() {
         None => return &[],
@@ -1,19 +1,34 @@
+use std::path::Path;
+use std::ptr::NonNull;
+use std::sync::Arc;
+
 use libcontainer::oci_spec::runtime::Spec;
 use libcontainer::workload::{Executor, ExecutorError, ExecutorValidationError, EMPTY};
-use wasmer::{Instance, Module, Store};
-use wasmer_wasix::WasiEnv;
-
-const EXECUTOR_NAME: &str = "wasmer";
+use wasmer::vm::{
+    MemoryError, MemoryStyle, TableStyle, VMMemory, VMMemoryDefinition, VMTable, VMTableDefinition,
+};
+use wasmer::wasmparser::Operator;
+use wasmer::{
+    BaseTunables, CompilerConfig, Cranelift, Engine, EngineBuilder, Instance, MemoryType, Module,
+    NativeEngineExt, Pages, Store, TableType, Target, Tunables,
+};
+use wasmer_middlewares::metering::{get_remaining_points, MeteringPoints};
+use wasmer_middlewares::Metering;
+use wasmer_wasix::{WasiEnv, WasiError};
+
+use super::cache::{self, ModuleCache};
+use super::limits::{self, WasmLimits, TIMEOUT_ANNOTATION, TRAP_EXIT_CODE};
+use super::wasi;
+
+pub const EXECUTOR_NAME: &str = "wasmer";
 
 #[derive(Clone)]
-pub struct WasmerExecutor {}
+pub struct WasmerExecutor {
+    cache: Option<ModuleCache>,
+}
 
 impl Executor for WasmerExecutor {
     fn exec(&self, spec: &Spec) -> Result<(), ExecutorError> {
-        if !can_handle(spec) {
-            return Err(ExecutorError::CantHandle(EXECUTOR_NAME));
-        }
-
         tracing::debug!("executing workload with wasmer handler");
         let process = spec.process().as_ref();
 
@@ -33,23 +48,53 @@ impl Executor for WasmerExecutor {
             return Err(ExecutorError::InvalidArg);
         }
 
-        if !args[0].ends_with(".wasm") && !args[0].ends_with(".wat") {
-            tracing::error!(
-                "first argument must be a wasm or wat module, but was {}",
-                args[0]
-            );
-            return Err(ExecutorError::InvalidArg);
+        let limits = WasmLimits::from_spec(spec).map_err(ExecutorError::Other)?;
+        let mut compiler = Cranelift::default();
+        if let Some(fuel) = limits.fuel {
+            compiler.push_middleware(Arc::new(Metering::new(fuel, |_: &Operator| 1)));
+        }
+        let mut engine: Engine = EngineBuilder::new(compiler).into();
+        if let Some(pages) = limits.memory_pages() {
+            engine.set_tunables(LimitingTunables {
+                limit: Pages(pages),
+                base: BaseTunables::for_target(&Target::default()),
+            });
         }
 
-        let mut store = Store::default();
-        let module = Module::from_file(&store, &args[0]).map_err(|err| {
-            tracing::error!(err = ?err, file = ?args[0], "could not load wasm module from file");
-            ExecutorError::Other("could not load wasm module from file".to_string())
-        })?;
+        let mut store = Store::new(engine);
+        let module = load_module(&store, &args[0], &limits, self.cache.as_ref())?;
+        if let Some(cache) = &self.cache {
+            cache.close();
+        }
 
-        let mut wasi_env = WasiEnv::builder("youki_wasm_app")
+        let mut builder = WasiEnv::builder("youki_wasm_app")
             .args(args.iter().skip(1))
-            .envs(env)
+            .envs(env);
+        for preopen in wasi::preopens(spec) {
+            builder
+                .add_preopen_build(|p| {
+                    p.directory(&preopen.host_path)
+                        .read(true)
+                        .write(!preopen.readonly)
+                        .create(!preopen.readonly);
+                    if Path::new(&preopen.guest_path) != preopen.host_path {
+                        p.alias(&preopen.guest_path);
+                    }
+                    p
+                })
+                .map_err(|err| {
+                    ExecutorError::Other(format!(
+                        "could not preopen {}: {}",
+                        preopen.guest_path, err
+                    ))
+                })?;
+        }
+        let cwd = wasi::cwd(spec).to_string_lossy().into_owned();
+        let mut wasi_env = builder
+            .setup_fs(Box::new(move |_, fs| {
+                fs.set_current_dir(&cwd);
+                Ok(())
+            }))
             .finalize(&mut store)
             .map_err(|err| ExecutorError::Other(format!("could not create wasi env: {}", err)))?;
 
@@ -71,85 +116,166 @@ impl Executor for WasmerExecutor {
                 "could not retrieve wasm module main function: {err}"
             ))
         })?;
-        start
-            .call(&mut store, &[])
-            .map_err(|err| ExecutorError::Execution(err.into()))?;
-
+        let result = start.call(&mut store, &[]);
         wasi_env.cleanup(&mut store, None);
 
-        Ok(())
+        let err = match result {
+            Ok(_) => limits::exit(0),
+            Err(err) => err,
+        };
+        let err = match err.downcast::<WasiError>() {
+            Ok(WasiError::Exit(code)) => limits::exit(code.raw()),
+            Ok(err) => err.to_string(),
+            Err(err) => err.to_string(),
+        };
+        if limits.fuel.is_some()
+            && matches!(
+                get_remaining_points(&mut store, &instance),
+                MeteringPoints::Exhausted
+            )
+        {
+            tracing::error!("wasm module ran out of fuel");
+        } else {
+            tracing::error!(err = %err, "wasm module trapped");
+        }
+        limits::exit(TRAP_EXIT_CODE)
     }
 
     fn validate(&self, spec: &Spec) -> Result<(), ExecutorValidationError> {
-        if !can_handle(spec) {
-            return Err(ExecutorValidationError::CantHandle(EXECUTOR_NAME));
+        let limits =
+            WasmLimits::from_spec(spec).map_err(ExecutorValidationError::ArgValidationError)?;
+        if limits.timeout.is_some() {
+            return Err(ExecutorValidationError::ArgValidationError(format!(
+                "the {EXECUTOR_NAME} executor doesn't support the {TIMEOUT_ANNOTATION} annotation"
+            )));
         }
 
         Ok(())
     }
 }
 
-pub fn get_executor() -> WasmerExecutor {
-    WasmerExecutor {}
+pub fn get_executor(cache: Option<ModuleCache>) -> WasmerExecutor {
+    WasmerExecutor { cache }
 }
 
-fn can_handle(spec: &Spec) -> bool {
-    if let Some(annotations) = spec.annotations() {
-        if let Some(handler) = annotations.get("run.oci.handler") {
-            return handler == "wasm";
+/// Compiles the module, or loads it from the cache if it was compiled for the
+/// same target and limits before
+fn load_module(
+    store: &Store,
+    path: &str,
+    limits: &WasmLimits,
+    cache: Option<&ModuleCache>,
+) -> Result<Module, ExecutorError> {
+    let bytes = std::fs::read(path).map_err(|err| {
+        tracing::error!(err = ?err, file = ?path, "could not read wasm module from file");
+        ExecutorError::Other("could not load wasm module from file".to_string())
+    })?;
+    // the metering middleware and the memory limit change the compiled code
+    let cached = cache.map(|cache| {
+        let config = (
+            wasmer::VERSION,
+            store.engine().target(),
+            limits.fuel,
+            limits.memory_pages(),
+        );
+        (cache, cache::key(EXECUTOR_NAME, config, &bytes))
+    });
+
+    if let Some((cache, key)) = &cached {
+        if let Some(artifact) = cache.load(key) {
+            // SAFETY: the cache is only written by youki, and the key covers the
+            // wasmer version and the target the artifact was compiled for
+            match unsafe { Module::deserialize(store, artifact) } {
+                Ok(module) => return Ok(module),
+                Err(err) => tracing::warn!(err = ?err, "could not load cached wasm module"),
+            }
         }
+    }
 
-        if let Some(variant) = annotations.get("module.wasm.image/variant") {
-            return variant == "compat";
+    let module = Module::new(store, &bytes).map_err(|err| {
+        tracing::error!(err = ?err, file = ?path, "could not load wasm module from file");
+        ExecutorError::Other("could not load wasm module from file".to_string())
+    })?;
+    if let Some((cache, key)) = &cached {
+        match module.serialize() {
+            Ok(artifact) => cache.store(key, &artifact),
+            Err(err) => tracing::warn!(err = ?err, "could not serialize wasm module"),
         }
     }
 
-    false
+    Ok(module)
 }
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
-
-    use anyhow::{Context, Result};
-    use libcontainer::oci_spec::runtime::SpecBuilder;
-
-    use super::*;
-
-    #[test]
-    fn test_can_handle_oci_handler() -> Result<()> {
-        let mut annotations = HashMap::with_capacity(1);
-        annotations.insert("run.oci.handler".to_owned(), "wasm".to_owned());
-        let spec = SpecBuilder::default()
-            .annotations(annotations)
-            .build()
-            .context("build spec")?;
+/// Tunables capping the size of the linear memories of a module
+struct LimitingTunables<T: Tunables> {
+    limit: Pages,
+    base: T,
+}
 
-        assert!(can_handle(&spec));
+impl<T: Tunables> LimitingTunables<T> {
+    fn adjust_memory(&self, requested: &MemoryType) -> MemoryType {
+        let mut adjusted = *requested;
+        if requested
+            .maximum
+            .map_or(true, |maximum| maximum > self.limit)
+        {
+            adjusted.maximum = Some(self.limit);
+        }
+        adjusted
+    }
 
+    fn validate_memory(&self, ty: &MemoryType) -> Result<(), MemoryError> {
+        if ty.minimum > self.limit {
+            return Err(MemoryError::MinimumMemoryTooLarge {
+                min_requested: ty.minimum,
+                max_allowed: self.limit,
+            });
+        }
         Ok(())
     }
+}
 
-    #[test]
-    fn test_can_handle_compat_wasm_spec() -> Result<()> {
-        let mut annotations = HashMap::with_capacity(1);
-        annotations.insert("module.wasm.image/variant".to_owned(), "compat".to_owned());
-        let spec = SpecBuilder::default()
-            .annotations(annotations)
-            .build()
-            .context("build spec")?;
+impl<T: Tunables> Tunables for LimitingTunables<T> {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory_style(&self.adjust_memory(memory))
+    }
 
-        assert!(can_handle(&spec));
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
 
-        Ok(())
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<VMMemory, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        self.base.create_host_memory(&adjusted, style)
     }
 
-    #[test]
-    fn test_can_handle_no_execute() -> Result<()> {
-        let spec = SpecBuilder::default().build().context("build spec")?;
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<VMMemory, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        self.base
+            .create_vm_memory(&adjusted, style, vm_definition_location)
+    }
 
-        assert!(!can_handle(&spec));
+    fn create_host_table(&self, ty: &TableType, style: &TableStyle) -> Result<VMTable, String> {
+        self.base.create_host_table(ty, style)
+    }
 
-        Ok(())
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<VMTable, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
     }
 }
@@ -1,19 +1,27 @@
 use libcontainer::oci_spec::runtime::Spec;
 use libcontainer::workload::{Executor, ExecutorError, ExecutorValidationError, EMPTY};
-use wasi_common::sync::{add_to_linker, WasiCtxBuilder};
-use wasmtime::{Engine, Linker, Module, Store};
+use wasi_common::sync::{add_to_linker, ambient_authority, Dir, WasiCtxBuilder};
+use wasi_common::{I32Exit, WasiCtx};
+use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
 
-const EXECUTOR_NAME: &str = "wasmtime";
+use super::cache::{self, ModuleCache};
+use super::limits::{self, WasmLimits, TRAP_EXIT_CODE};
+use super::wasi;
+
+pub const EXECUTOR_NAME: &str = "wasmtime";
+
+struct StoreState {
+    wasi: WasiCtx,
+    limits: StoreLimits,
+}
 
 #[derive(Clone)]
-pub struct WasmtimeExecutor {}
+pub struct WasmtimeExecutor {
+    cache: Option<ModuleCache>,
+}
 
 impl Executor for WasmtimeExecutor {
     fn exec(&self, spec: &Spec) -> Result<(), ExecutorError> {
-        if !can_handle(spec) {
-            return Err(ExecutorError::CantHandle(EXECUTOR_NAME));
-        }
-
         tracing::debug!("executing workload with wasmtime handler");
         let process = spec.process().as_ref();
 
@@ -27,19 +35,9 @@ impl Executor for WasmtimeExecutor {
             return Err(ExecutorError::InvalidArg);
         }
 
-        if !args[0].ends_with(".wasm") && !args[0].ends_with(".wat") {
-            tracing::error!(
-                "first argument must be a wasm or wat module, but was {}",
-                args[0]
-            );
-            return Err(ExecutorError::InvalidArg);
-        }
-
-        let mut cmd = args[0].clone();
-        let stripped = args[0].strip_prefix(std::path::MAIN_SEPARATOR);
-        if let Some(cmd_stripped) = stripped {
-            cmd = cmd_stripped.to_string();
-        }
+        // relative module paths are resolved against process.cwd, which is
+        // the working directory of the init process at this point
+        let cmd = &args[0];
 
         let envs: Vec<(String, String)> = process
             .and_then(|p| p.env().as_ref())
@@ -51,19 +49,28 @@ impl Executor for WasmtimeExecutor {
             })
             .collect();
 
-        let engine = Engine::default();
-        let module = Module::from_file(&engine, &cmd).map_err(|err| {
-            tracing::error!(err = ?err, file = ?cmd, "could not load wasm module from file");
-            ExecutorError::Other("could not load wasm module from file".to_string())
+        let limits = WasmLimits::from_spec(spec).map_err(ExecutorError::Other)?;
+        let mut config = Config::new();
+        config
+            .consume_fuel(limits.fuel.is_some())
+            .epoch_interruption(limits.timeout.is_some());
+        let engine = Engine::new(&config).map_err(|err| {
+            tracing::error!(err = ?err, "could not create wasm engine");
+            ExecutorError::Other("could not create wasm engine".to_string())
         })?;
+        let module = load_module(&engine, cmd, self.cache.as_ref())?;
+        if let Some(cache) = &self.cache {
+            cache.close();
+        }
 
         let mut linker = Linker::new(&engine);
-        add_to_linker(&mut linker, |s| s).map_err(|err| {
+        add_to_linker(&mut linker, |s: &mut StoreState| &mut s.wasi).map_err(|err| {
             tracing::error!(err = ?err, "cannot add wasi context to linker");
             ExecutorError::Other("cannot add wasi context to linker".to_string())
         })?;
 
-        let wasi = WasiCtxBuilder::new()
+        let mut builder = WasiCtxBuilder::new();
+        builder
             .inherit_stdio()
             .args(args)
             .map_err(|err| {
@@ -72,10 +79,56 @@ impl Executor for WasmtimeExecutor {
             .envs(&envs)
             .map_err(|err| {
                 ExecutorError::Other(format!("cannot add envs to wasi context: {}", err))
-            })?
-            .build();
+            })?;
+        // wasi-common can't restrict the access to a preopened directory, so
+        // writes to read-only preopens are refused by the read-only mounts.
+        for preopen in wasi::preopens(spec) {
+            let dir =
+                Dir::open_ambient_dir(&preopen.host_path, ambient_authority()).map_err(|err| {
+                    tracing::error!(err = ?err, path = ?preopen.host_path, "cannot open directory");
+                    ExecutorError::Other(format!(
+                        "cannot open directory {}: {}",
+                        preopen.host_path.display(),
+                        err
+                    ))
+                })?;
+            builder
+                .preopened_dir(dir, &preopen.guest_path)
+                .map_err(|err| {
+                    ExecutorError::Other(format!(
+                        "cannot preopen {} in wasi context: {}",
+                        preopen.guest_path, err
+                    ))
+                })?;
+        }
+        let wasi = builder.build();
 
-        let mut store = Store::new(&engine, wasi);
+        let mut store_limits = StoreLimitsBuilder::new().trap_on_grow_failure(true);
+        if let Some(memory) = limits.memory {
+            store_limits = store_limits.memory_size(usize::try_from(memory).unwrap_or(usize::MAX));
+        }
+        let mut store = Store::new(
+            &engine,
+            StoreState {
+                wasi,
+                limits: store_limits.build(),
+            },
+        );
+        store.limiter(|s| &mut s.limits);
+        if let Some(fuel) = limits.fuel {
+            store
+                .set_fuel(fuel)
+                .map_err(|err| ExecutorError::Other(format!("cannot set wasm fuel: {}", err)))?;
+        }
+        if let Some(timeout) = limits.timeout {
+            // the module traps once the epoch is incremented past the deadline
+            store.set_epoch_deadline(1);
+            let engine = engine.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(timeout);
+                engine.increment_epoch();
+            });
+        }
 
         let instance = linker.instantiate(&mut store, &module).map_err(|err| {
             tracing::error!(err = ?err, "wasm module could not be instantiated");
@@ -85,34 +138,73 @@ impl Executor for WasmtimeExecutor {
             ExecutorError::Other("could not retrieve wasm module main function".into())
         })?;
 
-        start
-            .call(&mut store, &[], &mut [])
-            .map_err(|err| ExecutorError::Execution(err.into()))
+        let err = match start.call(&mut store, &[], &mut []) {
+            Ok(()) => limits::exit(0),
+            Err(err) => err,
+        };
+        if let Some(exit) = err.downcast_ref::<I32Exit>() {
+            limits::exit(exit.0);
+        }
+        match err.downcast_ref::<Trap>() {
+            Some(Trap::OutOfFuel) => tracing::error!("wasm module ran out of fuel"),
+            Some(Trap::Interrupt) => tracing::error!("wasm module exceeded its timeout"),
+            _ => tracing::error!(err = ?err, "wasm module trapped"),
+        }
+        limits::exit(TRAP_EXIT_CODE)
     }
 
     fn validate(&self, spec: &Spec) -> Result<(), ExecutorValidationError> {
-        if !can_handle(spec) {
-            return Err(ExecutorValidationError::CantHandle(EXECUTOR_NAME));
-        }
+        WasmLimits::from_spec(spec).map_err(ExecutorValidationError::ArgValidationError)?;
 
         Ok(())
     }
 }
 
-pub fn get_executor() -> WasmtimeExecutor {
-    WasmtimeExecutor {}
+pub fn get_executor(cache: Option<ModuleCache>) -> WasmtimeExecutor {
+    WasmtimeExecutor { cache }
 }
 
-fn can_handle(spec: &Spec) -> bool {
-    if let Some(annotations) = spec.annotations() {
-        if let Some(handler) = annotations.get("run.oci.handler") {
-            return handler == "wasm";
+/// Compiles the module, or loads it from the cache if it was compiled with a
+/// compatible engine before
+fn load_module(
+    engine: &Engine,
+    path: &str,
+    cache: Option<&ModuleCache>,
+) -> Result<Module, ExecutorError> {
+    let bytes = std::fs::read(path).map_err(|err| {
+        tracing::error!(err = ?err, file = ?path, "could not read wasm module from file");
+        ExecutorError::Other("could not load wasm module from file".to_string())
+    })?;
+    let cached = cache.map(|cache| {
+        let key = cache::key(
+            EXECUTOR_NAME,
+            engine.precompile_compatibility_hash(),
+            &bytes,
+        );
+        (cache, key)
+    });
+
+    if let Some((cache, key)) = &cached {
+        if let Some(artifact) = cache.load(key) {
+            // SAFETY: the cache is only written by youki, and wasmtime checks
+            // the artifact was compiled by a compatible engine
+            match unsafe { Module::deserialize(engine, artifact) } {
+                Ok(module) => return Ok(module),
+                Err(err) => tracing::warn!(err = ?err, "could not load cached wasm module"),
+            }
         }
+    }
 
-        if let Some(variant) = annotations.get("module.wasm.image/variant") {
-            return variant == "compat";
+    let module = Module::new(engine, &bytes).map_err(|err| {
+        tracing::error!(err = ?err, file = ?path, "could not load wasm module from file");
+        ExecutorError::Other("could not load wasm module from file".to_string())
+    })?;
+    if let Some((cache, key)) = &cached {
+        match module.serialize() {
+            Ok(artifact) => cache.store(key, &artifact),
+            Err(err) => tracing::warn!(err = ?err, "could not serialize wasm module"),
         }
     }
 
-    false
+    Ok(module)
 }